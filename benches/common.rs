@@ -283,7 +283,7 @@ impl BenchDatabase for TonboS3BenchDataBase {
                 fs_options.clone(),
             )
             .unwrap()
-            .disable_wal();
+            .disable_wal(true);
 
         TonboS3BenchDataBase::new(
             tonbo::DB::new(option, TokioExecutor::current(), CustomerSchema)
@@ -337,7 +337,7 @@ impl BenchDatabase for TonboBenchDataBase {
             fusio::path::Path::from_filesystem_path(path.as_ref()).unwrap(),
             &CustomerSchema,
         )
-        .disable_wal();
+        .disable_wal(true);
 
         let db = tonbo::DB::new(option, TokioExecutor::current(), CustomerSchema)
             .await
@@ -62,7 +62,7 @@ fn single_write(c: &mut Criterion) {
             fusio::path::Path::from_filesystem_path("/tmp/tonbo").unwrap(),
             &KVSchema,
         )
-        .disable_wal();
+        .disable_wal(true);
         let db = runtime
             .block_on(async { DB::new(option, TokioExecutor::current(), KVSchema).await })
             .unwrap();
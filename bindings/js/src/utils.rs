@@ -43,9 +43,9 @@ pub(crate) fn parse_key(desc: &ValueDesc, key: JsValue, primary: bool) -> Result
             true => return Err(format!("{} can not be null", &desc.name).into()),
             false => {
                 return Ok(Value::new(
-                    desc.datatype,
+                    desc.datatype.clone(),
                     desc.name.clone(),
-                    none_value(desc.datatype),
+                    none_value(desc.datatype.clone()),
                     desc.is_nullable,
                 ))
             }
@@ -72,7 +72,7 @@ pub(crate) fn parse_key(desc: &ValueDesc, key: JsValue, primary: bool) -> Result
     };
 
     Ok(Value::new(
-        desc.datatype,
+        desc.datatype.clone(),
         desc.name.clone(),
         value,
         desc.is_nullable,
@@ -52,6 +52,13 @@ impl From<DbError> for PyErr {
             tonbo::DbError::WalWrite(err) => PyIOError::new_err(err.to_string()),
             tonbo::DbError::ExceedsMaxLevel => ExceedsMaxLevelError::new_err("Exceeds max level"),
             tonbo::DbError::Logger(err) => PyIOError::new_err(err.to_string()),
+            tonbo::DbError::InvalidRecord(err) => PyValueError::new_err(err.to_string()),
+            tonbo::DbError::KeyType(err) => PyValueError::new_err(err.to_string()),
+            tonbo::DbError::Arrow(err) => InnerError::new_err(err.to_string()),
+            tonbo::DbError::UnknownColumn(name) => {
+                PyValueError::new_err(format!("unknown column: {name}"))
+            }
+            err @ tonbo::DbError::ChangesTruncated { .. } => PyValueError::new_err(err.to_string()),
         }
     }
 }
@@ -92,9 +92,6 @@ impl DbOption {
                     .unwrap();
             }
         }
-        if !self.use_wal {
-            opt = opt.disable_wal()
-        }
-        opt
+        opt.disable_wal(!self.use_wal)
     }
 }
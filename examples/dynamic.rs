@@ -2,9 +2,9 @@ use std::{fs, sync::Arc};
 
 use fusio::path::Path;
 use tonbo::{
-    dyn_record, dyn_schema,
+    dyn_schema,
     executor::tokio::TokioExecutor,
-    record::{DataType, Value},
+    record::{DataType, DynRecordBuilder, Value},
     DbOption, DB,
 };
 
@@ -13,6 +13,12 @@ async fn main() {
     fs::create_dir_all("./db_path/users").unwrap();
 
     let schema = dyn_schema!(("foo", String, false), ("bar", Int32, true), 0);
+    // columns can be set in any order, the builder places them in schema order
+    let record = DynRecordBuilder::new(&schema)
+        .set("bar", 1_i32)
+        .set("foo", "hello".to_owned())
+        .build()
+        .unwrap();
 
     let options = DbOption::new(
         Path::from_filesystem_path("./db_path/users").unwrap(),
@@ -24,11 +30,7 @@ async fn main() {
 
     {
         let mut txn = db.transaction().await;
-        txn.insert(dyn_record!(
-            ("foo", String, false, "hello".to_owned()),
-            ("bar", Int32, true, 1),
-            0
-        ));
+        txn.insert(record);
 
         txn.commit().await.unwrap();
     }
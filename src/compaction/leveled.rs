@@ -3,6 +3,7 @@ use std::{cmp, collections::Bound, mem, sync::Arc};
 use async_lock::{RwLock, RwLockUpgradableReadGuard};
 use fusio_parquet::writer::AsyncWriter;
 use parquet::arrow::{AsyncArrowWriter, ProjectionMask};
+use tracing::error;
 
 use super::Compactor;
 use crate::{
@@ -15,7 +16,7 @@ use crate::{
     scope::Scope,
     stream::{level::LevelStream, ScanStream},
     version::{edit::VersionEdit, TransactionTs, Version, MAX_LEVEL},
-    DbOption, DbStorage,
+    CompactionMode, DbOption, DbStorage, TombstoneMode,
 };
 
 pub(crate) struct LeveledCompactor<R>
@@ -67,8 +68,8 @@ where
                 )
                 .await?,
             );
-            let (file_id, immutable) = mutable.into_immutable().await?;
-            guard.immutables.push((file_id, immutable));
+            let (file_ids, immutable) = mutable.into_immutable().await?;
+            guard.immutables.push((file_ids, immutable));
         } else if !is_manual {
             return Ok(());
         }
@@ -76,7 +77,7 @@ where
         if (is_manual && !guard.immutables.is_empty())
             || guard.immutables.len() > self.option.immutable_chunk_max_num
         {
-            let recover_wal_ids = guard.recover_wal_ids.take();
+            let (recover_wal_ids, recover_ts) = guard.recover_wal_ids.take().unzip();
             drop(guard);
 
             let guard = self.schema.upgradable_read().await;
@@ -100,7 +101,9 @@ where
                 let mut version_edits = vec![];
                 let mut delete_gens = vec![];
 
-                if Self::is_threshold_exceeded_major(&self.option, &version_ref, 0) {
+                if self.option.compaction_mode != CompactionMode::Manual
+                    && Self::is_threshold_exceeded_major(&self.option, &version_ref, 0)
+                {
                     Self::major_compaction(
                         &version_ref,
                         &self.option,
@@ -117,6 +120,17 @@ where
                 version_edits.push(VersionEdit::LatestTimeStamp {
                     ts: version_ref.increase_ts(),
                 });
+                // the recovered wal is removed with this flush even if some of its changes are
+                // still in memory
+                let flushed_ts = excess
+                    .iter()
+                    .filter_map(|(_, immutable)| immutable.max_ts())
+                    .chain(recover_ts)
+                    .chain(Some(version_ref.flushed_ts()))
+                    .max();
+                if let Some(ts) = flushed_ts {
+                    version_edits.push(VersionEdit::FlushedTimeStamp { ts });
+                }
 
                 self.ctx
                     .version_set
@@ -133,13 +147,46 @@ where
         Ok(())
     }
 
+    /// major compaction until level 0 is within its threshold, the levels below are compacted as
+    /// they exceed theirs
+    pub(crate) async fn compact(&mut self) -> Result<(), CompactionError<R>> {
+        loop {
+            let version_ref = self.ctx.version_set.current().await;
+            if !Self::is_threshold_exceeded_major(&self.option, &version_ref, 0) {
+                break;
+            }
+            let Some(oldest) = version_ref.level_slice[0].first() else {
+                break;
+            };
+            let mut version_edits = vec![];
+            let mut delete_gens = vec![];
+
+            Self::major_compaction(
+                &version_ref,
+                &self.option,
+                &oldest.min,
+                &oldest.max,
+                &mut version_edits,
+                &mut delete_gens,
+                &self.record_schema,
+                &self.ctx,
+            )
+            .await?;
+            if version_edits.is_empty() {
+                break;
+            }
+            self.ctx
+                .version_set
+                .apply_edits(version_edits, Some(delete_gens), false)
+                .await?;
+        }
+        Ok(())
+    }
+
     async fn minor_compaction(
         option: &DbOption,
         recover_wal_ids: Option<Vec<FileId>>,
-        batches: &[(
-            Option<FileId>,
-            Immutable<<R::Schema as RecordSchema>::Columns>,
-        )],
+        batches: &[(Vec<FileId>, Immutable<<R::Schema as RecordSchema>::Columns>)],
         schema: &R::Schema,
         manager: &StoreManager,
     ) -> Result<Option<Scope<<R::Schema as RecordSchema>::Key>>, CompactionError<R>> {
@@ -163,13 +210,13 @@ where
                         .await?,
                 ),
                 schema.arrow_schema().clone(),
-                Some(option.write_parquet_properties.clone()),
+                Some(option.sstable_writer_properties()),
             )?;
 
             if let Some(mut recover_wal_ids) = recover_wal_ids {
                 wal_ids.append(&mut recover_wal_ids);
             }
-            for (file_id, batch) in batches {
+            for (file_ids, batch) in batches {
                 if let (Some(batch_min), Some(batch_max)) = batch.scope() {
                     if matches!(min.as_ref().map(|min| min > batch_min), Some(true) | None) {
                         min = Some(batch_min.clone())
@@ -179,9 +226,7 @@ where
                     }
                 }
                 writer.write(batch.as_record_batch()).await?;
-                if let Some(file_id) = file_id {
-                    wal_ids.push(*file_id);
-                }
+                wal_ids.extend_from_slice(file_ids);
             }
             writer.close().await?;
             return Ok(Some(Scope {
@@ -206,6 +251,7 @@ where
         ctx: &Context<R>,
     ) -> Result<(), CompactionError<R>> {
         let mut level = 0;
+        let first_edit = version_edits.len();
 
         while level < MAX_LEVEL - 2 {
             if !Self::is_threshold_exceeded_major(option, version, level) {
@@ -231,6 +277,7 @@ where
                     streams.push(ScanStream::SsTable {
                         inner: SsTable::open(ctx.parquet_lru.clone(), scope.gen, file)
                             .await?
+                            .with_schema(ctx.arrow_schema().clone())
                             .scan(
                                 (Bound::Unbounded, Bound::Unbounded),
                                 u32::MAX.into(),
@@ -251,8 +298,10 @@ where
                     u32::MAX.into(),
                     None,
                     ProjectionMask::all(),
+                    ctx.arrow_schema().clone(),
                     level_fs.clone(),
                     ctx.parquet_lru.clone(),
+                    None,
                 )
                 .ok_or(CompactionError::EmptyLevel)?;
 
@@ -275,8 +324,10 @@ where
                     u32::MAX.into(),
                     None,
                     ProjectionMask::all(),
+                    ctx.arrow_schema().clone(),
                     level_l_fs.clone(),
                     ctx.parquet_lru.clone(),
+                    None,
                 )
                 .ok_or(CompactionError::EmptyLevel)?;
 
@@ -285,15 +336,34 @@ where
                 });
             }
 
-            Compactor::<R>::build_tables(
+            let drop_tombstones = option.tombstone_mode == TombstoneMode::Eager
+                && Self::is_bottommost(version, level, &meet_scopes_l, &meet_scopes_ll);
+            let outputs = version_edits.len();
+            let expected = Compactor::<R>::build_tables(
                 option,
                 version_edits,
                 level + 1,
                 streams,
                 instance,
                 level_l_fs,
+                drop_tombstones,
             )
             .await?;
+            if let Some(expected) = expected {
+                if let Err(err) = Compactor::<R>::verify_tables(
+                    option,
+                    &version_edits[outputs..],
+                    level + 1,
+                    level_l_fs,
+                    expected,
+                )
+                .await
+                {
+                    // none of the edits are applied, so the inputs stay in the version
+                    Self::remove_outputs(option, &version_edits[first_edit..], &ctx.manager).await;
+                    return Err(err);
+                }
+            }
 
             for scope in meet_scopes_l {
                 version_edits.push(VersionEdit::Remove {
@@ -315,6 +385,51 @@ where
         Ok(())
     }
 
+    /// delete the tables added by `version_edits` of a failed compaction
+    async fn remove_outputs(
+        option: &DbOption,
+        version_edits: &[VersionEdit<<R::Schema as RecordSchema>::Key>],
+        manager: &StoreManager,
+    ) {
+        for edit in version_edits {
+            let VersionEdit::Add { level, scope } = edit else {
+                continue;
+            };
+            let level = *level as usize;
+            let fs = manager.get_fs(option.level_fs_path(level).unwrap_or(&option.base_path));
+            if let Err(err) = fs.remove(&option.table_path(scope.gen, level)).await {
+                error!(
+                    "failed to remove table {} of a failed compaction: {}",
+                    scope.gen, err
+                );
+            }
+        }
+    }
+
+    /// whether no table left in `level` or below after compacting `meet_scopes_l` into
+    /// `meet_scopes_ll` holds keys in their range, so the removals compacted into the next level
+    /// shadow nothing
+    fn is_bottommost(
+        version: &Version<R>,
+        level: usize,
+        meet_scopes_l: &[&Scope<<R::Schema as RecordSchema>::Key>],
+        meet_scopes_ll: &[&Scope<<R::Schema as RecordSchema>::Key>],
+    ) -> bool {
+        let compacted = || meet_scopes_l.iter().chain(meet_scopes_ll);
+        let (Some(min), Some(max)) = (
+            compacted().map(|scope| &scope.min).min(),
+            compacted().map(|scope| &scope.max).max(),
+        ) else {
+            return false;
+        };
+
+        version.level_slice[level..]
+            .iter()
+            .flatten()
+            .filter(|scope| compacted().all(|other| other.gen != scope.gen))
+            .all(|scope| &scope.max < min || max < &scope.min)
+    }
+
     fn next_level_scopes<'a>(
         version: &'a Version<R>,
         min: &mut &'a <R::Schema as RecordSchema>::Key,
@@ -409,25 +524,36 @@ where
     /// The threshold is calculated by multiplying the base threshold with a magnification factor
     /// that increases exponentially with the level number.
     ///
+    /// The threshold is capped by `max_files_per_level` if it is set. Compacting a level removes
+    /// at least one of its tables before the new one is added, so the level stays within the cap.
+    ///
     /// Returns true if the number of tables in the level exceeds the threshold.
     pub(crate) fn is_threshold_exceeded_major(
         option: &DbOption,
         version: &Version<R>,
         level: usize,
     ) -> bool {
+        let threshold =
+            option.major_threshold_with_sst_size * option.level_sst_magnification.pow(level as u32);
+
         Version::<R>::tables_len(version, level)
-            >= (option.major_threshold_with_sst_size
-                * option.level_sst_magnification.pow(level as u32))
+            >= option
+                .max_files_per_level
+                .map_or(threshold, |max_files| threshold.min(max_files))
     }
 }
 
 #[cfg(all(test, feature = "tokio"))]
 pub(crate) mod tests {
-    use std::sync::{atomic::AtomicU32, Arc};
+    use std::{
+        collections::Bound,
+        sync::{atomic::AtomicU32, Arc},
+    };
 
     use flume::bounded;
     use fusio::{path::Path, DynFs};
     use fusio_dispatch::FsOptions;
+    use futures_util::StreamExt;
     use parquet_lru::NoCache;
     use tempfile::TempDir;
 
@@ -435,6 +561,7 @@ pub(crate) mod tests {
         compaction::{
             leveled::LeveledCompactor,
             tests::{build_parquet_table, build_version},
+            CompactionError, Compactor, KeyDigest,
         },
         context::Context,
         executor::tokio::TokioExecutor,
@@ -450,7 +577,7 @@ pub(crate) mod tests {
         trigger::{TriggerFactory, TriggerType},
         version::{cleaner::Cleaner, edit::VersionEdit, set::VersionSet, Version, MAX_LEVEL},
         wal::log::LogType,
-        DbError, DbOption, DB,
+        CompactionMode, DbError, DbOption, TombstoneMode, DB,
     };
 
     async fn build_immutable<R>(
@@ -573,8 +700,8 @@ pub(crate) mod tests {
             &option,
             None,
             &vec![
-                (Some(generate_file_id()), batch_1),
-                (Some(generate_file_id()), batch_2),
+                (vec![generate_file_id()], batch_1),
+                (vec![generate_file_id()], batch_2),
             ],
             &TestSchema,
             &manager,
@@ -636,8 +763,8 @@ pub(crate) mod tests {
             &option,
             None,
             &vec![
-                (Some(generate_file_id()), batch_1),
-                (Some(generate_file_id()), batch_2),
+                (vec![generate_file_id()], batch_1),
+                (vec![generate_file_id()], batch_2),
             ],
             &instance,
             &manager,
@@ -987,4 +1114,366 @@ pub(crate) mod tests {
         }
         dbg!(version);
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_max_files_per_level() {
+        const MAX_FILES: usize = 2;
+
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .max_files_per_level(MAX_FILES);
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let mut compacted = false;
+        for round in 0..24 {
+            for i in 0..4 {
+                db.insert(Test {
+                    vstring: format!("{:04}", round * 4 + i),
+                    vu32: i,
+                    vbool: Some(true),
+                })
+                .await
+                .unwrap();
+            }
+            db.flush().await.unwrap();
+
+            let layout = db.level_layout().await;
+            assert_eq!(layout.len(), MAX_LEVEL);
+            // the last two levels are never compacted further
+            for files in &layout[..MAX_LEVEL - 2] {
+                assert!(*files <= MAX_FILES, "{:?} after round {}", layout, round);
+            }
+            compacted |= layout[1..].iter().any(|files| *files > 0);
+        }
+        // without the cap level 0 would hold up to `major_threshold_with_sst_size` files and
+        // nothing would be compacted into level 1
+        assert!(compacted);
+        for key in 0..24 * 4 {
+            let key = format!("{:04}", key);
+            assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_manual_compaction_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .compaction_mode(CompactionMode::Manual);
+        let threshold = option.major_threshold_with_sst_size;
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let rounds = threshold + 2;
+        for round in 0..rounds {
+            for i in 0..4 {
+                db.insert(Test {
+                    vstring: format!("{:04}", round * 4 + i),
+                    vu32: i as u32,
+                    vbool: Some(true),
+                })
+                .await
+                .unwrap();
+            }
+            db.flush().await.unwrap();
+        }
+        // every flush adds a table to level 0 and nothing is compacted
+        let layout = db.level_layout().await;
+        assert_eq!(layout[0], rounds);
+        assert!(layout[1..].iter().all(|files| *files == 0), "{:?}", layout);
+
+        db.compact().await.unwrap();
+
+        let layout = db.level_layout().await;
+        assert!(layout[0] < threshold, "{:?}", layout);
+        assert!(layout[1] > 0, "{:?}", layout);
+        for key in 0..rounds * 4 {
+            let key = format!("{:04}", key);
+            assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_synchronous_compaction_mode() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .compaction_mode(CompactionMode::Synchronous);
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 0;
+        option.major_threshold_with_sst_size = 2;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 4);
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        // every 4th insert freezes the memtable and returns once it is flushed to level 0, the
+        // third flush finds 2 tables in level 0 and compacts them into level 1
+        for round in 0..3 {
+            for i in 0..4 {
+                db.insert(Test {
+                    vstring: format!("{:04}", round * 4 + i),
+                    vu32: i,
+                    vbool: Some(true),
+                })
+                .await
+                .unwrap();
+            }
+            let layout = db.level_layout().await;
+            if round < 2 {
+                assert_eq!(layout[0], round as usize + 1, "{:?}", layout);
+                assert_eq!(layout[1], 0, "{:?}", layout);
+            } else {
+                assert!(layout[0] < 2, "{:?}", layout);
+                assert!(layout[1] > 0, "{:?}", layout);
+            }
+        }
+        for key in 0..12 {
+            let key = format!("{:04}", key);
+            assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+        }
+    }
+
+    /// removes most of the keys of level 0, compacts them into level 1 and returns the number of
+    /// entries merged by a scan over the removed range
+    async fn removed_range_entries(tombstone_mode: TombstoneMode) -> usize {
+        let temp_dir = TempDir::new().unwrap();
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .compaction_mode(CompactionMode::Manual)
+        .tombstone_mode(tombstone_mode);
+        option.major_threshold_with_sst_size = 2;
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for i in 0..20 {
+            db.insert(Test {
+                vstring: format!("{:04}", i),
+                vu32: i,
+                vbool: Some(true),
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+        for i in 0..16 {
+            db.remove(format!("{:04}", i)).await.unwrap();
+        }
+        db.flush().await.unwrap();
+        db.compact().await.unwrap();
+
+        let layout = db.level_layout().await;
+        assert_eq!(layout[0], 0, "{:?}", layout);
+        assert_eq!(layout[1], 1, "{:?}", layout);
+        for i in 16..20 {
+            let key = format!("{:04}", i);
+            assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+        }
+
+        let (lower, upper) = ("0000".to_string(), "0015".to_string());
+        let changelog = db
+            .scan_changelog(
+                (Bound::Included(&lower), Bound::Included(&upper)),
+                |record| record.is_some(),
+            )
+            .await
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        changelog
+            .iter()
+            .flat_map(|(_, versions)| versions)
+            .inspect(|(_, is_some)| assert!(!is_some))
+            .count()
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_tombstone_mode() {
+        // level 1 is the bottommost level, so eager mode drops the removals with the records they
+        // shadow while lazy mode keeps the removals for reads to merge
+        assert_eq!(removed_range_entries(TombstoneMode::Lazy).await, 16);
+        assert_eq!(removed_range_entries(TombstoneMode::Eager).await, 0);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_compaction() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .compaction_mode(CompactionMode::Manual)
+        .tombstone_mode(TombstoneMode::Eager)
+        .verify_compaction(true);
+        option.major_threshold_with_sst_size = 2;
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for i in 0..20 {
+            db.insert(Test {
+                vstring: format!("{:04}", i),
+                vu32: i,
+                vbool: Some(true),
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+        for i in 0..8 {
+            db.remove(format!("{:04}", i)).await.unwrap();
+        }
+        for i in 8..12 {
+            db.insert(Test {
+                vstring: format!("{:04}", i),
+                vu32: i * 10,
+                vbool: None,
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+        // removals and the versions they shadow are dropped, which verification accounts for
+        db.compact().await.unwrap();
+
+        let layout = db.level_layout().await;
+        assert_eq!(layout[0], 0, "{:?}", layout);
+        assert_eq!(layout[1], 1, "{:?}", layout);
+        for i in 0..20 {
+            let key = format!("{:04}", i);
+            let vu32 = db.get(&key, |entry| entry.get().vu32).await.unwrap();
+            match i {
+                0..8 => assert_eq!(vu32, None),
+                8..12 => assert_eq!(vu32, Some(i * 10)),
+                _ => assert_eq!(vu32, Some(i)),
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn verify_compaction_fault() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .verify_compaction(true);
+        option.major_threshold_with_sst_size = 2;
+        let option = Arc::new(option);
+        let manager = Arc::new(StoreManager::new(option.base_fs.clone(), vec![]).unwrap());
+        manager
+            .base_fs()
+            .create_dir_all(&option.version_log_dir_path())
+            .await
+            .unwrap();
+        manager
+            .base_fs()
+            .create_dir_all(&option.wal_dir_path())
+            .await
+            .unwrap();
+
+        let ((table_gen_1, table_gen_2, table_gen_3, table_gen_4, _), version) =
+            build_version(&option, &manager, &Arc::new(TestSchema)).await;
+        let (_, clean_sender) = Cleaner::new(option.clone(), manager.clone());
+        let version_set = VersionSet::new(clean_sender, option.clone(), manager.clone())
+            .await
+            .unwrap();
+        let ctx = Context::new(
+            manager.clone(),
+            Arc::new(NoCache::default()),
+            version_set,
+            TestSchema.arrow_schema().clone(),
+        );
+
+        let mut version_edits = Vec::new();
+        LeveledCompactor::<Test>::major_compaction(
+            &version,
+            &option,
+            &2.to_string(),
+            &5.to_string(),
+            &mut version_edits,
+            &mut vec![],
+            &TestSchema,
+            &ctx,
+        )
+        .await
+        .unwrap();
+        let VersionEdit::Add { level: 1, scope } = &version_edits[0] else {
+            panic!("expected a table added to level 1, got {:?}", version_edits);
+        };
+
+        let mut expected = KeyDigest::default();
+        for key in 1..=6 {
+            expected.add(&key.to_string());
+        }
+        let fs = manager.base_fs();
+        Compactor::<Test>::verify_tables(&option, &version_edits, 1, fs, expected)
+            .await
+            .unwrap();
+
+        // lose all but one key of the compacted table
+        build_parquet_table::<Test>(
+            &option,
+            scope.gen,
+            vec![(
+                LogType::Full,
+                Test {
+                    vstring: 1.to_string(),
+                    vu32: 0,
+                    vbool: Some(true),
+                },
+                0.into(),
+            )],
+            &Arc::new(TestSchema),
+            1,
+            fs,
+        )
+        .await
+        .unwrap();
+        let err = Compactor::<Test>::verify_tables(&option, &version_edits, 1, fs, expected)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            CompactionError::Verification {
+                level: 1,
+                expected: digest,
+                actual,
+            } if digest == expected && actual.count == 1
+        ));
+
+        LeveledCompactor::<Test>::remove_outputs(&option, &version_edits, &manager).await;
+        let exists = |gen, level| {
+            fusio::path::path_to_local(&option.table_path(gen, level))
+                .unwrap()
+                .exists()
+        };
+        assert!(!exists(scope.gen, 1));
+        for (gen, level) in [
+            (table_gen_1, 0),
+            (table_gen_2, 0),
+            (table_gen_3, 1),
+            (table_gen_4, 1),
+        ] {
+            assert!(exists(gen, level));
+        }
+    }
 }
@@ -1,23 +1,34 @@
 pub(crate) mod leveled;
-use std::{pin::Pin, sync::Arc};
+use std::{
+    collections::hash_map::DefaultHasher,
+    fmt,
+    hash::{Hash, Hasher},
+    ops::Bound,
+    pin::Pin,
+    sync::Arc,
+};
 
+use flume::Sender;
 use fusio::DynFs;
 use fusio_parquet::writer::AsyncWriter;
 use futures_util::StreamExt;
 use leveled::LeveledCompactor;
-use parquet::arrow::AsyncArrowWriter;
+use parquet::arrow::{AsyncArrowWriter, ProjectionMask};
+use parquet_lru::NoCache;
 use thiserror::Error;
 use tokio::sync::oneshot;
 
 use crate::{
     fs::{generate_file_id, FileType},
     inmem::immutable::{ArrowArrays, Builder},
+    ondisk::sstable::SsTable,
     record::{KeyRef, Record, Schema as RecordSchema},
     scope::Scope,
     stream::{merge::MergeStream, ScanStream},
+    timestamp::Timestamp,
     transaction::CommitError,
     version::{edit::VersionEdit, VersionError},
-    DbOption,
+    CompactionMode, DbOption,
 };
 
 pub(crate) enum Compactor<R>
@@ -29,14 +40,103 @@ where
 
 #[derive(Debug)]
 pub enum CompactTask {
-    Freeze,
+    Freeze(Option<oneshot::Sender<()>>),
     Flush(Option<oneshot::Sender<()>>),
+    Compact(Option<oneshot::Sender<()>>),
+}
+
+/// the number and a checksum of the live keys of a compaction, the keys whose newest version is
+/// not a removal
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct KeyDigest {
+    pub count: u64,
+    pub checksum: u64,
+}
+
+impl KeyDigest {
+    fn add(&mut self, key: &impl Hash) {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        self.count += 1;
+        // the sum does not depend on the order the keys are added in
+        self.checksum = self.checksum.wrapping_add(hasher.finish());
+    }
+}
+
+impl fmt::Display for KeyDigest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} keys with checksum {:016x}",
+            self.count, self.checksum
+        )
+    }
+}
+
+/// builds the [`KeyDigest`] of entries ordered by key, with the newest version of a key first
+struct LiveKeys<K> {
+    digest: KeyDigest,
+    last: Option<K>,
+}
+
+impl<K> LiveKeys<K>
+where
+    K: Hash + Eq,
+{
+    fn new() -> Self {
+        Self {
+            digest: KeyDigest::default(),
+            last: None,
+        }
+    }
+
+    fn push(&mut self, key: K, is_live: bool) {
+        if self.last.as_ref() == Some(&key) {
+            return;
+        }
+        if is_live {
+            self.digest.add(&key);
+        }
+        self.last = Some(key);
+    }
+}
+
+/// ask the compaction task to freeze the full mutable memtable. In
+/// [`CompactionMode::Synchronous`] this waits until the memtable is flushed and compacted, so the
+/// lock of the [`DbStorage`](crate::DbStorage) must not be held
+pub(crate) async fn freeze(compaction_tx: &Sender<CompactTask>, mode: CompactionMode) {
+    if mode != CompactionMode::Synchronous {
+        let _ = compaction_tx.try_send(CompactTask::Freeze(None));
+        return;
+    }
+    let (tx, rx) = oneshot::channel();
+    if compaction_tx
+        .send_async(CompactTask::Freeze(Some(tx)))
+        .await
+        .is_ok()
+    {
+        let _ = rx.await;
+    }
 }
 
 impl<R> Compactor<R>
 where
     R: Record,
 {
+    /// run `task` and notify its sender if it succeeds, the sender is dropped on failure
+    pub(crate) async fn run(&mut self, task: CompactTask) -> Result<(), CompactionError<R>> {
+        let (result, option_tx) = match task {
+            CompactTask::Freeze(option_tx) => (self.check_then_compaction(false).await, option_tx),
+            CompactTask::Flush(option_tx) => (self.check_then_compaction(true).await, option_tx),
+            CompactTask::Compact(option_tx) => (self.compact().await, option_tx),
+        };
+        result?;
+        match option_tx {
+            Some(tx) => tx.send(()).map_err(|_| CompactionError::ChannelClose),
+            None => Ok(()),
+        }
+    }
+
     pub(crate) async fn check_then_compaction(
         &mut self,
         is_manual: bool,
@@ -46,6 +146,14 @@ where
         }
     }
 
+    pub(crate) async fn compact(&mut self) -> Result<(), CompactionError<R>> {
+        match self {
+            Compactor::Leveled(leveled) => leveled.compact().await,
+        }
+    }
+
+    /// merge `streams` into tables of `level`. Returns the [`KeyDigest`] of the merged entries if
+    /// [`DbOption::verify_compaction`] is enabled
     async fn build_tables<'scan>(
         option: &DbOption,
         version_edits: &mut Vec<VersionEdit<<R::Schema as RecordSchema>::Key>>,
@@ -53,18 +161,39 @@ where
         streams: Vec<ScanStream<'scan, R>>,
         schema: &R::Schema,
         fs: &Arc<dyn DynFs>,
-    ) -> Result<(), CompactionError<R>> {
-        let mut stream = MergeStream::<R>::from_vec(streams, u32::MAX.into()).await?;
+        drop_tombstones: bool,
+    ) -> Result<Option<KeyDigest>, CompactionError<R>> {
+        let mut stream = MergeStream::<R>::from_vec(streams, u32::MAX.into())
+            .await?
+            .versions_per_key(option.versions_per_key);
 
         // Kould: is the capacity parameter necessary?
         let mut builder =
             <R::Schema as RecordSchema>::Columns::builder(schema.arrow_schema().clone(), 8192);
         let mut min = None;
         let mut max = None;
+        let mut removed = None;
+        let mut live_keys = option.verify_compaction.then(LiveKeys::new);
 
         while let Some(result) = Pin::new(&mut stream).next().await {
             let entry = result?;
             let key = entry.key();
+            if let Some(live_keys) = &mut live_keys {
+                live_keys.push(key.value.clone().to_key(), entry.value().is_some());
+            }
+
+            if drop_tombstones {
+                // the merge stream yields the newest version first, so the versions following a
+                // removal are the ones it shadows
+                let owned = key.value.clone().to_key();
+                if removed.as_ref() == Some(&owned) {
+                    continue;
+                }
+                if entry.value().is_none() {
+                    removed = Some(owned);
+                    continue;
+                }
+            }
 
             if min.is_none() {
                 min = Some(key.value.clone().to_key())
@@ -99,6 +228,51 @@ where
             )
             .await?;
         }
+        Ok(live_keys.map(|live_keys| live_keys.digest))
+    }
+
+    /// read back the tables added by `version_edits` to `level` and check that they hold the
+    /// live keys of the compaction inputs
+    async fn verify_tables(
+        option: &DbOption,
+        version_edits: &[VersionEdit<<R::Schema as RecordSchema>::Key>],
+        level: usize,
+        fs: &Arc<dyn DynFs>,
+        expected: KeyDigest,
+    ) -> Result<(), CompactionError<R>> {
+        let mut live_keys = LiveKeys::new();
+        // the tables are added in key order, a key can only continue into the next table
+        for edit in version_edits {
+            let VersionEdit::Add { scope, .. } = edit else {
+                continue;
+            };
+            let file = fs
+                .open_options(
+                    &option.table_path(scope.gen, level),
+                    FileType::Parquet.open_options(true),
+                )
+                .await?;
+            // bypass the parquet lru, the tables are read once
+            let mut scan = SsTable::<R>::open(Arc::new(NoCache::default()), scope.gen, file)
+                .await?
+                .scan(
+                    (Bound::Unbounded, Bound::Unbounded),
+                    Timestamp::from(u32::MAX),
+                    None,
+                    ProjectionMask::all(),
+                )
+                .await?;
+            while let Some(entry) = scan.next().await.transpose()? {
+                live_keys.push(entry.key().to_key(), entry.get().is_some());
+            }
+        }
+        if live_keys.digest != expected {
+            return Err(CompactionError::Verification {
+                level,
+                expected,
+                actual: live_keys.digest,
+            });
+        }
         Ok(())
     }
 
@@ -141,7 +315,7 @@ where
                 .await?,
             ),
             schema.arrow_schema().clone(),
-            Some(option.write_parquet_properties.clone()),
+            Some(option.sstable_writer_properties()),
         )?;
         writer.write(columns.as_record_batch()).await?;
         writer.close().await?;
@@ -179,6 +353,12 @@ where
     Commit(#[from] CommitError<R>),
     #[error("the level being compacted does not have a table")]
     EmptyLevel,
+    #[error("compaction into level {level} changed the live keys from {expected} to {actual}")]
+    Verification {
+        level: usize,
+        expected: KeyDigest,
+        actual: KeyDigest,
+    },
 }
 
 #[cfg(all(test, feature = "tokio"))]
@@ -4,8 +4,10 @@ use arrow::datatypes::Schema;
 
 use crate::{
     fs::manager::StoreManager,
-    record::Record,
+    record::{Record, Schema as RecordSchema},
+    snapshot::SnapshotRegistry,
     timestamp::Timestamp,
+    verify::Quarantine,
     version::{set::VersionSet, TransactionTs},
     ParquetLru,
 };
@@ -15,6 +17,8 @@ pub(crate) struct Context<R: Record> {
     pub(crate) parquet_lru: ParquetLru,
     pub(crate) version_set: VersionSet<R>,
     pub(crate) arrow_schema: Arc<Schema>,
+    pub(crate) quarantine: Arc<Quarantine<<R::Schema as RecordSchema>::Key>>,
+    pub(crate) snapshots: SnapshotRegistry,
 }
 
 impl<R> Context<R>
@@ -32,6 +36,8 @@ where
             parquet_lru,
             version_set,
             arrow_schema,
+            quarantine: Arc::default(),
+            snapshots: SnapshotRegistry::default(),
         }
     }
 
@@ -51,6 +51,8 @@ where
     A: ArrowArrays,
 {
     data: A,
+    /// shared with the entries scanned from it, so that they stay valid on their own
+    record_batch: Arc<RecordBatch>,
     index: BTreeMap<Ts<<<A::Record as Record>::Schema as Schema>::Key>, u32>,
 }
 
@@ -75,8 +77,13 @@ where
         }
 
         let data = builder.finish(None);
+        let record_batch = Arc::new(data.as_record_batch().clone());
 
-        Self { data, index }
+        Self {
+            data,
+            record_batch,
+            index,
+        }
     }
 }
 
@@ -96,6 +103,11 @@ where
         )
     }
 
+    /// the newest timestamp of the changes held
+    pub(crate) fn max_ts(&self) -> Option<Timestamp> {
+        self.index.keys().map(|key| key.ts).max()
+    }
+
     pub(crate) fn as_record_batch(&self) -> &RecordBatch {
         self.data.as_record_batch()
     }
@@ -124,7 +136,7 @@ where
             .index
             .range::<TsRef<<<A::Record as Record>::Schema as Schema>::Key>, _>((lower, upper));
 
-        ImmutableScan::<A::Record>::new(range, self.data.as_record_batch(), projection_mask)
+        ImmutableScan::<A::Record>::new(range, &self.record_batch, projection_mask)
     }
 
     pub(crate) fn get(
@@ -161,7 +173,7 @@ where
     R: Record,
 {
     range: Range<'iter, Ts<<R::Schema as Schema>::Key>, u32>,
-    record_batch: &'iter RecordBatch,
+    record_batch: &'iter Arc<RecordBatch>,
     projection_mask: ProjectionMask,
 }
 
@@ -171,7 +183,7 @@ where
 {
     fn new(
         range: Range<'iter, Ts<<R::Schema as Schema>::Key>, u32>,
-        record_batch: &'iter RecordBatch,
+        record_batch: &'iter Arc<RecordBatch>,
         projection_mask: ProjectionMask,
     ) -> Self {
         Self {
@@ -197,15 +209,19 @@ where
                 &self.projection_mask,
                 &schema,
             );
-            // TODO: remove cloning record batch
-            RecordBatchEntry::new(self.record_batch.clone(), {
-                // Safety: record_ref self-references the record batch
-                unsafe {
-                    transmute::<OptionRecordRef<R::Ref<'_>>, OptionRecordRef<R::Ref<'static>>>(
-                        record_ref,
-                    )
-                }
-            })
+            RecordBatchEntry::new(
+                self.record_batch.clone(),
+                offset as usize,
+                schema.clone(),
+                {
+                    // Safety: record_ref self-references the record batch
+                    unsafe {
+                        transmute::<OptionRecordRef<R::Ref<'_>>, OptionRecordRef<R::Ref<'static>>>(
+                            record_ref,
+                        )
+                    }
+                },
+            )
         })
     }
 }
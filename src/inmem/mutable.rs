@@ -5,10 +5,10 @@ use crossbeam_skiplist::{
     map::{Entry, Range},
     SkipMap,
 };
-use fusio::DynFs;
+use fusio::{path::Path, DynFs};
 
 use crate::{
-    fs::{generate_file_id, FileId},
+    fs::{generate_file_id, FileId, FileType},
     inmem::immutable::Immutable,
     record::{KeyRef, Record, Schema},
     timestamp::{Timestamp, Ts, TsRef, EPOCH},
@@ -37,6 +37,9 @@ where
 {
     data: SkipMap<Ts<<R::Schema as Schema>::Key>, Option<R>>,
     wal: Option<Mutex<WalFile<R>>>,
+    wal_segment_size: Option<usize>,
+    max_wal_bytes: Option<usize>,
+    wal_dir_path: Path,
     trigger: Arc<dyn FreezeTrigger<R>>,
     schema: Arc<R::Schema>,
 }
@@ -69,6 +72,9 @@ where
         Ok(Self {
             data: Default::default(),
             wal,
+            wal_segment_size: option.wal_segment_size,
+            max_wal_bytes: option.max_wal_bytes,
+            wal_dir_path: option.wal_dir_path(),
             trigger,
             schema,
         })
@@ -115,21 +121,36 @@ where
         let timestamped_key = Ts::new(key, ts);
 
         let record_entry = Log::new(timestamped_key, value, log_ty);
+        let mut wal_exceeded = false;
         if let (Some(_log_ty), Some(wal)) = (log_ty, &self.wal) {
-            wal.lock()
-                .await
+            let mut wal_guard = wal.lock().await;
+            wal_guard
                 .write(&record_entry)
                 .await
                 .map_err(|e| DbError::WalWrite(Box::new(e)))?;
+
+            if matches!(self.wal_segment_size, Some(size) if wal_guard.written() >= size) {
+                let file_id = generate_file_id();
+                let path = self
+                    .wal_dir_path
+                    .child(format!("{}.{}", file_id, FileType::Wal));
+                wal_guard
+                    .rotate(path, file_id)
+                    .await
+                    .map_err(|e| DbError::WalWrite(Box::new(e)))?;
+            }
+            wal_exceeded =
+                matches!(self.max_wal_bytes, Some(max) if wal_guard.total_written() >= max);
         }
 
         let entry = self.data.insert(record_entry.key, record_entry.value);
 
-        Ok(entry
+        let mem_exceeded = entry
             .value()
             .as_ref()
             .map(|v| self.trigger.check_if_exceed(v))
-            .unwrap_or(false))
+            .unwrap_or(false);
+        Ok(mem_exceeded || wal_exceeded)
     }
 
     pub(crate) fn get(
@@ -183,20 +204,18 @@ where
 
     pub(crate) async fn into_immutable(
         self,
-    ) -> Result<
-        (Option<FileId>, Immutable<<R::Schema as Schema>::Columns>),
-        fusio_log::error::LogError,
-    > {
-        let mut file_id = None;
+    ) -> Result<(Vec<FileId>, Immutable<<R::Schema as Schema>::Columns>), fusio_log::error::LogError>
+    {
+        let mut file_ids = Vec::new();
 
         if let Some(wal) = self.wal {
             let mut wal_guard = wal.lock().await;
             wal_guard.flush().await?;
-            file_id = Some(wal_guard.file_id());
+            file_ids = wal_guard.file_ids();
         }
 
         Ok((
-            file_id,
+            file_ids,
             Immutable::new(self.data, self.schema.arrow_schema().clone()),
         ))
     }
@@ -208,6 +227,18 @@ where
         }
         Ok(())
     }
+
+    /// flush the wal and return the ids of its segments, oldest first
+    pub(crate) async fn wal_ids(&self) -> Result<Vec<FileId>, DbError<R>> {
+        match self.wal.as_ref() {
+            Some(wal) => {
+                let mut wal_guard = wal.lock().await;
+                wal_guard.flush().await?;
+                Ok(wal_guard.file_ids())
+            }
+            None => Ok(Vec::new()),
+        }
+    }
 }
 
 impl<R> MutableMemTable<R>
@@ -225,6 +256,7 @@ mod tests {
     use std::{ops::Bound, sync::Arc};
 
     use fusio::{disk::TokioFs, path::Path, DynFs};
+    use fusio_log::Encode;
 
     use super::MutableMemTable;
     use crate::{
@@ -232,7 +264,7 @@ mod tests {
         record::{test::StringSchema, DataType, DynRecord, DynSchema, Record, Value, ValueDesc},
         tests::{Test, TestRef},
         timestamp::Ts,
-        trigger::TriggerFactory,
+        trigger::{TriggerFactory, TriggerType},
         wal::log::LogType,
         DbOption,
     };
@@ -442,4 +474,109 @@ mod tests {
             dbg!(entry.clone().value().as_ref().unwrap());
         }
     }
+
+    #[tokio::test]
+    async fn test_max_wal_bytes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fs = Arc::new(TokioFs) as Arc<dyn DynFs>;
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .max_wal_bytes(512);
+        option.trigger_type = TriggerType::SizeOfMem(64 * 1024);
+        fs.create_dir_all(&option.wal_dir_path()).await.unwrap();
+
+        let trigger = TriggerFactory::create(option.trigger_type);
+        let mem_table =
+            MutableMemTable::<Test>::new(&option, trigger, fs.clone(), Arc::new(TestSchema {}))
+                .await
+                .unwrap();
+
+        let mut mem_size = 0;
+        let mut inserted = 0;
+        for i in 0..1000_u32 {
+            let record = Test {
+                vstring: i.to_string(),
+                vu32: i,
+                vbool: None,
+            };
+            mem_size += record.size() + record.key().size();
+            inserted += 1;
+            if mem_table
+                .insert(LogType::Full, record, i.into())
+                .await
+                .unwrap()
+            {
+                break;
+            }
+        }
+        assert!(inserted < 1000, "wal size never triggered a freeze");
+        assert!(mem_size < 64 * 1024);
+    }
+
+    #[tokio::test]
+    async fn test_dyn_mem_size_trigger() {
+        const BUDGET: usize = 64 * 1024;
+        // strings keep a larger buffer than their contents, which is what the memtable holds on to
+        const CAPACITY: usize = 4096;
+
+        let temp_dir = tempfile::tempdir().unwrap();
+        let schema = DynSchema::new(
+            vec![
+                ValueDesc::new("id".to_string(), DataType::UInt32, false),
+                ValueDesc::new("payload".to_string(), DataType::String, false),
+            ],
+            0,
+        );
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        option.trigger_type = TriggerType::SizeOfMem(BUDGET);
+        let fs = Arc::new(TokioFs) as Arc<dyn DynFs>;
+        fs.create_dir_all(&option.wal_dir_path()).await.unwrap();
+
+        let trigger = TriggerFactory::create(option.trigger_type);
+        let mutable =
+            MutableMemTable::<DynRecord>::new(&option, trigger, fs.clone(), Arc::new(schema))
+                .await
+                .unwrap();
+
+        let mut allocated = 0;
+        for i in 0..1000_u32 {
+            let mut payload = String::with_capacity(CAPACITY);
+            payload.push_str(&"x".repeat(1000));
+            allocated += payload.capacity();
+            let record = DynRecord::new(
+                vec![
+                    Value::new(DataType::UInt32, "id".to_string(), Arc::new(i), false),
+                    Value::new(
+                        DataType::String,
+                        "payload".to_string(),
+                        Arc::new(payload),
+                        false,
+                    ),
+                ],
+                0,
+            );
+            if mutable
+                .insert(LogType::Full, record, 0_u32.into())
+                .await
+                .unwrap()
+            {
+                break;
+            }
+        }
+        assert!(
+            allocated <= BUDGET + CAPACITY,
+            "{} bytes allocated",
+            allocated
+        );
+        assert!(
+            allocated >= BUDGET - 2 * CAPACITY,
+            "{} bytes allocated",
+            allocated
+        );
+    }
 }
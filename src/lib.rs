@@ -121,12 +121,26 @@ pub mod stream;
 pub mod timestamp;
 pub mod transaction;
 mod trigger;
+pub mod verify;
 mod version;
 mod wal;
 
-use std::{collections::HashMap, io, marker::PhantomData, mem, ops::Bound, pin::pin, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, VecDeque},
+    io,
+    marker::PhantomData,
+    mem,
+    ops::Bound,
+    pin::pin,
+    sync::Arc,
+};
 
 pub use arrow;
+use arrow::{
+    array::{ArrayRef, BooleanArray, RecordBatch, UInt32Array},
+    error::ArrowError,
+};
 use async_lock::RwLock;
 use async_stream::stream;
 use compaction::leveled::LeveledCompactor;
@@ -136,8 +150,11 @@ use fs::FileId;
 pub use fusio::{SeqRead, Write};
 pub use fusio_log::{Decode, Encode};
 use futures_core::Stream;
-use futures_util::StreamExt;
-use inmem::{immutable::Immutable, mutable::MutableMemTable};
+use futures_util::{future::Either, StreamExt, TryStreamExt};
+use inmem::{
+    immutable::{ArrowArrays, Builder, Immutable},
+    mutable::MutableMemTable,
+};
 use lockable::LockableHashMap;
 use magic::USER_COLUMN_OFFSET;
 pub use once_cell;
@@ -147,7 +164,10 @@ use parquet::{
     errors::ParquetError,
 };
 use parquet_lru::{DynLruCache, NoCache};
-use record::Record;
+use record::{
+    DynRecord, DynRecordImmutableArrays, DynRecordRef, DynSchema, KeyRef, KeyTypeMismatch, Record,
+    RecordRef, RecordValidationError,
+};
 use thiserror::Error;
 use timestamp::{Timestamp, TsRef};
 use tokio::sync::oneshot;
@@ -155,24 +175,28 @@ pub use tonbo_macros::{KeyAttributes, Record};
 use tracing::error;
 use transaction::{CommitError, Transaction, TransactionEntry};
 use trigger::FreezeTrigger;
+use verify::{RebuildReport, ScanGaps, VerifyReport};
 use wal::log::Log;
 
 pub use crate::option::*;
 use crate::{
-    compaction::{CompactTask, CompactionError, Compactor},
+    compaction::{self, CompactTask, Compactor},
     executor::Executor,
     fs::{manager::StoreManager, parse_file_id, FileType},
     record::Schema,
-    snapshot::Snapshot,
+    snapshot::{Snapshot, SnapshotInfo},
     stream::{
-        mem_projection::MemProjectionStream, merge::MergeStream, package::PackageStream, Entry,
-        ScanStream,
+        mem_projection::MemProjectionStream, merge::MergeStream, package::PackageStream,
+        reverse_key_order, Entry, ScanStream,
     },
     trigger::TriggerFactory,
     version::{cleaner::Cleaner, set::VersionSet, TransactionTs, Version, VersionError},
     wal::{log::LogType, RecoverError, WalFile},
 };
 
+/// the number of keys [`DB::remove_range`] removes per transaction
+const REMOVE_RANGE_CHUNK: usize = 1024;
+
 pub struct DB<R, E>
 where
     R: Record,
@@ -204,6 +228,22 @@ where
         )
         .await
     }
+
+    /// rebuild the manifest of a closed [`DB`] from the SSTables found in the directories of
+    /// `option`, e.g. after the manifest was lost or corrupted. Existing manifest files are
+    /// replaced.
+    ///
+    /// This is a best-effort recovery: tables are placed in the lowest level stored in their
+    /// directory (or the level in their name with [`SstableNaming::Leveled`]), tables that can't
+    /// be read or placed are reported and left untouched. Tables that compaction had replaced but
+    /// not yet deleted are restored as well.
+    pub async fn rebuild_manifest(option: DbOption) -> Result<RebuildReport, DbError<R>> {
+        let manager = Arc::new(StoreManager::new(
+            option.base_fs.clone(),
+            option.level_paths.clone(),
+        )?);
+        Ok(verify::rebuild_manifest(Arc::new(option), manager).await?)
+    }
 }
 
 impl<R, E> DB<R, E>
@@ -283,18 +323,7 @@ where
 
         executor.spawn(async move {
             while let Ok(task) = task_rx.recv_async().await {
-                if let Err(err) = match task {
-                    CompactTask::Freeze => compactor.check_then_compaction(false).await,
-                    CompactTask::Flush(option_tx) => {
-                        let mut result = compactor.check_then_compaction(true).await;
-                        if let Some(tx) = option_tx {
-                            if result.is_ok() {
-                                result = tx.send(()).map_err(|_| CompactionError::ChannelClose);
-                            }
-                        }
-                        result
-                    }
-                } {
+                if let Err(err) = compactor.run(task).await {
                     error!("[Compaction Error]: {}", err)
                 }
             }
@@ -347,29 +376,124 @@ where
         )
     }
 
-    /// insert a single tonbo record
-    pub async fn insert(&self, record: R) -> Result<(), CommitError<R>> {
-        Ok(self.write(record, self.ctx.increase_ts()).await?)
+    /// the snapshots that have not been dropped yet, oldest first. Transactions hold a snapshot
+    /// as well. A live snapshot keeps the SSTables it reads from being removed by compactions, so
+    /// a snapshot with a large [`SnapshotInfo::age`] is often one that was forgotten.
+    pub fn list_snapshots(&self) -> Vec<SnapshotInfo> {
+        self.ctx.snapshots.list(self.ctx.load_ts())
+    }
+
+    /// insert a single tonbo record, returns the timestamp the write was committed at. Later
+    /// reads of the record report it through [`TransactionEntry::ts`], which makes it usable as
+    /// the expected version of a compare-and-set.
+    pub async fn insert(&self, record: R) -> Result<Timestamp, CommitError<R>> {
+        self.validate_record(&record).await?;
+        let ts = self.ctx.increase_ts();
+        self.write(record, ts).await?;
+        Ok(ts)
     }
 
-    /// insert a sequence of data as a single batch
+    /// insert a sequence of data as a single batch, returns the timestamp all the records were
+    /// committed at
+    ///
+    /// every record is validated before anything is written, so an invalid record rejects the
+    /// whole batch
     pub async fn insert_batch(
         &self,
         records: impl ExactSizeIterator<Item = R>,
-    ) -> Result<(), CommitError<R>> {
-        Ok(self.write_batch(records, self.ctx.increase_ts()).await?)
+    ) -> Result<Timestamp, CommitError<R>> {
+        let records = records.collect::<Vec<_>>();
+        for record in records.iter() {
+            self.validate_record(record).await?;
+        }
+        let ts = self.ctx.increase_ts();
+        self.write_batch(records.into_iter(), ts).await?;
+        Ok(ts)
     }
 
     /// delete the record with the primary key as the `key`
     pub async fn remove(&self, key: <R::Schema as Schema>::Key) -> Result<bool, CommitError<R>> {
-        Ok(self
-            .schema
-            .read()
-            .await
+        let schema = self.schema.read().await;
+        let coerced = match schema
+            .record_schema
+            .coerce_key(&key)
+            .map_err(DbError::from)?
+        {
+            Cow::Owned(key) => Some(key),
+            Cow::Borrowed(_) => None,
+        };
+        let key = coerced.unwrap_or(key);
+
+        Ok(schema
             .remove(LogType::Full, key, self.ctx.increase_ts())
             .await?)
     }
 
+    /// delete every record with a primary key in the `range`. The range is walked in key order
+    /// and its keys are removed with key tombstones, in chunks of up to 1024 keys each resolved
+    /// against a new snapshot and committed in its own transaction, only their primary keys are
+    /// read. Returns the number of deleted records, an empty range deletes nothing.
+    ///
+    /// The delete is not atomic: readers may see the range partially removed, an error leaves
+    /// the chunks committed before it removed, and records written to the range concurrently
+    /// are only removed if they are committed before the chunk holding them is read.
+    pub async fn remove_range(
+        &self,
+        range: (
+            Bound<&<R::Schema as Schema>::Key>,
+            Bound<&<R::Schema as Schema>::Key>,
+        ),
+    ) -> Result<usize, CommitError<R>> {
+        let is_empty = match range {
+            (Bound::Included(lower), Bound::Included(upper)) => lower > upper,
+            (Bound::Included(lower) | Bound::Excluded(lower), Bound::Excluded(upper))
+            | (Bound::Excluded(lower), Bound::Included(upper)) => lower >= upper,
+            _ => false,
+        };
+        if is_empty {
+            return Ok(0);
+        }
+
+        let mut deleted = 0;
+        let mut last = None;
+        loop {
+            let lower = match &last {
+                Some(key) => Bound::Excluded(key),
+                None => range.0,
+            };
+            let mut txn = self.transaction().await;
+            let mut keys = Vec::new();
+            let mut entries = 0;
+            let mut next = None;
+            {
+                let mut scan = txn
+                    .scan((lower, range.1))
+                    .projection_with_index(Vec::new())
+                    .limit(REMOVE_RANGE_CHUNK)
+                    .take()
+                    .await?;
+                while let Some(entry) = scan.next().await.transpose()? {
+                    entries += 1;
+                    let key = entry.key().value().clone().to_key();
+                    if entry.value().is_some() {
+                        keys.push(key.clone());
+                    }
+                    next = Some(key);
+                }
+            }
+            last = next;
+            deleted += keys.len();
+            for key in keys {
+                txn.remove(key);
+            }
+            txn.commit().await?;
+
+            if entries < REMOVE_RANGE_CHUNK {
+                return Ok(deleted);
+            }
+        }
+    }
+
     /// trigger compaction manually. This will flush the WAL and trigger compaction
     pub async fn flush(&self) -> Result<(), CommitError<R>> {
         let (tx, rx) = oneshot::channel();
@@ -383,22 +507,70 @@ where
         Ok(())
     }
 
+    /// run major compaction on every level over its threshold and wait for it to finish. This is
+    /// the only way to compact with [`CompactionMode::Manual`], data still in memory is not
+    /// compacted until it is flushed, e.g. by [`DB::flush`]
+    pub async fn compact(&self) -> Result<(), CommitError<R>> {
+        let (tx, rx) = oneshot::channel();
+        let compaction_tx = { self.schema.read().await.compaction_tx.clone() };
+        compaction_tx
+            .send_async(CompactTask::Compact(Some(tx)))
+            .await?;
+
+        rx.await.map_err(|_| CommitError::ChannelClose)?;
+
+        Ok(())
+    }
+
     /// get the record with `key` as the primary key and process it using closure `f`
     pub async fn get<T>(
         &self,
         key: &<R::Schema as Schema>::Key,
-        mut f: impl FnMut(TransactionEntry<'_, R>) -> Option<T>,
+        f: impl FnMut(TransactionEntry<'_, R>) -> Option<T>,
     ) -> Result<Option<T>, CommitError<R>> {
-        Ok(self
-            .schema
-            .read()
+        self.get_with_projection(key, Projection::All, f).await
+    }
+
+    /// get only the column at `column_index` (and the primary key) of the record with `key` as
+    /// the primary key and process it using closure `f`. Other columns are not read from SSTables.
+    /// Fails with [`DbError::UnknownColumn`] if the schema has no column at `column_index`.
+    pub async fn get_column<T>(
+        &self,
+        key: &<R::Schema as Schema>::Key,
+        column_index: usize,
+        f: impl FnMut(TransactionEntry<'_, R>) -> Option<T>,
+    ) -> Result<Option<T>, CommitError<R>> {
+        let name = self
+            .ctx
+            .arrow_schema()
+            .fields()
+            .get(column_index + USER_COLUMN_OFFSET)
+            .ok_or_else(|| DbError::UnknownColumn(format!("#{column_index}")))?
+            .name();
+
+        self.get_with_projection(key, Projection::Parts(vec![name]), f)
             .await
+    }
+
+    async fn get_with_projection<T>(
+        &self,
+        key: &<R::Schema as Schema>::Key,
+        projection: Projection<'_>,
+        mut f: impl FnMut(TransactionEntry<'_, R>) -> Option<T>,
+    ) -> Result<Option<T>, CommitError<R>> {
+        let schema = self.schema.read().await;
+        let key = schema
+            .record_schema
+            .coerce_key(key)
+            .map_err(DbError::from)?;
+
+        Ok(schema
             .get(
                 &self.ctx,
                 &*self.ctx.version_set.current().await,
-                key,
+                &key,
                 self.ctx.load_ts(),
-                Projection::All,
+                projection,
             )
             .await?
             .and_then(|entry| {
@@ -437,11 +609,309 @@ where
         }
     }
 
+    /// scan every version of the records with primary keys in the `range` and group them by key
+    /// into a changelog. Each item holds the key and the timestamp of each of its versions along
+    /// with `f` applied to it, oldest first. `f` receives `None` for a removal.
+    ///
+    /// Only the versions of one key are kept in memory at a time.
+    pub async fn scan_changelog<'scan, T: 'scan>(
+        &'scan self,
+        range: (
+            Bound<&'scan <R::Schema as Schema>::Key>,
+            Bound<&'scan <R::Schema as Schema>::Key>,
+        ),
+        mut f: impl FnMut(Option<R::Ref<'_>>) -> T + 'scan,
+    ) -> impl Stream<Item = Result<ChangelogEntry<R, T>, CommitError<R>>> + 'scan {
+        stream! {
+            let schema = self.schema.read().await;
+            let current = self.ctx.version_set.current().await;
+            let mut scan = Scan::new(
+                &schema,
+                range,
+                self.ctx.load_ts(),
+                &*current,
+                Box::new(|_| None),
+                self.ctx.clone(),
+            ).versions(usize::MAX).take().await?;
+
+            let mut group: Option<ChangelogEntry<R, T>> = None;
+            while let Some(entry) = scan.next().await {
+                let entry = entry?;
+                let key = entry.key();
+                let value = f(entry.value());
+                match &mut group {
+                    Some((group_key, versions)) if group_key.as_key_ref() == key.value => {
+                        versions.push((key.ts, value));
+                    }
+                    _ => {
+                        let next = (key.value.to_key(), vec![(key.ts, value)]);
+                        if let Some((key, mut versions)) = group.replace(next) {
+                            // the merge stream yields the newest version first
+                            versions.reverse();
+                            yield Ok((key, versions));
+                        }
+                    }
+                }
+            }
+            if let Some((key, mut versions)) = group {
+                versions.reverse();
+                yield Ok((key, versions));
+            }
+        }
+    }
+
+    /// the changes committed after `ts` in the order they were written to the wal, the changes
+    /// of one transaction by key. Each item holds the timestamp, the key and `f` applied to the
+    /// record, `f` receives `None` for a removal. Timestamps are kept when the database is
+    /// reopened, so a follower resumes by passing the timestamp of the last change it applied.
+    ///
+    /// Only the wal that hasn't been flushed into SSTables yet is retained, a `ts` older than it
+    /// fails with [`DbError::ChangesTruncated`] and the follower has to start over from a copy of
+    /// the database. Without a wal no change is retained. Transactions that are still being
+    /// committed are left to the next call.
+    pub async fn changes_since<'scan, T: 'scan>(
+        &'scan self,
+        ts: Timestamp,
+        mut f: impl FnMut(Option<R::Ref<'_>>) -> T + 'scan,
+    ) -> impl Stream<Item = Result<ChangeEvent<R, T>, CommitError<R>>> + 'scan {
+        stream! {
+            let schema = self.schema.read().await;
+            let retained = if schema.option.use_wal {
+                self.ctx.version_set.current().await.flushed_ts()
+            } else {
+                self.ctx.load_ts()
+            };
+            if ts < retained {
+                yield Err(DbError::ChangesTruncated {
+                    requested: ts,
+                    retained,
+                }
+                .into());
+                return;
+            }
+
+            // segments are listed from the oldest memtable to the mutable one
+            let mut wal_ids = Vec::new();
+            if let Some((recover_wal_ids, _)) = &schema.recover_wal_ids {
+                wal_ids.extend_from_slice(recover_wal_ids);
+            }
+            for (file_ids, _) in schema.immutables.iter() {
+                wal_ids.extend_from_slice(file_ids);
+            }
+            wal_ids.append(&mut schema.mutable.wal_ids().await?);
+
+            let mut transaction_map = HashMap::new();
+            for wal_id in wal_ids {
+                let mut logs = pin!(
+                    WalFile::<R>::recover(
+                        schema.option.base_fs.clone(),
+                        schema.option.wal_path(wal_id),
+                    )
+                    .await
+                );
+                while let Some(batch) = logs.next().await {
+                    for log in batch.map_err(DbError::from)? {
+                        let Log {
+                            key,
+                            value,
+                            log_type,
+                        } = log;
+                        if key.ts <= ts {
+                            continue;
+                        }
+                        let changes = match log_type.unwrap() {
+                            LogType::Full => vec![(key.value, value)],
+                            LogType::First => {
+                                transaction_map.insert(key.ts, vec![(key.value, value)]);
+                                continue;
+                            }
+                            LogType::Middle => {
+                                transaction_map
+                                    .get_mut(&key.ts)
+                                    .unwrap()
+                                    .push((key.value, value));
+                                continue;
+                            }
+                            LogType::Last => {
+                                let mut changes = transaction_map.remove(&key.ts).unwrap();
+                                changes.push((key.value, value));
+                                changes
+                            }
+                        };
+                        for (key_value, value) in changes {
+                            let change = f(value.as_ref().map(R::as_record_ref));
+                            yield Ok((key.ts, key_value, change));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// scan records with primary keys in the `range` and pass each of them to `f` as a
+    /// [`RecordRef`](record::RecordRef) borrowing the underlying arrow arrays, without building
+    /// an owned record per row. The reference is only valid during the call. Removed records are
+    /// skipped.
+    pub async fn scan_ref(
+        &self,
+        range: (
+            Bound<&<R::Schema as Schema>::Key>,
+            Bound<&<R::Schema as Schema>::Key>,
+        ),
+        mut f: impl FnMut(R::Ref<'_>),
+    ) -> Result<(), CommitError<R>> {
+        let schema = self.schema.read().await;
+        let current = self.ctx.version_set.current().await;
+        let mut scan = Scan::new(
+            &schema,
+            range,
+            self.ctx.load_ts(),
+            &*current,
+            Box::new(|_| None),
+            self.ctx.clone(),
+        )
+        .take()
+        .await?;
+
+        while let Some(entry) = scan.next().await.transpose()? {
+            if let Some(record) = entry.value() {
+                f(record);
+            }
+        }
+        Ok(())
+    }
+
+    /// the number of records with primary keys in the `range`. Only the primary keys are read
+    /// from SSTables, removed records are not counted and each key is counted once.
+    pub async fn scan_count(
+        &self,
+        range: (
+            Bound<&<R::Schema as Schema>::Key>,
+            Bound<&<R::Schema as Schema>::Key>,
+        ),
+    ) -> Result<usize, CommitError<R>> {
+        let schema = self.schema.read().await;
+        let current = self.ctx.version_set.current().await;
+        let mut scan = Scan::new(
+            &schema,
+            range,
+            self.ctx.load_ts(),
+            &*current,
+            Box::new(|_| None),
+            self.ctx.clone(),
+        )
+        .projection_with_index(Vec::new())
+        .take()
+        .await?;
+
+        let mut count = 0;
+        while let Some(entry) = scan.next().await.transpose()? {
+            if entry.value().is_some() {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// scan records with primary keys in the `range` and map each of them to a `T` using `f`.
+    /// Removed records are skipped. Errors returned by `f` are yielded by the stream as they are,
+    /// errors of the scan itself are converted into `E`.
+    pub async fn scan_as<'scan, T: 'scan, E: From<CommitError<R>> + 'scan>(
+        &'scan self,
+        range: (
+            Bound<&'scan <R::Schema as Schema>::Key>,
+            Bound<&'scan <R::Schema as Schema>::Key>,
+        ),
+        mut f: impl FnMut(R::Ref<'_>) -> Result<T, E> + 'scan,
+    ) -> impl Stream<Item = Result<T, E>> + 'scan {
+        stream! {
+            let schema = self.schema.read().await;
+            let current = self.ctx.version_set.current().await;
+            let mut scan = Scan::new(
+                &schema,
+                range,
+                self.ctx.load_ts(),
+                &*current,
+                Box::new(|_| None),
+                self.ctx.clone(),
+            )
+            .take()
+            .await
+            .map_err(|err| E::from(CommitError::from(err)))?;
+
+            while let Some(entry) = scan.next().await {
+                let entry = entry.map_err(|err| E::from(CommitError::from(err)))?;
+                if let Some(record) = entry.value() {
+                    yield f(record);
+                }
+            }
+        }
+    }
+
+    async fn validate_record(&self, record: &R) -> Result<(), DbError<R>> {
+        self.schema
+            .read()
+            .await
+            .record_schema
+            .validate_record(record)?;
+        Ok(())
+    }
+
+    /// split the key space into at most `n` contiguous sub-ranges whose union is the whole table,
+    /// so that each of them can be scanned by a different worker. Split points are taken from the
+    /// key ranges of the SSTables, so the sub-ranges are roughly balanced by on-disk data.
+    ///
+    /// **Note**: data not yet flushed to SSTables doesn't affect the split points, but is still
+    /// covered by the returned ranges.
+    pub async fn split_ranges(
+        &self,
+        n: usize,
+    ) -> Vec<(
+        Bound<<R::Schema as Schema>::Key>,
+        Bound<<R::Schema as Schema>::Key>,
+    )> {
+        let version = self.ctx.version_set.current().await;
+        let mut boundaries = version
+            .level_slice
+            .iter()
+            .flatten()
+            .flat_map(|scope| [scope.min.clone(), scope.max.clone()])
+            .collect::<Vec<_>>();
+        boundaries.sort();
+        boundaries.dedup();
+
+        let n = n.clamp(1, boundaries.len().max(1));
+        let mut ranges = Vec::with_capacity(n);
+        let mut lower = Bound::Unbounded;
+        for i in 1..n {
+            let split = boundaries[i * boundaries.len() / n].clone();
+            ranges.push((lower, Bound::Excluded(split.clone())));
+            lower = Bound::Included(split);
+        }
+        ranges.push((lower, Bound::Unbounded));
+
+        ranges
+    }
+
+    /// number of SSTables in each level, from level 0 to the last level
+    pub async fn level_layout(&self) -> Vec<usize> {
+        self.ctx
+            .version_set
+            .current()
+            .await
+            .level_slice
+            .iter()
+            .map(Vec::len)
+            .collect()
+    }
+
     pub(crate) async fn write(&self, record: R, ts: Timestamp) -> Result<(), DbError<R>> {
         let schema = self.schema.read().await;
 
         if schema.write(LogType::Full, record, ts).await? {
-            let _ = schema.compaction_tx.try_send(CompactTask::Freeze);
+            let (compaction_tx, mode) = schema.compaction_trigger();
+            drop(schema);
+            compaction::freeze(&compaction_tx, mode).await;
         }
 
         Ok(())
@@ -470,13 +940,31 @@ where
                 schema.write(LogType::Full, first, ts).await?
             };
             if is_excess {
-                let _ = schema.compaction_tx.try_send(CompactTask::Freeze);
+                let (compaction_tx, mode) = schema.compaction_trigger();
+                drop(schema);
+                compaction::freeze(&compaction_tx, mode).await;
             }
         };
 
         Ok(())
     }
 
+    /// check the manifest against the SSTables it references without modifying anything.
+    ///
+    /// Reports SSTables whose recorded key range differs from their contents, overlapping
+    /// SSTables in sorted levels, SSTables that can't be read and SSTables quarantined by scans.
+    pub async fn verify(&self) -> VerifyReport<<R::Schema as Schema>::Key> {
+        let mut report = self
+            .ctx
+            .version_set
+            .current()
+            .await
+            .verify(self.ctx.storage_manager())
+            .await;
+        report.issues.extend(self.ctx.quarantine.issues());
+        report
+    }
+
     /// flush WAL to the stable storage. If WAL is disabled, this method will do nothing.
     ///
     /// There is no guarantee that the data will be flushed to WAL because of the buffer. So it is
@@ -500,17 +988,157 @@ where
     }
 }
 
+impl<E> DB<DynRecord, E>
+where
+    E: Executor + Send + Sync + 'static,
+{
+    /// insert `record` unless the stored record with the same key has the same values, see
+    /// [`DynRecord::diff`]. Returns whether the record was written.
+    ///
+    /// The stored record is read in the transaction that writes `record`, so the insert fails
+    /// with [`CommitError::WriteConflict`] if the key is written concurrently.
+    pub async fn insert_if_changed(
+        &self,
+        record: DynRecord,
+    ) -> Result<bool, CommitError<DynRecord>> {
+        self.validate_record(&record).await?;
+
+        let mut txn = self.transaction().await;
+        let key = record.key();
+        let changed = match txn.get(&key, Projection::All).await? {
+            Some(stored) => !record.as_record_ref().diff(&stored.get()).is_empty(),
+            None => true,
+        };
+        if changed {
+            txn.insert(record);
+            txn.commit().await?;
+        }
+        Ok(changed)
+    }
+
+    /// insert every row of `batch` as a record at the same timestamp, see [`DB::insert_batch`].
+    /// The columns of `batch` must have the names and datatypes of the columns of the table, in
+    /// the same order, and only nullable columns may hold nulls.
+    pub async fn insert_record_batch(
+        &self,
+        batch: &RecordBatch,
+    ) -> Result<Timestamp, CommitError<DynRecord>> {
+        let schema = self
+            .schema
+            .read()
+            .await
+            .record_schema
+            .arrow_schema()
+            .clone();
+        let fields = &schema.fields()[USER_COLUMN_OFFSET..];
+        if batch.num_columns() != fields.len() {
+            return Err(DbError::from(RecordValidationError::ColumnCount {
+                expected: fields.len(),
+                actual: batch.num_columns(),
+            })
+            .into());
+        }
+        for (index, (expected, actual)) in fields.iter().zip(batch.schema().fields()).enumerate() {
+            if expected.name() != actual.name() || expected.data_type() != actual.data_type() {
+                return Err(DbError::from(RecordValidationError::BatchColumnMismatch {
+                    index,
+                    expected: expected.clone(),
+                    actual: actual.clone(),
+                })
+                .into());
+            }
+            if !expected.is_nullable() && batch.column(index).null_count() > 0 {
+                return Err(DbError::from(RecordValidationError::NullValue {
+                    column: expected.name().clone(),
+                })
+                .into());
+            }
+        }
+
+        // add the `_null` and `_ts` columns to read the rows the way rows of SSTables are read
+        let num_rows = batch.num_rows();
+        let mut columns: Vec<ArrayRef> = vec![
+            Arc::new(BooleanArray::from(vec![false; num_rows])),
+            Arc::new(UInt32Array::from(vec![0; num_rows])),
+        ];
+        columns.extend(batch.columns().iter().cloned());
+        let batch = RecordBatch::try_new(schema.clone(), columns).map_err(DbError::from)?;
+        let projection_mask = ProjectionMask::all();
+        let records = (0..num_rows)
+            .map(|offset| {
+                let record =
+                    DynRecordRef::from_record_batch(&batch, offset, &projection_mask, &schema);
+                DynRecord::from_record_ref(record.get().expect("rows of the batch are not removed"))
+            })
+            .collect::<Vec<_>>();
+
+        self.insert_batch(records.into_iter()).await
+    }
+
+    /// scan records with primary keys in the `range` into arrow record batches of up to
+    /// `batch_size` rows, the inverse of [`DB::insert_record_batch`]. The batches hold the
+    /// columns of the table in order, without `_null` and `_ts`. Removed records are skipped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `batch_size` is 0.
+    pub async fn scan_to_batches<'scan>(
+        &'scan self,
+        range: (
+            Bound<&'scan <DynSchema as Schema>::Key>,
+            Bound<&'scan <DynSchema as Schema>::Key>,
+        ),
+        batch_size: usize,
+    ) -> impl Stream<Item = Result<RecordBatch, CommitError<DynRecord>>> + 'scan {
+        assert!(batch_size > 0, "batch size must be greater than 0");
+        stream! {
+            let schema = self.schema.read().await;
+            let current = self.ctx.version_set.current().await;
+            let arrow_schema = schema.record_schema.arrow_schema().clone();
+            let user_columns =
+                (USER_COLUMN_OFFSET..arrow_schema.fields().len()).collect::<Vec<_>>();
+            let mut scan = Scan::new(
+                &schema,
+                range,
+                self.ctx.load_ts(),
+                &*current,
+                Box::new(|_| None),
+                self.ctx.clone(),
+            ).take().await?;
+
+            let mut builder = DynRecordImmutableArrays::builder(arrow_schema, batch_size);
+            let mut rows = 0;
+            while let Some(entry) = scan.next().await {
+                let entry = entry?;
+                if let Some(record) = entry.value() {
+                    builder.push(entry.key(), Some(record));
+                    rows += 1;
+                }
+                if rows == batch_size {
+                    yield Ok(builder.finish(Some(&user_columns)).as_record_batch().clone());
+                    rows = 0;
+                }
+            }
+            if rows > 0 {
+                yield Ok(builder.finish(Some(&user_columns)).as_record_batch().clone());
+            }
+        }
+    }
+}
+
 pub(crate) struct DbStorage<R>
 where
     R: Record,
 {
     pub mutable: MutableMemTable<R>,
-    pub immutables: Vec<(Option<FileId>, Immutable<<R::Schema as Schema>::Columns>)>,
+    pub immutables: Vec<(Vec<FileId>, Immutable<<R::Schema as Schema>::Columns>)>,
     compaction_tx: Sender<CompactTask>,
-    recover_wal_ids: Option<Vec<FileId>>,
+    /// the wal files replayed on open and the timestamp recovered from them
+    recover_wal_ids: Option<(Vec<FileId>, Timestamp)>,
     trigger: Arc<dyn FreezeTrigger<R>>,
     record_schema: Arc<R::Schema>,
     option: Arc<DbOption>,
+    projection: Option<Vec<usize>>,
 }
 
 impl<R> DbStorage<R>
@@ -543,6 +1171,25 @@ where
             wal_metas
         };
 
+        let projection = option
+            .projection
+            .as_ref()
+            .map(|columns| {
+                let mut projection = vec![0, 1, record_schema.primary_key_index()];
+                for name in columns {
+                    projection.push(
+                        record_schema
+                            .column_index(name)
+                            .ok_or_else(|| DbError::UnknownColumn(name.clone()))?,
+                    );
+                }
+                projection.sort_unstable();
+                projection.dedup();
+
+                Ok::<_, DbError<R>>(projection)
+            })
+            .transpose()?;
+
         let trigger = TriggerFactory::create(option.trigger_type);
         let mut schema = DbStorage {
             mutable: MutableMemTable::new(
@@ -558,6 +1205,7 @@ where
             trigger,
             record_schema,
             option: option.clone(),
+            projection,
         };
 
         for wal_meta in wal_metas {
@@ -583,9 +1231,8 @@ where
 
                     let is_excess = match log_type.unwrap() {
                         LogType::Full => {
-                            schema
-                                .recover_append(key, version_set.increase_ts(), value)
-                                .await?
+                            version_set.advance_ts(ts);
+                            schema.recover_append(key, ts, value).await?
                         }
                         LogType::First => {
                             transaction_map.insert(ts, vec![(key, value)]);
@@ -600,7 +1247,7 @@ where
                             let mut records = transaction_map.remove(&ts).unwrap();
                             records.push((key, value));
 
-                            let ts = version_set.increase_ts();
+                            version_set.advance_ts(ts);
                             for (key, value_option) in records {
                                 is_excess = schema.recover_append(key, ts, value_option).await?;
                             }
@@ -608,12 +1255,13 @@ where
                         }
                     };
                     if is_excess {
-                        let _ = schema.compaction_tx.try_send(CompactTask::Freeze);
+                        // the compaction task doesn't run yet, so recovery never waits for it
+                        let _ = schema.compaction_tx.try_send(CompactTask::Freeze(None));
                     }
                 }
             }
         }
-        schema.recover_wal_ids = Some(wal_ids);
+        schema.recover_wal_ids = Some((wal_ids, version_set.load_ts()));
 
         Ok(schema)
     }
@@ -652,7 +1300,13 @@ where
         let schema = ctx.arrow_schema();
 
         let projection = match projection {
-            Projection::All => ProjectionMask::all(),
+            Projection::All => match &self.projection {
+                Some(projection) => ProjectionMask::roots(
+                    &ArrowSchemaConverter::new().convert(schema).unwrap(),
+                    projection.clone(),
+                ),
+                None => ProjectionMask::all(),
+            },
             Projection::Parts(projection) => {
                 let mut fixed_projection: Vec<usize> = [0, 1, primary_key_index]
                     .into_iter()
@@ -663,6 +1317,7 @@ where
                     }))
                     .collect();
                 fixed_projection.dedup();
+                self.restrict_projection(&mut fixed_projection);
 
                 ProjectionMask::roots(
                     &ArrowSchemaConverter::new().convert(schema).unwrap(),
@@ -689,12 +1344,20 @@ where
                 ctx.storage_manager(),
                 TsRef::new(key, ts),
                 projection,
+                ctx.arrow_schema(),
                 ctx.cache().clone(),
             )
             .await?
             .map(|entry| Entry::RecordBatch(entry)))
     }
 
+    /// drop the columns that are not part of [`DbOption::with_projection`] from `indices`
+    fn restrict_projection(&self, indices: &mut Vec<usize>) {
+        if let Some(projection) = &self.projection {
+            indices.retain(|index| projection.contains(index));
+        }
+    }
+
     fn check_conflict(&self, key: &<R::Schema as Schema>::Key, ts: Timestamp) -> bool {
         self.mutable.check_conflict(key, ts)
             || self
@@ -704,6 +1367,12 @@ where
                 .any(|(_, immutable)| immutable.check_conflict(key, ts))
     }
 
+    /// what is needed to ask for a freeze once the guard of `self` is released, see
+    /// [`compaction::freeze`]
+    pub(crate) fn compaction_trigger(&self) -> (Sender<CompactTask>, CompactionMode) {
+        (self.compaction_tx.clone(), self.option.compaction_mode)
+    }
+
     async fn flush_wal(&self) -> Result<(), DbError<R>> {
         self.mutable.flush_wal().await?;
         Ok(())
@@ -740,9 +1409,13 @@ where
         Box<dyn FnOnce(Option<ProjectionMask>) -> Option<ScanStream<'scan, R>> + Send + 'scan>,
 
     limit: Option<usize>,
+    versions: usize,
+    reverse: bool,
+    physical_order: bool,
     projection_indices: Option<Vec<usize>>,
     projection: ProjectionMask,
     ctx: Arc<Context<R>>,
+    gaps: ScanGaps<<R::Schema as Schema>::Key>,
 }
 
 impl<'scan, 'range, R> Scan<'scan, 'range, R>
@@ -762,6 +1435,16 @@ where
         >,
         ctx: Arc<Context<R>>,
     ) -> Self {
+        let projection = match &schema.projection {
+            Some(projection) => ProjectionMask::roots(
+                &ArrowSchemaConverter::new()
+                    .convert(schema.record_schema.arrow_schema())
+                    .unwrap(),
+                projection.clone(),
+            ),
+            None => ProjectionMask::all(),
+        };
+
         Self {
             schema,
             lower,
@@ -770,12 +1453,24 @@ where
             version,
             fn_pre_stream,
             limit: None,
-            projection_indices: None,
-            projection: ProjectionMask::all(),
+            versions: 1,
+            reverse: false,
+            physical_order: false,
+            projection_indices: schema.projection.clone(),
+            projection,
             ctx,
+            gaps: ScanGaps::default(),
         }
     }
 
+    /// a handle on the SSTables this scan skips because they are quarantined, filled while the
+    /// stream of [`Scan::take`] or [`Scan::package`] is consumed. Once the stream is done, an
+    /// empty [`ScanGaps`] means the results are complete. See
+    /// [`DbOption::quarantine_unreadable_sstables`]
+    pub fn gaps(&self) -> ScanGaps<<R::Schema as Schema>::Key> {
+        self.gaps.clone()
+    }
+
     /// limit for the scan
     pub fn limit(self, limit: usize) -> Self {
         Self {
@@ -784,21 +1479,63 @@ where
         }
     }
 
-    /// fields in projection Record by field indices
+    /// yield the `versions` newest versions of each key, newest first, instead of only the latest
+    /// one. Versions of removals are yielded as well.
+    pub fn versions(self, versions: usize) -> Self {
+        Self {
+            versions: versions.max(1),
+            ..self
+        }
+    }
+
+    /// yield the keys in descending order within the range, the versions of a key are still
+    /// yielded newest first. Only applies to [`Scan::take`].
+    ///
+    /// The matching entries are buffered before the first one is yielded: with a [`Scan::limit`]
+    /// only the largest keys holding `limit` entries are kept, without one the memory used is
+    /// O(range) rather than O(limit).
+    pub fn reverse(self) -> Self {
+        Self {
+            reverse: true,
+            ..self
+        }
+    }
+
+    /// for debugging the physical layout: yield the entries in the order they are stored instead
+    /// of merging them by key. The entries of the transaction and the memtables come first, then
+    /// the ones of each SSTable, level by level, in the order of its row groups and rows. Every
+    /// stored version is yielded, [`Entry::level`] and [`Entry::file_id`] tell the SSTable it was
+    /// read from. [`Scan::versions`] and [`Scan::reverse`] do not apply. Only applies to
+    /// [`Scan::take`].
+    pub fn physical_order(self) -> Self {
+        Self {
+            physical_order: true,
+            ..self
+        }
+    }
+
+    /// fields in projection Record by field names
+    ///
+    /// The virtual columns [`magic::LEVEL`] and [`magic::FILE_ID`] may be requested alongside user
+    /// columns, they are read through [`Entry::level`] and [`Entry::file_id`]. Columns outside of
+    /// [`DbOption::with_projection`] are ignored.
     pub fn projection(self, projection: &[&str]) -> Self {
-        let schema = self.schema.record_schema.arrow_schema();
+        let record_schema = &self.schema.record_schema;
+        let schema = record_schema.arrow_schema();
         let mut projection = projection
             .iter()
+            .filter(|name| **name != magic::LEVEL && **name != magic::FILE_ID)
             .map(|name| {
-                schema
-                    .index_of(name)
-                    .unwrap_or_else(|_| panic!("unexpected field {}", name))
+                record_schema
+                    .column_index(name)
+                    .unwrap_or_else(|| panic!("unexpected field {}", name))
             })
             .collect::<Vec<usize>>();
-        let primary_key_index = self.schema.record_schema.primary_key_index();
+        let primary_key_index = record_schema.primary_key_index();
         let mut fixed_projection = vec![0, 1, primary_key_index];
         fixed_projection.append(&mut projection);
         fixed_projection.dedup();
+        self.schema.restrict_projection(&mut fixed_projection);
 
         let mask = ProjectionMask::roots(
             &ArrowSchemaConverter::new().convert(schema).unwrap(),
@@ -822,6 +1559,7 @@ where
         let mut fixed_projection = vec![0, 1, primary_key_index];
         fixed_projection.append(&mut projection);
         fixed_projection.dedup();
+        self.schema.restrict_projection(&mut fixed_projection);
 
         let mask = ProjectionMask::roots(
             &ArrowSchemaConverter::new()
@@ -838,6 +1576,9 @@ where
     }
 
     /// get a Stream that returns single row of Record
+    ///
+    /// A [`Scan::reverse`] scan without a [`Scan::limit`] is not streamed: every entry of the
+    /// range is read and held in memory before the first one is yielded.
     pub async fn take(
         self,
     ) -> Result<impl Stream<Item = Result<Entry<'scan, R>, ParquetError>>, DbError<R>> {
@@ -876,16 +1617,55 @@ where
                 &mut streams,
                 (self.lower, self.upper),
                 self.ts,
-                self.limit,
+                // the limit applies to the largest keys of a reverse scan
+                self.limit.filter(|_| !self.reverse),
                 self.projection,
+                &self.gaps,
             )
             .await?;
 
-        let mut merge_stream = MergeStream::from_vec(streams, self.ts).await?;
+        if self.physical_order {
+            let entries = futures_util::stream::iter(streams).flatten();
+            return Ok(Either::Right(Either::Right(
+                entries.take(self.limit.unwrap_or(usize::MAX)),
+            )));
+        }
+        let mut merge_stream = MergeStream::from_vec(streams, self.ts)
+            .await?
+            .versions_per_key(self.versions);
+        if self.reverse {
+            let mut merge_stream = pin!(merge_stream);
+            let mut entries = VecDeque::new();
+            while let Some(entry) = merge_stream.try_next().await? {
+                entries.push_back(entry);
+                let Some(limit) = self.limit else {
+                    continue;
+                };
+                // keep the fewest largest keys holding `limit` entries, with all their versions
+                while let Some(first) = entries.front() {
+                    let versions = entries
+                        .iter()
+                        .take_while(|entry| entry.key().value == first.key().value)
+                        .count();
+                    if entries.len() - versions < limit {
+                        break;
+                    }
+                    entries.drain(..versions);
+                }
+            }
+            let mut entries = Vec::from(entries);
+            reverse_key_order(&mut entries);
+            if let Some(limit) = self.limit {
+                entries.truncate(limit);
+            }
+            return Ok(Either::Right(Either::Left(futures_util::stream::iter(
+                entries.into_iter().map(Ok),
+            ))));
+        }
         if let Some(limit) = self.limit {
             merge_stream = merge_stream.limit(limit);
         }
-        Ok(merge_stream)
+        Ok(Either::Left(merge_stream))
     }
 
     /// Get a Stream that returns RecordBatch consisting of a `batch_size` number of records
@@ -933,9 +1713,12 @@ where
                 self.ts,
                 self.limit,
                 self.projection,
+                &self.gaps,
             )
             .await?;
-        let merge_stream = MergeStream::from_vec(streams, self.ts).await?;
+        let merge_stream = MergeStream::from_vec(streams, self.ts)
+            .await?
+            .versions_per_key(self.versions);
 
         Ok(PackageStream::new(
             batch_size,
@@ -971,6 +1754,23 @@ where
     ExceedsMaxLevel,
     #[error("write log error: {0}")]
     Logger(#[from] fusio_log::error::LogError),
+    #[error("invalid record: {0}")]
+    InvalidRecord(#[from] RecordValidationError),
+    #[error("invalid key: {0}")]
+    KeyType(#[from] KeyTypeMismatch),
+    #[error("arrow error: {0}")]
+    Arrow(#[from] ArrowError),
+    #[error("unknown column: {0}")]
+    UnknownColumn(String),
+    #[error(
+        "changes after timestamp {} are no longer retained, the wal starts after {}",
+        u32::from(*.requested),
+        u32::from(*.retained)
+    )]
+    ChangesTruncated {
+        requested: Timestamp,
+        retained: Timestamp,
+    },
 }
 
 type LockMap<K> = Arc<LockableHashMap<K, ()>>;
@@ -982,11 +1782,18 @@ pub enum Projection<'r> {
 
 pub type ParquetLru = Arc<dyn DynLruCache<FileId> + Send + Sync>;
 
+/// the versions of one key yielded by [`DB::scan_changelog`], oldest first
+pub type ChangelogEntry<R, T> = (<<R as Record>::Schema as Schema>::Key, Vec<(Timestamp, T)>);
+
+/// a change yielded by [`DB::changes_since`]: the timestamp it was committed at and its key
+pub type ChangeEvent<R, T> = (Timestamp, <<R as Record>::Schema as Schema>::Key, T);
+
 #[cfg(all(test, feature = "tokio"))]
 pub(crate) mod tests {
     use std::{
         collections::{BTreeMap, Bound},
         mem,
+        pin::pin,
         sync::Arc,
     };
 
@@ -999,8 +1806,17 @@ pub(crate) mod tests {
     use fusio::{disk::TokioFs, path::Path, DynFs, SeqRead, Write};
     use fusio_dispatch::FsOptions;
     use fusio_log::{Decode, Encode};
-    use futures::StreamExt;
-    use parquet::arrow::ProjectionMask;
+    use futures::{StreamExt, TryStreamExt};
+    use parquet::{
+        arrow::ProjectionMask,
+        basic::{Compression, Encoding},
+        file::{
+            metadata::ColumnChunkMetaData,
+            properties::WriterProperties,
+            reader::{FileReader, SerializedFileReader},
+        },
+        schema::types::ColumnPath,
+    };
     use parquet_lru::NoCache;
     use tempfile::TempDir;
     use tracing::error;
@@ -1009,19 +1825,27 @@ pub(crate) mod tests {
         cast_arc_value,
         compaction::{leveled::LeveledCompactor, CompactTask, CompactionError, Compactor},
         context::Context,
+        dyn_record, dyn_schema,
         executor::{tokio::TokioExecutor, Executor},
         fs::{generate_file_id, manager::StoreManager},
         inmem::{immutable::tests::TestSchema, mutable::MutableMemTable},
+        magic,
         record::{
             option::OptionRecordRef,
             runtime::test::{test_dyn_item_schema, test_dyn_items},
-            DataType, DynRecord, Key, RecordDecodeError, RecordEncodeError, RecordRef,
-            Schema as RecordSchema, Value, F32, F64,
+            DataType, DynRecord, DynRecordBuilder, DynRecordRef, DynSchema, Key, KeyTypeMismatch,
+            RecordDecodeError, RecordEncodeError, RecordRef, RecordValidationError,
+            Schema as RecordSchema, TimeUnit, Timestamp, Value, ValueDesc, F32, F64,
         },
+        transaction::{CommitError, TransactionEntry},
         trigger::{TriggerFactory, TriggerType},
-        version::{cleaner::Cleaner, set::tests::build_version_set, Version},
+        version::{
+            cleaner::Cleaner, edit::VersionEdit, set::tests::build_version_set, Version,
+            VersionError, FORMAT_VERSION,
+        },
         wal::log::LogType,
-        CompactionOption, DbError, DbOption, Projection, Record, DB,
+        ChangeEvent, CompactionOption, DbError, DbOption, Projection, Record, SstableNaming, DB,
+        REMOVE_RANGE_CHUNK,
     };
 
     #[derive(Debug, PartialEq, Eq, Clone)]
@@ -1156,6 +1980,14 @@ pub(crate) mod tests {
             }
         }
 
+        fn is_null(&self, index: usize) -> bool {
+            match index {
+                1 => self.vu32.is_none(),
+                2 => self.vbool.is_none(),
+                _ => false,
+            }
+        }
+
         fn from_record_batch(
             record_batch: &'r RecordBatch,
             offset: usize,
@@ -1353,7 +2185,7 @@ pub(crate) mod tests {
                 .unwrap();
 
             vec![(
-                Some(generate_file_id()),
+                vec![generate_file_id()],
                 mutable.into_immutable().await.unwrap().1,
             )]
         };
@@ -1369,6 +2201,7 @@ pub(crate) mod tests {
                 trigger,
                 record_schema: Arc::new(TestSchema {}),
                 option,
+                projection: None,
             },
             compaction_rx,
         ))
@@ -1423,7 +2256,6 @@ pub(crate) mod tests {
         executor.spawn(async move {
             while let Ok(task) = compaction_rx.recv_async().await {
                 if let Err(err) = match task {
-                    CompactTask::Freeze => compactor.check_then_compaction(false).await,
                     CompactTask::Flush(option_tx) => {
                         let mut result = compactor.check_then_compaction(true).await;
                         if let Some(tx) = option_tx {
@@ -1435,6 +2267,7 @@ pub(crate) mod tests {
                         }
                         result
                     }
+                    task => compactor.run(task).await,
                 } {
                     error!("[Compaction Error]: {}", err)
                 }
@@ -1541,244 +2374,302 @@ pub(crate) mod tests {
         assert!(!version.level_slice[0].is_empty());
     }
 
-    #[ignore = "s3"]
-    #[cfg(all(feature = "aws", feature = "tokio-http"))]
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_s3_recover() {
+    async fn test_versions_per_key() {
         let temp_dir = TempDir::new().unwrap();
 
-        if option_env!("AWS_ACCESS_KEY_ID").is_none()
-            || option_env!("AWS_SECRET_ACCESS_KEY").is_none()
-        {
-            eprintln!("can not get `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`");
-            return;
-        }
-        let key_id = std::option_env!("AWS_ACCESS_KEY_ID").unwrap().to_string();
-        let secret_key = std::option_env!("AWS_SECRET_ACCESS_KEY")
-            .unwrap()
-            .to_string();
-        let bucket = std::env::var("BUCKET_NAME").expect("expected s3 bucket not to be empty");
-        let region = Some(std::env::var("AWS_REGION").expect("expected s3 region not to be empty"));
-        let token = Some(std::option_env!("AWS_SESSION_TOKEN").unwrap().to_string());
-
-        let s3_option = FsOptions::S3 {
-            bucket,
-            credential: Some(fusio::remotes::aws::AwsCredential {
-                key_id,
-                secret_key,
-                token,
-            }),
-            endpoint: None,
-            region,
-            sign_payload: None,
-            checksum: None,
-        };
-        let option = DbOption::new(
+        let mut option = DbOption::new(
             Path::from_filesystem_path(temp_dir.path()).unwrap(),
-            &TestSchema {},
+            &TestSchema,
         )
-        .base_fs(s3_option.clone());
-        {
-            let db = DB::new(option.clone(), TokioExecutor::current(), TestSchema)
-                .await
-                .unwrap();
+        .versions_per_key(5);
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.major_threshold_with_sst_size = 1;
+        option.major_default_oldest_table_num = 1;
 
-            for item in &test_items()[0..10] {
-                db.insert(item.clone()).await.unwrap();
-            }
-            // flush to s3
-            db.flush().await.unwrap();
-            drop(db);
-        }
-        {
-            // remove files in local disk
-            let wal_path = option.wal_dir_path().to_string();
-            let path = std::path::Path::new(wal_path.as_str());
-            if path.exists() {
-                std::fs::remove_dir_all(path).unwrap();
-            }
-            let version_path = option.version_log_dir_path().to_string();
-            let path = std::path::Path::new(version_path.as_str());
-            if path.exists() {
-                std::fs::remove_dir_all(path).unwrap();
-            }
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for ts in 0..7_u32 {
+            let item = Test {
+                vstring: "key".to_string(),
+                vu32: ts,
+                vbool: Some(true),
+            };
+            db.write(item, ts.into()).await.unwrap();
         }
-        // test recover from s3
-        {
-            let db: DB<Test, TokioExecutor> =
-                DB::new(option.clone(), TokioExecutor::current(), TestSchema)
-                    .await
-                    .unwrap();
-            let mut sort_items = BTreeMap::new();
-            for item in test_items()[0..10].iter() {
-                sort_items.insert(item.vstring.clone(), item.clone());
-            }
-            let tx = db.transaction().await;
-            let mut scan = tx
-                .scan((Bound::Unbounded, Bound::Unbounded))
-                .projection(&["vstring", "vu32", "vbool"])
-                .take()
-                .await
-                .unwrap();
+        db.flush().await.unwrap();
+        // the next flush compacts the table holding all 7 versions into level 1
+        let item = Test {
+            vstring: "other".to_string(),
+            vu32: 0,
+            vbool: None,
+        };
+        db.write(item, 7.into()).await.unwrap();
+        db.flush().await.unwrap();
 
-            while let Some(actual) = scan.next().await.transpose().unwrap() {
-                let (expected_key, expected) = sort_items.pop_first().unwrap();
-                assert_eq!(actual.key().value, expected_key);
-                assert_eq!(actual.value().as_ref().unwrap().vstring, expected.vstring);
-                assert_eq!(actual.value().as_ref().unwrap().vu32, Some(expected.vu32));
-                assert_eq!(actual.value().as_ref().unwrap().vbool, expected.vbool);
-            }
+        let version = db.ctx.version_set.current().await;
+        assert!(!version.level_slice[1].is_empty());
+
+        let schema = db.schema.read().await;
+        let key = "key".to_string();
+        for ts in 0..7_u32 {
+            let vu32 = schema
+                .get(&db.ctx, &version, &key, ts.into(), Projection::All)
+                .await
+                .unwrap()
+                .and_then(|entry| entry.value().and_then(|record| record.vu32));
+            assert_eq!(vu32, (ts >= 2).then_some(ts));
         }
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn schema_recover() {
+    async fn test_scan_changelog() {
         let temp_dir = TempDir::new().unwrap();
-        let fs = Arc::new(TokioFs) as Arc<dyn DynFs>;
-
-        let option = Arc::new(DbOption::new(
+        let option = DbOption::new(
             Path::from_filesystem_path(temp_dir.path()).unwrap(),
             &TestSchema,
-        ));
-        fs.create_dir_all(&option.wal_dir_path()).await.unwrap();
-
-        let (task_tx, _task_rx) = bounded(1);
-
-        let trigger = TriggerFactory::create(option.trigger_type);
-        let schema: crate::DbStorage<Test> = crate::DbStorage {
-            mutable: MutableMemTable::new(&option, trigger.clone(), fs, Arc::new(TestSchema))
-                .await
-                .unwrap(),
-            immutables: Default::default(),
-            compaction_tx: task_tx.clone(),
-            recover_wal_ids: None,
-            trigger,
-            record_schema: Arc::new(TestSchema),
-            option: option.clone(),
-        };
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
 
-        for (i, item) in test_items().into_iter().enumerate() {
-            schema
-                .write(LogType::Full, item, (i as u32).into())
-                .await
-                .unwrap();
+        for vu32 in 0..3 {
+            db.insert(Test {
+                vstring: "a".to_string(),
+                vu32,
+                vbool: Some(true),
+            })
+            .await
+            .unwrap();
         }
-        schema.flush_wal().await.unwrap();
-        drop(schema);
-
-        let db: DB<Test, TokioExecutor> = DB::new(
-            option.as_ref().to_owned(),
-            TokioExecutor::current(),
-            TestSchema,
-        )
+        db.insert(Test {
+            vstring: "b".to_string(),
+            vu32: 10,
+            vbool: None,
+        })
         .await
         .unwrap();
+        // the versions of "a" are read from an SSTable, the removal of "b" from memory
+        db.flush().await.unwrap();
+        db.remove("b".to_string()).await.unwrap();
 
-        let mut sort_items = BTreeMap::new();
-        for item in test_items() {
-            sort_items.insert(item.vstring.clone(), item);
-        }
-        {
-            let tx = db.transaction().await;
-            let mut scan = tx
-                .scan((Bound::Unbounded, Bound::Unbounded))
-                .take()
-                .await
-                .unwrap();
+        let changelog = db
+            .scan_changelog((Bound::Unbounded, Bound::Unbounded), |record| {
+                record.and_then(|record| record.vu32)
+            })
+            .await
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(changelog.len(), 2);
+
+        let (key, versions) = &changelog[0];
+        assert_eq!(key, "a");
+        assert_eq!(
+            versions.iter().map(|(_, vu32)| *vu32).collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(2)]
+        );
+        assert!(versions.windows(2).all(|pair| pair[0].0 < pair[1].0));
 
-            while let Some(entry) = scan.next().await.transpose().unwrap() {
-                let (_, test) = sort_items.pop_first().unwrap();
+        let (key, versions) = &changelog[1];
+        assert_eq!(key, "b");
+        assert_eq!(
+            versions.iter().map(|(_, vu32)| *vu32).collect::<Vec<_>>(),
+            vec![Some(10), None]
+        );
+    }
 
-                assert_eq!(entry.key().value, test.key());
-                assert_eq!(entry.value().as_ref().unwrap().vstring, test.vstring);
-                assert_eq!(entry.value().as_ref().unwrap().vu32, Some(test.vu32));
-                assert_eq!(entry.value().as_ref().unwrap().vbool, test.vbool);
-            }
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_insert_ts() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let items = test_items();
+        let mut inserted = Vec::new();
+        for item in &items[0..4] {
+            inserted.push(db.insert(item.clone()).await.unwrap());
+        }
+        assert!(inserted.windows(2).all(|pair| pair[0] < pair[1]));
+        let batch_ts = db.insert_batch(items[4..8].iter().cloned()).await.unwrap();
+        assert!(batch_ts > inserted[3]);
+        inserted.extend([batch_ts; 4]);
+
+        // overwriting a record reports the timestamp of the latest write
+        inserted[1] = db.insert(items[1].clone()).await.unwrap();
+        for (item, ts) in items[0..8].iter().zip(inserted) {
+            let read = db.get(&item.vstring, |entry| entry.ts()).await.unwrap();
+            assert_eq!(read, Some(ts));
         }
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn dyn_schema_recover() {
+    async fn test_insert_if_changed() {
         let temp_dir = TempDir::new().unwrap();
-        let manager = StoreManager::new(FsOptions::Local, vec![]).unwrap();
-
-        let dyn_schema = Arc::new(test_dyn_item_schema());
-        let option = Arc::new(DbOption::new(
+        let dyn_schema = test_dyn_item_schema();
+        let option = DbOption::new(
             Path::from_filesystem_path(temp_dir.path()).unwrap(),
-            dyn_schema.as_ref(),
-        ));
-        manager
-            .base_fs()
-            .create_dir_all(&option.wal_dir_path())
+            &dyn_schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option, TokioExecutor::current(), dyn_schema)
+                .await
+                .unwrap();
+
+        let record = || test_dyn_items().swap_remove(0);
+        assert!(db.insert_if_changed(record()).await.unwrap());
+        assert!(!db.insert_if_changed(record()).await.unwrap());
+        // compared against the record read from an SSTable
+        db.flush().await.unwrap();
+        assert!(!db.insert_if_changed(record()).await.unwrap());
+
+        let mut changed = record();
+        changed.values[9].value = Arc::new(Some(F64::from(-0.0)));
+        let key = changed.key();
+        assert!(db.insert_if_changed(changed).await.unwrap());
+        let price = db
+            .get(&key, |entry| {
+                let price = entry.get().columns[9].clone();
+                Some(*cast_arc_value!(price.value, Option<F64>))
+            })
             .await
+            .unwrap()
             .unwrap();
+        assert!(price.unwrap().is_sign_negative());
+    }
 
-        let (task_tx, _task_rx) = bounded(1);
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_changes_since() {
+        async fn changes(
+            db: &DB<Test, TokioExecutor>,
+            ts: crate::timestamp::Timestamp,
+        ) -> Vec<ChangeEvent<Test, Option<Test>>> {
+            db.changes_since(ts, |record| {
+                record.map(|record| Test {
+                    vstring: record.vstring.to_string(),
+                    vu32: record.vu32.unwrap(),
+                    vbool: record.vbool,
+                })
+            })
+            .await
+            .map(Result::unwrap)
+            .collect()
+            .await
+        }
 
-        let trigger = TriggerFactory::create(option.trigger_type);
-        let schema: crate::DbStorage<DynRecord> = crate::DbStorage {
-            mutable: MutableMemTable::new(
-                &option,
-                trigger.clone(),
-                manager.base_fs().clone(),
-                dyn_schema.clone(),
-            )
-            .await
-            .unwrap(),
-            immutables: Default::default(),
-            compaction_tx: task_tx.clone(),
-            recover_wal_ids: None,
-            trigger,
-            record_schema: dyn_schema.clone(),
-            option,
-        };
+        async fn apply(
+            db: &DB<Test, TokioExecutor>,
+            changes: Vec<ChangeEvent<Test, Option<Test>>>,
+        ) -> crate::timestamp::Timestamp {
+            let mut applied = 0.into();
+            for (ts, key, record) in changes {
+                assert!(ts >= applied);
+                match record {
+                    Some(record) => {
+                        db.insert(record).await.unwrap();
+                    }
+                    None => {
+                        db.remove(key).await.unwrap();
+                    }
+                }
+                applied = ts;
+            }
+            applied
+        }
 
-        for item in test_dyn_items().into_iter() {
-            schema
-                .write(LogType::Full, item, 0_u32.into())
-                .await
-                .unwrap();
+        async fn records(db: &DB<Test, TokioExecutor>) -> Vec<(String, u32)> {
+            db.scan((Bound::Unbounded, Bound::Unbounded), |entry| {
+                let record = entry.get();
+                (record.vstring.to_string(), record.vu32.unwrap())
+            })
+            .await
+            .map(Result::unwrap)
+            .collect()
+            .await
         }
-        schema.flush_wal().await.unwrap();
-        drop(schema);
 
-        let option = DbOption::new(
-            Path::from_filesystem_path(temp_dir.path()).unwrap(),
-            dyn_schema.as_ref(),
+        let leader_dir = TempDir::new().unwrap();
+        let follower_dir = TempDir::new().unwrap();
+        let leader_option = DbOption::new(
+            Path::from_filesystem_path(leader_dir.path()).unwrap(),
+            &TestSchema,
         );
-        let dyn_schema = test_dyn_item_schema();
-        let db: DB<DynRecord, TokioExecutor> =
-            DB::new(option, TokioExecutor::current(), dyn_schema)
+        let follower_option = DbOption::new(
+            Path::from_filesystem_path(follower_dir.path()).unwrap(),
+            &TestSchema,
+        );
+
+        let leader: DB<Test, TokioExecutor> =
+            DB::new(leader_option.clone(), TokioExecutor::current(), TestSchema)
                 .await
                 .unwrap();
-
-        let mut sort_items = BTreeMap::new();
-        for item in test_dyn_items() {
-            sort_items.insert(item.key(), item);
-        }
-
-        {
-            let tx = db.transaction().await;
-            let mut scan = tx
-                .scan((Bound::Unbounded, Bound::Unbounded))
-                .projection(&["id", "age", "height"])
-                .take()
+        let follower: DB<Test, TokioExecutor> =
+            DB::new(follower_option, TokioExecutor::current(), TestSchema)
                 .await
                 .unwrap();
 
-            while let Some(entry) = scan.next().await.transpose().unwrap() {
-                let columns1 = entry.value().unwrap().columns;
-                let (_, record) = sort_items.pop_first().unwrap();
-                let columns2 = record.as_record_ref().columns;
+        for item in &test_items()[0..3] {
+            leader.insert(item.clone()).await.unwrap();
+        }
+        let applied = apply(&follower, changes(&leader, 0.into()).await).await;
+        assert_eq!(records(&follower).await, records(&leader).await);
+
+        let mut txn = leader.transaction().await;
+        txn.insert(Test {
+            vstring: "0".to_string(),
+            vu32: 100,
+            vbool: None,
+        });
+        txn.remove("1".to_string());
+        txn.commit().await.unwrap();
+        let changes_before_flush = changes(&leader, applied).await;
+        assert_eq!(changes_before_flush.len(), 2);
+        let applied = apply(&follower, changes_before_flush).await;
+
+        // flushing drops the wal of the changes up to `applied`
+        leader.flush().await.unwrap();
+        {
+            let mut truncated = pin!(leader.changes_since(0.into(), |_| ()).await);
+            assert!(matches!(
+                truncated.next().await,
+                Some(Err(CommitError::Database(DbError::ChangesTruncated { requested, retained })))
+                    if requested == 0.into() && retained == applied
+            ));
+            assert!(truncated.next().await.is_none());
+        }
+        assert!(changes(&leader, applied).await.is_empty());
 
-                assert_eq!(columns1.get(1), columns2.get(1));
-                assert_eq!(columns1.get(2), columns2.get(2));
-            }
+        for item in &test_items()[3..6] {
+            leader.insert(item.clone()).await.unwrap();
         }
+        leader.flush_wal().await.unwrap();
+
+        let before = changes(&leader, applied).await;
+        drop(leader);
+        let leader: DB<Test, TokioExecutor> =
+            DB::new(leader_option, TokioExecutor::current(), TestSchema)
+                .await
+                .unwrap();
+        assert_eq!(changes(&leader, applied).await, before);
+
+        let changes = changes(&leader, applied).await;
+        assert_eq!(changes.len(), 3);
+        assert!(changes.iter().all(|(ts, _, _)| *ts > applied));
+        apply(&follower, changes).await;
+        assert_eq!(records(&follower).await, records(&leader).await);
+        assert_eq!(records(&follower).await.len(), 5);
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_get_removed() {
+    async fn test_split_ranges() {
         let temp_dir = TempDir::new().unwrap();
 
         let mut option = DbOption::new(
@@ -1787,157 +2678,1025 @@ pub(crate) mod tests {
         );
         option.immutable_chunk_num = 1;
         option.immutable_chunk_max_num = 1;
-        option.major_threshold_with_sst_size = 3;
-        option.major_default_oldest_table_num = 1;
-        option.trigger_type = TriggerType::Length(5);
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
         let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
             .await
             .unwrap();
 
-        for (idx, item) in test_items().into_iter().enumerate() {
-            if idx % 2 == 0 {
-                db.write(item, 0.into()).await.unwrap();
-            } else {
-                db.remove(item.vstring).await.unwrap();
+        assert_eq!(
+            db.split_ranges(4).await,
+            vec![(Bound::Unbounded, Bound::Unbounded)]
+        );
+
+        for chunk in test_items().chunks(8) {
+            for item in chunk {
+                db.insert(item.clone()).await.unwrap();
+            }
+            db.flush().await.unwrap();
+        }
+        // unflushed data must be covered as well
+        db.insert(Test {
+            vstring: "99".to_string(),
+            vu32: 99,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+
+        let ranges = db.split_ranges(4).await;
+        assert_eq!(ranges.len(), 4);
+        assert_eq!(ranges.first().unwrap().0, Bound::Unbounded);
+        assert_eq!(ranges.last().unwrap().1, Bound::Unbounded);
+        for pair in ranges.windows(2) {
+            match (&pair[0].1, &pair[1].0) {
+                (Bound::Excluded(upper), Bound::Included(lower)) => assert_eq!(upper, lower),
+                bounds => panic!("ranges are not contiguous: {:?}", bounds),
             }
         }
 
-        for i in 0..32 {
-            let vstring = db
-                .get(&i.to_string(), |e| Some(e.get().vstring.to_string()))
+        let txn = db.transaction().await;
+        let mut keys = vec![];
+        for (lower, upper) in ranges.iter() {
+            let mut scan = txn
+                .scan((lower.as_ref(), upper.as_ref()))
+                .take()
                 .await
                 .unwrap();
-            if i % 2 == 0 {
-                assert!(vstring.is_some());
-                assert_eq!(vstring, Some(i.to_string()));
-            } else {
-                assert!(vstring.is_none());
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                keys.push(entry.value().unwrap().vstring.to_string());
             }
         }
+        let mut expected = test_items()
+            .into_iter()
+            .map(|item| item.vstring)
+            .chain(Some("99".to_string()))
+            .collect::<Vec<_>>();
+        expected.sort();
+        assert_eq!(keys, expected);
     }
 
     #[tokio::test(flavor = "multi_thread")]
-    async fn test_read_write_dyn() {
+    async fn test_disable_wal() {
         let temp_dir = TempDir::new().unwrap();
 
-        let dyn_schema = test_dyn_item_schema();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .disable_wal(true);
+
+        {
+            let db: DB<Test, TokioExecutor> =
+                DB::new(option.clone(), TokioExecutor::current(), TestSchema)
+                    .await
+                    .unwrap();
+
+            for item in &test_items()[0..10] {
+                db.insert(item.clone()).await.unwrap();
+            }
+            db.flush().await.unwrap();
+            for item in &test_items()[10..20] {
+                db.insert(item.clone()).await.unwrap();
+            }
+            assert!(std::fs::read_dir(temp_dir.path().join("wal"))
+                .unwrap()
+                .next()
+                .is_none());
+            // simulate a crash: drop the db without flushing the memtable
+        }
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+        for (i, item) in test_items()[0..20].iter().enumerate() {
+            let found = db.get(&item.vstring, |_| Some(())).await.unwrap();
+            assert_eq!(found.is_some(), i < 10);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_wal_segment_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let wal_files = || {
+            std::fs::read_dir(temp_dir.path().join("wal"))
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect::<Vec<_>>()
+        };
+
         let mut option = DbOption::new(
             Path::from_filesystem_path(temp_dir.path()).unwrap(),
-            &dyn_schema,
-        );
-        option.immutable_chunk_num = 1;
-        option.immutable_chunk_max_num = 1;
-        option.major_threshold_with_sst_size = 3;
-        option.level_sst_magnification = 10;
-        option.max_sst_file_size = 2 * 1024 * 1024;
-        option.major_default_oldest_table_num = 1;
-        option.trigger_type = TriggerType::Length(5);
+            &TestSchema,
+        )
+        .wal_segment_size(64);
+        // snapshot the manifest on every edit so flushed wal segments are removed eagerly
+        option.version_log_snapshot_threshold = 0;
 
-        let db: DB<DynRecord, TokioExecutor> =
-            DB::new(option, TokioExecutor::current(), dyn_schema)
+        let db: DB<Test, TokioExecutor> =
+            DB::new(option.clone(), TokioExecutor::current(), TestSchema)
                 .await
                 .unwrap();
-
-        for (i, item) in test_dyn_items().into_iter().enumerate() {
-            if i == 28 {
-                db.remove(item.key()).await.unwrap();
-            } else {
-                db.write(item, 0.into()).await.unwrap();
-            }
+        for item in &test_items()[0..20] {
+            db.insert(item.clone()).await.unwrap();
         }
+        db.flush_wal().await.unwrap();
 
-        dbg!(db.ctx.version_set.current().await);
-        // test get
-        {
-            let tx = db.transaction().await;
+        let segments = wal_files();
+        assert!(segments.len() > 1, "{:?}", segments);
 
-            for i in 0..50 {
-                let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(i as i64), false);
-                let option1 = tx.get(&key, Projection::All).await.unwrap();
-                if i == 28 {
-                    assert!(option1.is_none());
-                    continue;
-                }
-                let entry = option1.unwrap();
-                let record_ref = entry.get();
+        db.flush().await.unwrap();
+        let remaining = wal_files();
+        assert!(remaining.iter().all(|file| !segments.contains(file)));
 
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.first().unwrap().value, i64),
-                    i as i64
-                );
-                let height = cast_arc_value!(record_ref.columns.get(2).unwrap().value, Option<i16>);
-                if i < 45 {
-                    assert_eq!(*height, Some(20 * i as i16),);
-                } else {
-                    assert!(height.is_none());
-                }
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(3).unwrap().value, Option<i32>),
-                    Some(200 * i),
-                );
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(4).unwrap().value, Option<String>),
-                    Some(i.to_string()),
-                );
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(5).unwrap().value, Option<String>),
-                    Some(format!("{}@tonbo.io", i)),
-                );
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(6).unwrap().value, Option<bool>),
-                    Some(i % 2 == 0),
-                );
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(7).unwrap().value, Option<Vec<u8>>),
-                    Some(i.to_le_bytes().to_vec()),
-                );
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(8).unwrap().value, Option<F32>),
-                    Some(F32::from(i as f32 * 1.11)),
-                );
-                assert_eq!(
-                    *cast_arc_value!(record_ref.columns.get(9).unwrap().value, Option<F64>),
-                    Some(F64::from(i as f64 * 1.01)),
-                );
-            }
-            tx.commit().await.unwrap();
+        for item in &test_items()[20..30] {
+            db.insert(item.clone()).await.unwrap();
         }
-        // test scan
+        db.flush_wal().await.unwrap();
+        drop(db);
+
+        // the unflushed segments are replayed on recovery
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+        for item in &test_items()[0..30] {
+            assert!(db.get(&item.vstring, |_| Some(())).await.unwrap().is_some());
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_unsupported_format_version() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+
         {
-            let tx = db.transaction().await;
-            let lower = Value::new(DataType::Int64, "id".to_owned(), Arc::new(0_i64), false);
-            let upper = Value::new(DataType::Int64, "id".to_owned(), Arc::new(49_i64), false);
-            let mut scan = tx
-                .scan((Bound::Included(&lower), Bound::Included(&upper)))
-                .projection(&["id", "height", "bytes", "grade", "price"])
-                .take()
+            let db: DB<Test, TokioExecutor> =
+                DB::new(option.clone(), TokioExecutor::current(), TestSchema)
+                    .await
+                    .unwrap();
+            db.insert(test_items()[0].clone()).await.unwrap();
+            db.flush().await.unwrap();
+            // simulate a manifest written by a newer release
+            db.ctx
+                .version_set
+                .apply_edits(
+                    vec![VersionEdit::FormatVersion {
+                        version: FORMAT_VERSION + 1,
+                    }],
+                    None,
+                    false,
+                )
                 .await
                 .unwrap();
+        }
 
-            let mut i = 0_i64;
-            while let Some(entry) = scan.next().await.transpose().unwrap() {
-                if i == 28 {
-                    assert!(entry.value().is_none());
-                    i += 1;
-                    continue;
-                }
-                let columns = entry.value().unwrap().columns;
+        // the manifest is left untouched, so every attempt fails the same way
+        for _ in 0..2 {
+            let result: Result<DB<Test, TokioExecutor>, _> =
+                DB::new(option.clone(), TokioExecutor::current(), TestSchema).await;
+            assert!(matches!(
+                result,
+                Err(DbError::Version(VersionError::UnsupportedFormatVersion { found, supported }))
+                    if found == FORMAT_VERSION + 1 && supported == FORMAT_VERSION
+            ));
+        }
+    }
 
-                let primary_key_col = columns.first().unwrap();
-                assert_eq!(primary_key_col.datatype(), DataType::Int64);
-                assert_eq!(primary_key_col.desc.name, "id".to_string());
-                assert_eq!(
-                    *primary_key_col
-                        .value
-                        .as_ref()
-                        .downcast_ref::<i64>()
-                        .unwrap(),
-                    i
-                );
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_sstable_naming() {
+        let temp_dir = TempDir::new().unwrap();
 
-                let col = columns.get(2).unwrap();
-                assert_eq!(col.datatype(), DataType::Int16);
-                assert_eq!(col.desc.name, "height".to_string());
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .sstable_naming(SstableNaming::Leveled);
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+        {
+            let db: DB<Test, TokioExecutor> =
+                DB::new(option.clone(), TokioExecutor::current(), TestSchema)
+                    .await
+                    .unwrap();
+
+            for item in &test_items()[0..10] {
+                db.write(item.clone(), 0.into()).await.unwrap();
+            }
+            db.flush().await.unwrap();
+
+            let version = db.ctx.version_set.current().await;
+            assert!(!version.level_slice[0].is_empty());
+            for scope in version.level_slice[0].iter() {
+                assert!(temp_dir
+                    .path()
+                    .join(format!("L0-{}.parquet", scope.gen))
+                    .exists());
+                assert!(!temp_dir
+                    .path()
+                    .join(format!("{}.parquet", scope.gen))
+                    .exists());
+            }
+        }
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+        let tx = db.transaction().await;
+        let key = 3.to_string();
+        let entry = tx.get(&key, Projection::All).await.unwrap().unwrap();
+        assert_eq!(entry.get().vu32, Some(3));
+    }
+
+    /// flushes distinct `vstring` keys and `vu32` values out of 4 distinct ones into an SSTable
+    /// and returns the metadata of their column chunks
+    async fn dictionary_column_chunks(
+        vstring_dictionary: bool,
+        vu32_dictionary: bool,
+    ) -> (ColumnChunkMetaData, ColumnChunkMetaData) {
+        let temp_dir = TempDir::new().unwrap();
+        let vstring = ColumnPath::new(vec!["vstring".to_string()]);
+        let vu32 = ColumnPath::new(vec!["vu32".to_string()]);
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .write_parquet_option(
+            WriterProperties::builder()
+                .set_compression(Compression::UNCOMPRESSED)
+                .build(),
+        )
+        .column_dictionary_enabled(vstring.clone(), vstring_dictionary)
+        .column_dictionary_enabled(vu32.clone(), vu32_dictionary);
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let mut rng = fastrand::Rng::with_seed(42);
+        for i in 0..1000 {
+            db.insert(Test {
+                vstring: format!("{:05}", i),
+                vu32: [7, 1 << 20, 123_456_789, u32::MAX][rng.usize(..4)],
+                vbool: None,
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+
+        let version = db.ctx.version_set.current().await;
+        let gen = version.level_slice[0][0].gen;
+        let file = std::fs::File::open(temp_dir.path().join(format!("{}.parquet", gen))).unwrap();
+        let reader = SerializedFileReader::new(file).unwrap();
+        let row_group = reader.metadata().row_group(0);
+        let column = |path: &ColumnPath| {
+            row_group
+                .columns()
+                .iter()
+                .find(|column| column.column_path() == path)
+                .unwrap()
+                .clone()
+        };
+        (column(&vstring), column(&vu32))
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_column_dictionary_enabled() {
+        let is_dictionary_encoded = |column: &ColumnChunkMetaData| {
+            column.dictionary_page_offset().is_some()
+                && column.encodings().contains(&Encoding::RLE_DICTIONARY)
+        };
+
+        let (vstring, vu32) = dictionary_column_chunks(false, true).await;
+        assert!(!is_dictionary_encoded(&vstring));
+        assert!(is_dictionary_encoded(&vu32));
+
+        let (vstring, plain_vu32) = dictionary_column_chunks(true, false).await;
+        assert!(is_dictionary_encoded(&vstring));
+        assert!(!is_dictionary_encoded(&plain_vu32));
+
+        // 4 distinct values are encoded as a dictionary of 4 entries and 2-bit indices
+        assert!(
+            vu32.compressed_size() < plain_vu32.compressed_size(),
+            "{} >= {}",
+            vu32.compressed_size(),
+            plain_vu32.compressed_size()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_virtual_columns() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for item in &test_items()[0..5] {
+            db.write(item.clone(), 0.into()).await.unwrap();
+        }
+        db.flush().await.unwrap();
+        for item in &test_items()[5..10] {
+            db.write(item.clone(), 0.into()).await.unwrap();
+        }
+        let version = db.ctx.version_set.current().await;
+        assert_eq!(version.level_slice[0].len(), 1);
+        let gen = version.level_slice[0][0].gen;
+
+        let tx = db.transaction().await;
+        let mut scan = tx
+            .scan((Bound::Unbounded, Bound::Unbounded))
+            .projection(&["vu32", magic::LEVEL, magic::FILE_ID])
+            .take()
+            .await
+            .unwrap();
+
+        let mut count = 0;
+        while let Some(entry) = scan.next().await.transpose().unwrap() {
+            let vu32 = entry.value().unwrap().vu32.unwrap();
+            if vu32 < 5 {
+                assert_eq!(entry.level(), Some(0));
+                assert_eq!(entry.file_id(), Some(gen));
+            } else {
+                assert_eq!(entry.level(), None);
+                assert_eq!(entry.file_id(), None);
+            }
+            assert_eq!(entry.ts(), 0.into());
+            count += 1;
+        }
+        assert_eq!(count, 10);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_read_concurrency() {
+        let mut results = vec![];
+        for read_concurrency in [1, 8] {
+            let temp_dir = TempDir::new().unwrap();
+
+            let mut option = DbOption::new(
+                Path::from_filesystem_path(temp_dir.path()).unwrap(),
+                &TestSchema,
+            )
+            .read_concurrency(read_concurrency);
+            option.immutable_chunk_num = 1;
+            option.immutable_chunk_max_num = 1;
+            option.major_threshold_with_sst_size = 100;
+            option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+            let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+                .await
+                .unwrap();
+
+            // later tables overwrite some keys of earlier ones
+            for (round, chunk) in test_items().chunks(4).enumerate() {
+                for item in chunk {
+                    db.insert(item.clone()).await.unwrap();
+                }
+                db.insert(Test {
+                    vstring: (round % 3).to_string(),
+                    vu32: 100 + round as u32,
+                    vbool: None,
+                })
+                .await
+                .unwrap();
+                db.flush().await.unwrap();
+            }
+            let version = db.ctx.version_set.current().await;
+            assert_eq!(version.level_slice[0].len(), 8);
+            drop(version);
+
+            let txn = db.transaction().await;
+            let mut scan = txn
+                .scan((Bound::Unbounded, Bound::Unbounded))
+                .take()
+                .await
+                .unwrap();
+            let mut entries = vec![];
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                let value = entry.value().unwrap();
+                entries.push((value.vstring.to_string(), value.vu32));
+            }
+            results.push(entries);
+        }
+        assert_eq!(results[0].len(), 32);
+        assert_eq!(results[0][0], ("0".to_string(), 106));
+        assert_eq!(results[0], results[1]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_reverse() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for item in &test_items()[0..10] {
+            db.write(item.clone(), 0.into()).await.unwrap();
+        }
+        db.flush().await.unwrap();
+        for item in &test_items()[10..20] {
+            db.write(item.clone(), 0.into()).await.unwrap();
+        }
+        db.insert(Test {
+            vstring: "3".to_string(),
+            vu32: 100,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+
+        let lower = "1".to_string();
+        let upper = "5".to_string();
+        let scan_keys = |reverse: bool, versions: usize, limit: Option<usize>| {
+            let db = &db;
+            let (lower, upper) = (&lower, &upper);
+            async move {
+                let tx = db.transaction().await;
+                let mut scan = tx
+                    .scan((Bound::Included(lower), Bound::Excluded(upper)))
+                    .versions(versions);
+                if reverse {
+                    scan = scan.reverse();
+                }
+                if let Some(limit) = limit {
+                    scan = scan.limit(limit);
+                }
+                let mut scan = scan.take().await.unwrap();
+
+                let mut keys = Vec::new();
+                while let Some(entry) = scan.next().await.transpose().unwrap() {
+                    let value = entry.value().unwrap();
+                    keys.push((value.vstring.to_string(), value.vu32.unwrap()));
+                }
+                keys
+            }
+        };
+
+        let forward = scan_keys(false, 1, None).await;
+        assert_eq!(forward.len(), 14);
+        assert_eq!(forward.first(), Some(&("1".to_string(), 1)));
+        assert!(forward.contains(&("3".to_string(), 100)));
+        let mut reverse = scan_keys(true, 1, None).await;
+        assert_eq!(reverse.first(), Some(&("4".to_string(), 4)));
+        reverse.reverse();
+        assert_eq!(reverse, forward);
+
+        // the limit keeps the largest keys
+        assert_eq!(
+            scan_keys(true, 1, Some(3)).await,
+            vec![
+                ("4".to_string(), 4),
+                ("3".to_string(), 100),
+                ("2".to_string(), 2)
+            ]
+        );
+        // the versions of a key stay newest first
+        assert_eq!(
+            scan_keys(true, 2, Some(4)).await,
+            vec![
+                ("4".to_string(), 4),
+                ("3".to_string(), 100),
+                ("3".to_string(), 3),
+                ("2".to_string(), 2)
+            ]
+        );
+        assert_eq!(
+            scan_keys(true, 2, Some(3)).await,
+            vec![
+                ("4".to_string(), 4),
+                ("3".to_string(), 100),
+                ("3".to_string(), 3)
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_physical_order() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for i in [3_u32, 0, 4, 1, 2] {
+            db.insert(Test {
+                vstring: i.to_string(),
+                vu32: i,
+                vbool: Some(true),
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+        let version = db.ctx.version_set.current().await;
+        assert_eq!(version.level_slice[0].len(), 1);
+        let gen = version.level_slice[0][0].gen;
+        db.insert(Test {
+            vstring: "1".to_string(),
+            vu32: 10,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+
+        let tx = db.transaction().await;
+        let mut scan = tx
+            .scan((Bound::Unbounded, Bound::Unbounded))
+            .physical_order()
+            .take()
+            .await
+            .unwrap();
+
+        let mut rows = Vec::new();
+        while let Some(entry) = scan.next().await.transpose().unwrap() {
+            let value = entry.value().unwrap();
+            rows.push((
+                entry.file_id(),
+                value.vstring.to_string(),
+                value.vu32.unwrap(),
+            ));
+        }
+        // the memtable comes first and the SSTable is sorted by the primary key, the versions
+        // of key "1" are not merged
+        assert_eq!(
+            rows,
+            vec![
+                (None, "1".to_string(), 10),
+                (Some(gen), "0".to_string(), 0),
+                (Some(gen), "1".to_string(), 1),
+                (Some(gen), "2".to_string(), 2),
+                (Some(gen), "3".to_string(), 3),
+                (Some(gen), "4".to_string(), 4),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_count() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let insert = |i: u32| {
+            db.insert(Test {
+                vstring: format!("{:03}", i),
+                vu32: i,
+                vbool: None,
+            })
+        };
+        for i in 0..50 {
+            insert(i).await.unwrap();
+        }
+        db.flush().await.unwrap();
+        for i in 50..100 {
+            insert(i).await.unwrap();
+        }
+        // newer versions of flushed keys are counted once
+        for i in 40..50 {
+            insert(i).await.unwrap();
+        }
+        for i in (0..100).step_by(10) {
+            db.remove(format!("{:03}", i)).await.unwrap();
+        }
+
+        let count = db.scan_count((Bound::Unbounded, Bound::Unbounded));
+        assert_eq!(count.await.unwrap(), 90);
+        let (lower, upper) = ("010".to_string(), "020".to_string());
+        let count = db.scan_count((Bound::Included(&lower), Bound::Excluded(&upper)));
+        assert_eq!(count.await.unwrap(), 9);
+    }
+
+    #[ignore = "s3"]
+    #[cfg(all(feature = "aws", feature = "tokio-http"))]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_s3_recover() {
+        let temp_dir = TempDir::new().unwrap();
+
+        if option_env!("AWS_ACCESS_KEY_ID").is_none()
+            || option_env!("AWS_SECRET_ACCESS_KEY").is_none()
+        {
+            eprintln!("can not get `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`");
+            return;
+        }
+        let key_id = std::option_env!("AWS_ACCESS_KEY_ID").unwrap().to_string();
+        let secret_key = std::option_env!("AWS_SECRET_ACCESS_KEY")
+            .unwrap()
+            .to_string();
+        let bucket = std::env::var("BUCKET_NAME").expect("expected s3 bucket not to be empty");
+        let region = Some(std::env::var("AWS_REGION").expect("expected s3 region not to be empty"));
+        let token = Some(std::option_env!("AWS_SESSION_TOKEN").unwrap().to_string());
+
+        let s3_option = FsOptions::S3 {
+            bucket,
+            credential: Some(fusio::remotes::aws::AwsCredential {
+                key_id,
+                secret_key,
+                token,
+            }),
+            endpoint: None,
+            region,
+            sign_payload: None,
+            checksum: None,
+        };
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema {},
+        )
+        .base_fs(s3_option.clone());
+        {
+            let db = DB::new(option.clone(), TokioExecutor::current(), TestSchema)
+                .await
+                .unwrap();
+
+            for item in &test_items()[0..10] {
+                db.insert(item.clone()).await.unwrap();
+            }
+            // flush to s3
+            db.flush().await.unwrap();
+            drop(db);
+        }
+        {
+            // remove files in local disk
+            let wal_path = option.wal_dir_path().to_string();
+            let path = std::path::Path::new(wal_path.as_str());
+            if path.exists() {
+                std::fs::remove_dir_all(path).unwrap();
+            }
+            let version_path = option.version_log_dir_path().to_string();
+            let path = std::path::Path::new(version_path.as_str());
+            if path.exists() {
+                std::fs::remove_dir_all(path).unwrap();
+            }
+        }
+        // test recover from s3
+        {
+            let db: DB<Test, TokioExecutor> =
+                DB::new(option.clone(), TokioExecutor::current(), TestSchema)
+                    .await
+                    .unwrap();
+            let mut sort_items = BTreeMap::new();
+            for item in test_items()[0..10].iter() {
+                sort_items.insert(item.vstring.clone(), item.clone());
+            }
+            let tx = db.transaction().await;
+            let mut scan = tx
+                .scan((Bound::Unbounded, Bound::Unbounded))
+                .projection(&["vstring", "vu32", "vbool"])
+                .take()
+                .await
+                .unwrap();
+
+            while let Some(actual) = scan.next().await.transpose().unwrap() {
+                let (expected_key, expected) = sort_items.pop_first().unwrap();
+                assert_eq!(actual.key().value, expected_key);
+                assert_eq!(actual.value().as_ref().unwrap().vstring, expected.vstring);
+                assert_eq!(actual.value().as_ref().unwrap().vu32, Some(expected.vu32));
+                assert_eq!(actual.value().as_ref().unwrap().vbool, expected.vbool);
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn schema_recover() {
+        let temp_dir = TempDir::new().unwrap();
+        let fs = Arc::new(TokioFs) as Arc<dyn DynFs>;
+
+        let option = Arc::new(DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        ));
+        fs.create_dir_all(&option.wal_dir_path()).await.unwrap();
+
+        let (task_tx, _task_rx) = bounded(1);
+
+        let trigger = TriggerFactory::create(option.trigger_type);
+        let schema: crate::DbStorage<Test> = crate::DbStorage {
+            mutable: MutableMemTable::new(&option, trigger.clone(), fs, Arc::new(TestSchema))
+                .await
+                .unwrap(),
+            immutables: Default::default(),
+            compaction_tx: task_tx.clone(),
+            recover_wal_ids: None,
+            trigger,
+            record_schema: Arc::new(TestSchema),
+            option: option.clone(),
+            projection: None,
+        };
+
+        for (i, item) in test_items().into_iter().enumerate() {
+            schema
+                .write(LogType::Full, item, (i as u32).into())
+                .await
+                .unwrap();
+        }
+        schema.flush_wal().await.unwrap();
+        drop(schema);
+
+        let db: DB<Test, TokioExecutor> = DB::new(
+            option.as_ref().to_owned(),
+            TokioExecutor::current(),
+            TestSchema,
+        )
+        .await
+        .unwrap();
+
+        let mut sort_items = BTreeMap::new();
+        for item in test_items() {
+            sort_items.insert(item.vstring.clone(), item);
+        }
+        {
+            let tx = db.transaction().await;
+            let mut scan = tx
+                .scan((Bound::Unbounded, Bound::Unbounded))
+                .take()
+                .await
+                .unwrap();
+
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                let (_, test) = sort_items.pop_first().unwrap();
+
+                assert_eq!(entry.key().value, test.key());
+                assert_eq!(entry.value().as_ref().unwrap().vstring, test.vstring);
+                assert_eq!(entry.value().as_ref().unwrap().vu32, Some(test.vu32));
+                assert_eq!(entry.value().as_ref().unwrap().vbool, test.vbool);
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn dyn_schema_recover() {
+        let temp_dir = TempDir::new().unwrap();
+        let manager = StoreManager::new(FsOptions::Local, vec![]).unwrap();
+
+        let dyn_schema = Arc::new(test_dyn_item_schema());
+        let option = Arc::new(DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            dyn_schema.as_ref(),
+        ));
+        manager
+            .base_fs()
+            .create_dir_all(&option.wal_dir_path())
+            .await
+            .unwrap();
+
+        let (task_tx, _task_rx) = bounded(1);
+
+        let trigger = TriggerFactory::create(option.trigger_type);
+        let schema: crate::DbStorage<DynRecord> = crate::DbStorage {
+            mutable: MutableMemTable::new(
+                &option,
+                trigger.clone(),
+                manager.base_fs().clone(),
+                dyn_schema.clone(),
+            )
+            .await
+            .unwrap(),
+            immutables: Default::default(),
+            compaction_tx: task_tx.clone(),
+            recover_wal_ids: None,
+            trigger,
+            record_schema: dyn_schema.clone(),
+            option,
+            projection: None,
+        };
+
+        for item in test_dyn_items().into_iter() {
+            schema
+                .write(LogType::Full, item, 0_u32.into())
+                .await
+                .unwrap();
+        }
+        schema.flush_wal().await.unwrap();
+        drop(schema);
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            dyn_schema.as_ref(),
+        );
+        let dyn_schema = test_dyn_item_schema();
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option, TokioExecutor::current(), dyn_schema)
+                .await
+                .unwrap();
+
+        let mut sort_items = BTreeMap::new();
+        for item in test_dyn_items() {
+            sort_items.insert(item.key(), item);
+        }
+
+        {
+            let tx = db.transaction().await;
+            let mut scan = tx
+                .scan((Bound::Unbounded, Bound::Unbounded))
+                .projection(&["id", "age", "height"])
+                .take()
+                .await
+                .unwrap();
+
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                let columns1 = entry.value().unwrap().columns;
+                let (_, record) = sort_items.pop_first().unwrap();
+                let columns2 = record.as_record_ref().columns;
+
+                assert_eq!(columns1.get(1), columns2.get(1));
+                assert_eq!(columns1.get(2), columns2.get(2));
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_removed() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.major_threshold_with_sst_size = 3;
+        option.major_default_oldest_table_num = 1;
+        option.trigger_type = TriggerType::Length(5);
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for (idx, item) in test_items().into_iter().enumerate() {
+            if idx % 2 == 0 {
+                db.write(item, 0.into()).await.unwrap();
+            } else {
+                db.remove(item.vstring).await.unwrap();
+            }
+        }
+
+        for i in 0..32 {
+            let vstring = db
+                .get(&i.to_string(), |e| Some(e.get().vstring.to_string()))
+                .await
+                .unwrap();
+            if i % 2 == 0 {
+                assert!(vstring.is_some());
+                assert_eq!(vstring, Some(i.to_string()));
+            } else {
+                assert!(vstring.is_none());
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_read_write_dyn() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let dyn_schema = test_dyn_item_schema();
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &dyn_schema,
+        );
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.major_threshold_with_sst_size = 3;
+        option.level_sst_magnification = 10;
+        option.max_sst_file_size = 2 * 1024 * 1024;
+        option.major_default_oldest_table_num = 1;
+        option.trigger_type = TriggerType::Length(5);
+
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option, TokioExecutor::current(), dyn_schema)
+                .await
+                .unwrap();
+
+        for (i, item) in test_dyn_items().into_iter().enumerate() {
+            if i == 28 {
+                db.remove(item.key()).await.unwrap();
+            } else {
+                db.write(item, 0.into()).await.unwrap();
+            }
+        }
+
+        dbg!(db.ctx.version_set.current().await);
+        // test get
+        {
+            let tx = db.transaction().await;
+
+            for i in 0..50 {
+                let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(i as i64), false);
+                let option1 = tx.get(&key, Projection::All).await.unwrap();
+                if i == 28 {
+                    assert!(option1.is_none());
+                    continue;
+                }
+                let entry = option1.unwrap();
+                let record_ref = entry.get();
+
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.first().unwrap().value, i64),
+                    i as i64
+                );
+                let height = cast_arc_value!(record_ref.columns.get(2).unwrap().value, Option<i16>);
+                if i < 45 {
+                    assert_eq!(*height, Some(20 * i as i16),);
+                } else {
+                    assert!(height.is_none());
+                }
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(3).unwrap().value, Option<i32>),
+                    Some(200 * i),
+                );
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(4).unwrap().value, Option<String>),
+                    Some(i.to_string()),
+                );
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(5).unwrap().value, Option<String>),
+                    Some(format!("{}@tonbo.io", i)),
+                );
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(6).unwrap().value, Option<bool>),
+                    Some(i % 2 == 0),
+                );
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(7).unwrap().value, Option<Vec<u8>>),
+                    Some(i.to_le_bytes().to_vec()),
+                );
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(8).unwrap().value, Option<F32>),
+                    Some(F32::from(i as f32 * 1.11)),
+                );
+                assert_eq!(
+                    *cast_arc_value!(record_ref.columns.get(9).unwrap().value, Option<F64>),
+                    Some(F64::from(i as f64 * 1.01)),
+                );
+            }
+            tx.commit().await.unwrap();
+        }
+        // test scan
+        {
+            let tx = db.transaction().await;
+            let lower = Value::new(DataType::Int64, "id".to_owned(), Arc::new(0_i64), false);
+            let upper = Value::new(DataType::Int64, "id".to_owned(), Arc::new(49_i64), false);
+            let mut scan = tx
+                .scan((Bound::Included(&lower), Bound::Included(&upper)))
+                .projection(&["id", "height", "bytes", "grade", "price"])
+                .take()
+                .await
+                .unwrap();
+
+            let mut i = 0_i64;
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                if i == 28 {
+                    assert!(entry.value().is_none());
+                    i += 1;
+                    continue;
+                }
+                let columns = entry.value().unwrap().columns;
+
+                let primary_key_col = columns.first().unwrap();
+                assert_eq!(primary_key_col.datatype(), DataType::Int64);
+                assert_eq!(primary_key_col.desc.name, "id".to_string());
+                assert_eq!(
+                    *primary_key_col
+                        .value
+                        .as_ref()
+                        .downcast_ref::<i64>()
+                        .unwrap(),
+                    i
+                );
+
+                let col = columns.get(2).unwrap();
+                assert_eq!(col.datatype(), DataType::Int16);
+                assert_eq!(col.desc.name, "height".to_string());
                 let height = *col.value.as_ref().downcast_ref::<Option<i16>>().unwrap();
                 if i < 45 {
                     assert_eq!(height, Some(i as i16 * 20));
@@ -1950,47 +3709,1194 @@ pub(crate) mod tests {
                         .is_none(),);
                 }
 
-                let col = columns.get(3).unwrap();
-                assert_eq!(col.datatype(), DataType::Int32);
-                assert_eq!(col.desc.name, "weight".to_string());
-                let weight = col.value.as_ref().downcast_ref::<Option<i32>>();
-                assert!(weight.is_some());
-                assert_eq!(*weight.unwrap(), None);
+                let col = columns.get(3).unwrap();
+                assert_eq!(col.datatype(), DataType::Int32);
+                assert_eq!(col.desc.name, "weight".to_string());
+                let weight = col.value.as_ref().downcast_ref::<Option<i32>>();
+                assert!(weight.is_some());
+                assert_eq!(*weight.unwrap(), None);
+
+                let col = columns.get(4).unwrap();
+                assert_eq!(col.datatype(), DataType::String);
+                assert_eq!(col.desc.name, "name".to_string());
+                let name = col.value.as_ref().downcast_ref::<Option<String>>();
+                assert!(name.is_some());
+                assert_eq!(name.unwrap(), &None);
+
+                let col = columns.get(6).unwrap();
+                assert_eq!(col.datatype(), DataType::Boolean);
+                assert_eq!(col.desc.name, "enabled".to_string());
+                let enabled = col.value.as_ref().downcast_ref::<Option<bool>>();
+                assert!(enabled.is_some());
+                assert_eq!(*enabled.unwrap(), None);
+
+                let col = columns.get(7).unwrap();
+                assert_eq!(col.datatype(), DataType::Bytes);
+                assert_eq!(col.desc.name, "bytes".to_string());
+                let bytes = col.value.as_ref().downcast_ref::<Option<Vec<u8>>>();
+                assert!(bytes.is_some());
+                assert_eq!(bytes.unwrap(), &Some((i as i32).to_le_bytes().to_vec()));
+
+                let col = columns.get(8).unwrap();
+                assert_eq!(col.datatype(), DataType::Float32);
+                let v = col.value.as_ref().downcast_ref::<Option<F32>>();
+                assert!(v.is_some());
+                assert_eq!(v.unwrap(), &Some(F32::from(i as f32 * 1.11)));
+
+                let col = columns.get(9).unwrap();
+                assert_eq!(col.datatype(), DataType::Float64);
+                let v = col.value.as_ref().downcast_ref::<Option<F64>>();
+                assert!(v.is_some());
+                assert_eq!(v.unwrap(), &Some(F64::from(i as f64 * 1.01)));
+                i += 1
+            }
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_insert_invalid_dyn_record() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let dyn_schema = test_dyn_item_schema();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &dyn_schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option, TokioExecutor::current(), dyn_schema)
+                .await
+                .unwrap();
+
+        assert!(matches!(
+            db.insert(DynRecord::new(vec![], 0)).await,
+            Err(CommitError::Database(DbError::InvalidRecord(
+                RecordValidationError::Empty
+            )))
+        ));
+
+        let id = Value::new(DataType::Int64, "id".to_string(), Arc::new(0_i64), false);
+        assert!(matches!(
+            db.insert(DynRecord::new(vec![id], 0)).await,
+            Err(CommitError::Database(DbError::InvalidRecord(
+                RecordValidationError::ColumnCount { actual: 1, .. }
+            )))
+        ));
+
+        let mut items = test_dyn_items();
+        items.truncate(2);
+        items.push(DynRecord::new(vec![], 0));
+        assert!(db.insert_batch(items.into_iter()).await.is_err());
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(0_i64), false);
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_none());
+
+        for item in test_dyn_items().into_iter().take(2) {
+            db.insert(item).await.unwrap();
+        }
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_invalid_dyn_record_columns() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let dyn_schema = test_dyn_item_schema();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &dyn_schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option, TokioExecutor::current(), dyn_schema)
+                .await
+                .unwrap();
+
+        let invalid = |index: usize, value: Value| {
+            let mut record = test_dyn_items().swap_remove(0);
+            record.values[index] = value;
+            record
+        };
+        let db = &db;
+        let insert = |record: DynRecord| async move {
+            match db.insert(record).await {
+                Err(CommitError::Database(DbError::InvalidRecord(err))) => err,
+                other => panic!("expected an invalid record, got {:?}", other),
+            }
+        };
+
+        let err = insert(invalid(
+            3,
+            Value::new(DataType::Int64, "weight".into(), Arc::new(0_i64), false),
+        ))
+        .await;
+        assert_eq!(
+            err.to_string(),
+            "column weight has datatype Int32, but the value has datatype Int64"
+        );
+        assert!(matches!(
+            err,
+            RecordValidationError::ColumnTypeMismatch {
+                expected: DataType::Int32,
+                actual: DataType::Int64,
+                ..
+            }
+        ));
+        assert!(matches!(
+            insert(invalid(
+                3,
+                Value::new(DataType::Int32, "weight".into(), Arc::new(0_i64), false),
+            ))
+            .await,
+            RecordValidationError::InvalidValue {
+                datatype: DataType::Int32,
+                ..
+            }
+        ));
+        assert!(matches!(
+            insert(invalid(
+                4,
+                Value::new(DataType::String, "name".into(), Arc::new(None::<String>), false),
+            ))
+            .await,
+            RecordValidationError::NullValue { column } if column == "name"
+        ));
+        assert!(matches!(
+            insert(invalid(
+                1,
+                Value::new(DataType::Int8, "age".into(), Arc::new(1_i8), false),
+            ))
+            .await,
+            RecordValidationError::NullableMismatch {
+                expected: true,
+                actual: false,
+                ..
+            }
+        ));
+        let mut short = test_dyn_items().swap_remove(0);
+        short.values.truncate(5);
+        assert!(matches!(
+            insert(short).await,
+            RecordValidationError::ColumnCount {
+                expected: 11,
+                actual: 5
+            }
+        ));
+
+        // records written in a transaction are checked on commit
+        let mut txn = db.transaction().await;
+        txn.insert(invalid(
+            3,
+            Value::new(DataType::Int64, "weight".into(), Arc::new(0_i64), false),
+        ));
+        assert!(matches!(
+            txn.commit().await,
+            Err(CommitError::Database(DbError::InvalidRecord(
+                RecordValidationError::ColumnTypeMismatch { .. }
+            )))
+        ));
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(0_i64), false);
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_none());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_key_type_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let dyn_schema = test_dyn_item_schema();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &dyn_schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option, TokioExecutor::current(), dyn_schema)
+                .await
+                .unwrap();
+        for item in test_dyn_items().into_iter().take(2) {
+            db.insert(item).await.unwrap();
+        }
+
+        let missing = Value::new(DataType::Int64, "id".to_string(), Arc::new(7_i64), false);
+        assert!(db.get(&missing, |_| Some(())).await.unwrap().is_none());
+
+        let wrong = Value::new(
+            DataType::String,
+            "id".to_string(),
+            Arc::new("0".to_string()),
+            false,
+        );
+        assert!(matches!(
+            db.get(&wrong, |_| Some(())).await,
+            Err(CommitError::Database(DbError::KeyType(KeyTypeMismatch {
+                expected: DataType::Int64,
+                actual: DataType::String,
+            })))
+        ));
+        assert!(matches!(
+            db.remove(wrong.clone()).await,
+            Err(CommitError::Database(DbError::KeyType(_)))
+        ));
+
+        let mut txn = db.transaction().await;
+        assert!(matches!(
+            txn.get(&wrong, Projection::All).await,
+            Err(DbError::KeyType(_))
+        ));
+        txn.remove(wrong);
+        assert!(matches!(
+            txn.commit().await,
+            Err(CommitError::Database(DbError::KeyType(_)))
+        ));
+
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(0_i64), false);
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_timestamp_unit_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let schema = DynSchema::new(
+            vec![
+                ValueDesc::new("id".to_string(), DataType::Int64, false),
+                ValueDesc::new(
+                    "created_at".to_string(),
+                    DataType::Timestamp(TimeUnit::Second),
+                    true,
+                ),
+            ],
+            0,
+        );
+        let millis = DynRecordBuilder::new(&schema)
+            .set("id", 0_i64)
+            .set("created_at", Timestamp::new_millis(1717507203412))
+            .build()
+            .unwrap();
+        let seconds = DynRecordBuilder::new(&schema)
+            .set("id", 0_i64)
+            .set("created_at", Timestamp::new_seconds(1717507203))
+            .build()
+            .unwrap();
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+
+        match db.insert(millis).await {
+            Err(CommitError::Database(DbError::InvalidRecord(err))) => {
+                assert_eq!(
+                    err.to_string(),
+                    "column created_at stores timestamps in Second, but the value is in \
+                     Millisecond"
+                );
+                assert!(matches!(
+                    err,
+                    RecordValidationError::TimestampUnitMismatch {
+                        expected: TimeUnit::Second,
+                        actual: TimeUnit::Millisecond,
+                        ..
+                    }
+                ));
+            }
+            other => panic!("expected a timestamp unit mismatch, got {:?}", other),
+        }
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(0_i64), false);
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_none());
+
+        db.insert(seconds).await.unwrap();
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_some());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_coerce_integer_keys() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let schema = dyn_schema!(("id", Int64, false), ("v", Int32, true), 0).coerce_integer_keys();
+        let records = [5_i64, (1_i64 << 32) + 7]
+            .into_iter()
+            .map(|id| {
+                DynRecordBuilder::new(&schema)
+                    .set("id", id)
+                    .set("v", id as i32)
+                    .build()
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+        db.insert_batch(records.into_iter()).await.unwrap();
+
+        let key = |id: i32| Value::new(DataType::Int32, "id".to_string(), Arc::new(id), false);
+        let found = db
+            .get(&key(5), |entry| {
+                let record = entry.get();
+                Some(*cast_arc_value!(record.columns[0].value, i64))
+            })
+            .await
+            .unwrap();
+        assert_eq!(found, Some(5));
+        // 7 must not match the row whose id only agrees with it in the low 32 bits
+        assert!(db.get(&key(7), |_| Some(())).await.unwrap().is_none());
+
+        db.remove(key(5)).await.unwrap();
+        assert!(db.get(&key(5), |_| Some(())).await.unwrap().is_none());
+
+        let wide = Value::new(
+            DataType::Int64,
+            "id".to_string(),
+            Arc::new((1_i64 << 32) + 7),
+            false,
+        );
+        assert!(wide.cast_integer(DataType::Int32).is_none());
+        assert!(key(-1).cast_integer(DataType::UInt64).is_none());
+        let negative = Value::new(DataType::Int64, "id".to_string(), Arc::new(-1_i64), false);
+        assert_eq!(key(-1).cast_integer(DataType::Int64), Some(negative));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_reopen_with_stored_schema() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let schema = dyn_schema!(("name", String, true), ("id", Int64, false), 1);
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option.clone(), TokioExecutor::current(), schema)
+                .await
+                .unwrap();
+        for id in 0..10_i64 {
+            db.insert(dyn_record!(
+                ("name", String, true, Some(id.to_string())),
+                ("id", Int64, false, id),
+                1
+            ))
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+        let gen = db.ctx.version_set.current().await.level_slice[0][0].gen;
+        drop(db);
+
+        // only the SSTable is consulted to rebuild the schema
+        let table =
+            std::fs::File::open(fusio::path::path_to_local(&option.table_path(gen, 0)).unwrap())
+                .unwrap();
+        let stored = parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder::try_new(table)
+            .unwrap()
+            .schema()
+            .clone();
+        let schema = DynSchema::from_stored_arrow_schema(&stored).unwrap();
+        assert_eq!(schema.primary_index, 1);
+
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+        for id in 0..10_i64 {
+            let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(id), false);
+            let name = db
+                .get(&key, |entry| {
+                    cast_arc_value!(entry.get().columns[0].value, Option<String>).clone()
+                })
+                .await
+                .unwrap();
+            assert_eq!(name, Some(id.to_string()));
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_add_column_default() {
+        let temp_dir = TempDir::new().unwrap();
+        let schema = dyn_schema!(("id", Int64, false), ("name", String, true), 0);
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        )
+        .disable_wal(true);
+        let db: DB<DynRecord, TokioExecutor> =
+            DB::new(option.clone(), TokioExecutor::current(), schema)
+                .await
+                .unwrap();
+        for id in 0..10_i64 {
+            db.insert(dyn_record!(
+                ("id", Int64, false, id),
+                ("name", String, true, Some(id.to_string())),
+                0
+            ))
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+        assert!(!db.ctx.version_set.current().await.level_slice[0].is_empty());
+        drop(db);
+
+        let schema = dyn_schema!(("id", Int64, false), ("name", String, true), 0)
+            .add_column(
+                ValueDesc::new("score".to_string(), DataType::Int32, false).with_default(
+                    Value::new(DataType::Int32, "score".to_string(), Arc::new(0_i32), false),
+                ),
+            )
+            .unwrap()
+            .add_column(ValueDesc::new("note".to_string(), DataType::String, true))
+            .unwrap();
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+        db.insert(dyn_record!(
+            ("id", Int64, false, 10_i64),
+            ("name", String, true, Some("10".to_string())),
+            ("score", Int32, false, 7_i32),
+            ("note", String, true, Some("new".to_string())),
+            0
+        ))
+        .await
+        .unwrap();
+
+        let get = |id: i64| {
+            let db = &db;
+            async move {
+                let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(id), false);
+                db.get(&key, |entry| {
+                    let columns = entry.get().columns;
+                    Some((
+                        columns[1].get::<String>().cloned(),
+                        columns[2].get::<i32>().copied(),
+                        columns[3].get::<String>().cloned(),
+                    ))
+                })
+                .await
+                .unwrap()
+                .unwrap()
+            }
+        };
+        for id in 0..10_i64 {
+            assert_eq!(get(id).await, (Some(id.to_string()), Some(0), None));
+        }
+        assert_eq!(
+            get(10).await,
+            (Some("10".to_string()), Some(7), Some("new".to_string()))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_dyn_list_column() {
+        async fn tags(db: &DB<DynRecord, TokioExecutor>) -> Vec<Option<Vec<i32>>> {
+            db.scan((Bound::Unbounded, Bound::Unbounded), |entry| {
+                entry.get().columns[1].get::<Vec<Value>>().map(|items| {
+                    items
+                        .iter()
+                        .map(|item| *item.get::<i32>().unwrap())
+                        .collect()
+                })
+            })
+            .await
+            .map(Result::unwrap)
+            .collect()
+            .await
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let list = DataType::List(Box::new(DataType::Int32));
+        let schema = DynSchema::new(
+            vec![
+                ValueDesc::new("id".to_string(), DataType::Int64, false),
+                ValueDesc::new("tags".to_string(), list.clone(), true),
+            ],
+            0,
+        );
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+
+        let item =
+            |value: i32| Value::new(DataType::Int32, "item".to_string(), Arc::new(value), false);
+        let lists = [Some(vec![item(1), item(2), item(3)]), Some(vec![]), None];
+        for (id, tags) in lists.into_iter().enumerate() {
+            db.insert(DynRecord::new(
+                vec![
+                    Value::new(
+                        DataType::Int64,
+                        "id".to_string(),
+                        Arc::new(id as i64),
+                        false,
+                    ),
+                    Value::new(list.clone(), "tags".to_string(), Arc::new(tags), true),
+                ],
+                0,
+            ))
+            .await
+            .unwrap();
+        }
+        let expected = vec![Some(vec![1, 2, 3]), Some(vec![]), None];
+        assert_eq!(tags(&db).await, expected);
+
+        db.flush().await.unwrap();
+        assert!(!db.ctx.version_set.current().await.level_slice[0].is_empty());
+        assert_eq!(tags(&db).await, expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_entry_is_null() {
+        let temp_dir = TempDir::new().unwrap();
+        let schema = dyn_schema!(
+            ("id", Int64, false),
+            ("age", Int32, true),
+            ("height", Int32, true),
+            0
+        );
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+        db.insert(dyn_record!(
+            ("id", Int64, false, 1_i64),
+            ("age", Int32, true, None::<i32>),
+            ("height", Int32, true, Some(180_i32)),
+            0
+        ))
+        .await
+        .unwrap();
+
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(1_i64), false);
+        let nulls = |entry: TransactionEntry<'_, DynRecord>| {
+            Some((0..3).map(|index| entry.is_null(index)).collect::<Vec<_>>())
+        };
+        // read from the memtable
+        assert_eq!(
+            db.get(&key, nulls).await.unwrap(),
+            Some(vec![false, true, false])
+        );
+
+        // read from the arrow arrays of the SSTable
+        db.flush().await.unwrap();
+        assert_eq!(
+            db.get(&key, nulls).await.unwrap(),
+            Some(vec![false, true, false])
+        );
+        let scanned = db
+            .scan((Bound::Unbounded, Bound::Unbounded), nulls)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(scanned.len(), 1);
+        assert_eq!(
+            *scanned[0].as_ref().unwrap(),
+            Some(vec![false, true, false])
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_as() {
+        #[derive(Debug, PartialEq)]
+        struct User {
+            id: i32,
+            name: String,
+        }
+
+        #[derive(Debug)]
+        enum MapError {
+            #[allow(dead_code)]
+            Commit(CommitError<DynRecord>),
+            MissingName(i32),
+        }
+
+        impl From<CommitError<DynRecord>> for MapError {
+            fn from(err: CommitError<DynRecord>) -> Self {
+                MapError::Commit(err)
+            }
+        }
+
+        fn to_user(record: DynRecordRef<'_>) -> Result<User, MapError> {
+            let id = *cast_arc_value!(record.columns[0].value, i32);
+            let name = cast_arc_value!(record.columns[1].value, Option<String>)
+                .clone()
+                .ok_or(MapError::MissingName(id))?;
+            Ok(User { id, name })
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let schema = dyn_schema!(("id", Int32, false), ("name", String, true), 0);
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+        for id in 0..5_i32 {
+            let name = (id != 3).then(|| format!("user-{}", id));
+            db.insert(dyn_record!(
+                ("id", Int32, false, id),
+                ("name", String, true, name),
+                0
+            ))
+            .await
+            .unwrap();
+        }
+
+        let lower = Value::new(DataType::Int32, "id".to_string(), Arc::new(0_i32), false);
+        let upper = Value::new(DataType::Int32, "id".to_string(), Arc::new(3_i32), false);
+        let users = db
+            .scan_as((Bound::Included(&lower), Bound::Excluded(&upper)), to_user)
+            .await
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            users,
+            (0..3)
+                .map(|id| User {
+                    id,
+                    name: format!("user-{}", id),
+                })
+                .collect::<Vec<_>>()
+        );
+
+        // the error of the closure is yielded in place of the row it failed on
+        let results = db
+            .scan_as((Bound::Unbounded, Bound::Unbounded), to_user)
+            .await
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(results.len(), 5);
+        assert!(matches!(results[3], Err(MapError::MissingName(3))));
+        assert_eq!(results[4].as_ref().unwrap().name, "user-4");
+    }
+
+    /// counts the bytes read from SSTables
+    #[cfg(feature = "bytes")]
+    mod counting {
+        use std::{
+            future::Future,
+            ops::Range,
+            sync::{
+                atomic::{AtomicUsize, Ordering},
+                Arc,
+            },
+        };
+
+        use futures::future::BoxFuture;
+        use parquet::{
+            arrow::{arrow_reader::ArrowReaderOptions, async_reader::AsyncFileReader},
+            file::metadata::ParquetMetaData,
+        };
+        use parquet_lru::LruCache;
+
+        use crate::fs::FileId;
+
+        pub(crate) struct CountingReader<R> {
+            inner: R,
+            read: Arc<AtomicUsize>,
+        }
+
+        impl<R: AsyncFileReader> AsyncFileReader for CountingReader<R> {
+            fn get_bytes(
+                &mut self,
+                range: Range<u64>,
+            ) -> BoxFuture<'_, parquet::errors::Result<bytes::Bytes>> {
+                self.read
+                    .fetch_add((range.end - range.start) as usize, Ordering::Relaxed);
+                self.inner.get_bytes(range)
+            }
+
+            fn get_metadata<'s>(
+                &'s mut self,
+                options: Option<&'s ArrowReaderOptions>,
+            ) -> BoxFuture<'s, parquet::errors::Result<Arc<ParquetMetaData>>> {
+                self.inner.get_metadata(options)
+            }
+
+            fn get_byte_ranges(
+                &mut self,
+                ranges: Vec<Range<u64>>,
+            ) -> BoxFuture<'_, parquet::errors::Result<Vec<bytes::Bytes>>> {
+                let len = ranges.iter().map(|r| (r.end - r.start) as usize).sum();
+                self.read.fetch_add(len, Ordering::Relaxed);
+                self.inner.get_byte_ranges(ranges)
+            }
+        }
+
+        pub(crate) struct CountingCache {
+            pub(crate) read: Arc<AtomicUsize>,
+        }
+
+        impl LruCache<FileId> for CountingCache {
+            type LruReader<R>
+                = CountingReader<R>
+            where
+                R: AsyncFileReader + 'static;
+
+            fn get_reader<R>(
+                &self,
+                _key: FileId,
+                reader: R,
+            ) -> impl Future<Output = CountingReader<R>> + Send
+            where
+                R: AsyncFileReader + 'static,
+            {
+                let read = self.read.clone();
+                async move {
+                    CountingReader {
+                        inner: reader,
+                        read,
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "bytes")]
+    fn wide_records(schema: &DynSchema) -> Vec<DynRecord> {
+        (0..64_i64)
+            .map(|id| {
+                DynRecordBuilder::new(schema)
+                    .set("id", id)
+                    .set("n", id as i32)
+                    .set("payload", id.to_string().repeat(1024))
+                    .build()
+                    .unwrap()
+            })
+            .collect()
+    }
 
-                let col = columns.get(4).unwrap();
-                assert_eq!(col.datatype(), DataType::String);
-                assert_eq!(col.desc.name, "name".to_string());
-                let name = col.value.as_ref().downcast_ref::<Option<String>>();
-                assert!(name.is_some());
-                assert_eq!(name.unwrap(), &None);
+    #[cfg(feature = "bytes")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_get_column() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
 
-                let col = columns.get(6).unwrap();
-                assert_eq!(col.datatype(), DataType::Boolean);
-                assert_eq!(col.desc.name, "enabled".to_string());
-                let enabled = col.value.as_ref().downcast_ref::<Option<bool>>();
-                assert!(enabled.is_some());
-                assert_eq!(*enabled.unwrap(), None);
+        use counting::CountingCache;
 
-                let col = columns.get(7).unwrap();
-                assert_eq!(col.datatype(), DataType::Bytes);
-                assert_eq!(col.desc.name, "bytes".to_string());
-                let bytes = col.value.as_ref().downcast_ref::<Option<Vec<u8>>>();
-                assert!(bytes.is_some());
-                assert_eq!(bytes.unwrap(), &Some((i as i32).to_le_bytes().to_vec()));
+        let temp_dir = TempDir::new().unwrap();
 
-                let col = columns.get(8).unwrap();
-                assert_eq!(col.datatype(), DataType::Float32);
-                let v = col.value.as_ref().downcast_ref::<Option<F32>>();
-                assert!(v.is_some());
-                assert_eq!(v.unwrap(), &Some(F32::from(i as f32 * 1.11)));
+        let schema = dyn_schema!(
+            ("id", Int64, false),
+            ("n", Int32, true),
+            ("payload", String, true),
+            0
+        );
+        let records = wide_records(&schema);
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
 
-                let col = columns.get(9).unwrap();
-                assert_eq!(col.datatype(), DataType::Float64);
-                let v = col.value.as_ref().downcast_ref::<Option<F64>>();
-                assert!(v.is_some());
-                assert_eq!(v.unwrap(), &Some(F64::from(i as f64 * 1.01)));
-                i += 1
+        let read = Arc::new(AtomicUsize::new(0));
+        let db: DB<DynRecord, TokioExecutor> = DB::build(
+            Arc::new(option),
+            TokioExecutor::current(),
+            schema,
+            Arc::new(CountingCache { read: read.clone() }),
+        )
+        .await
+        .unwrap();
+        db.insert_batch(records.into_iter()).await.unwrap();
+        db.flush().await.unwrap();
+        assert!(!db.ctx.version_set.current().await.level_slice[0].is_empty());
+
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(42_i64), false);
+
+        read.store(0, Ordering::Relaxed);
+        let full = db
+            .get(&key, |entry| {
+                let record = entry.get();
+                assert!(cast_arc_value!(record.columns[2].value, Option<String>).is_some());
+                *cast_arc_value!(record.columns[1].value, Option<i32>)
+            })
+            .await
+            .unwrap();
+        let full_read = read.swap(0, Ordering::Relaxed);
+
+        let column = db
+            .get_column(&key, 1, |entry| {
+                let record = entry.get();
+                assert!(cast_arc_value!(record.columns[2].value, Option<String>).is_none());
+                *cast_arc_value!(record.columns[1].value, Option<i32>)
+            })
+            .await
+            .unwrap();
+        let column_read = read.load(Ordering::Relaxed);
+
+        assert_eq!(full, Some(42));
+        assert_eq!(column, Some(42));
+        assert!(column_read > 0);
+        assert!(column_read < full_read);
+
+        assert!(matches!(
+            db.get_column(&key, 3, |_| Some(())).await,
+            Err(CommitError::Database(DbError::UnknownColumn(_)))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_insert_record_batch() {
+        use arrow::{
+            array::{Int32Array, Int64Array, StringArray},
+            datatypes::{DataType as ArrowDataType, Field},
+        };
+
+        let temp_dir = TempDir::new().unwrap();
+        let schema = dyn_schema!(
+            ("id", Int64, false),
+            ("name", String, true),
+            ("score", Int32, false),
+            0
+        );
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+
+        let new_batch = |name: &str, scores: Vec<Option<i32>>| {
+            let fields = vec![
+                Field::new("id", ArrowDataType::Int64, false),
+                Field::new("name", ArrowDataType::Utf8, true),
+                Field::new(name, ArrowDataType::Int32, true),
+            ];
+            let len = scores.len() as i64;
+            RecordBatch::try_new(
+                Arc::new(Schema::new(fields)),
+                vec![
+                    Arc::new(Int64Array::from_iter_values(0..len)),
+                    Arc::new(StringArray::from_iter(
+                        (0..len).map(|id| (id % 7 != 0).then(|| format!("user-{}", id))),
+                    )),
+                    Arc::new(Int32Array::from(scores)),
+                ],
+            )
+            .unwrap()
+        };
+
+        let batch = new_batch("score", (0..500).map(|id| Some(id * 2)).collect());
+        db.insert_record_batch(&batch).await.unwrap();
+        for id in [0_i64, 1, 250, 499] {
+            let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(id), false);
+            let row = db
+                .get(&key, |entry| {
+                    let record = entry.get();
+                    Some((
+                        cast_arc_value!(record.columns[1].value, Option<String>).clone(),
+                        *cast_arc_value!(record.columns[2].value, Option<i32>),
+                    ))
+                })
+                .await
+                .unwrap();
+            let name = (id % 7 != 0).then(|| format!("user-{}", id));
+            assert_eq!(row, Some((name, Some(id as i32 * 2))));
+        }
+        let key = Value::new(DataType::Int64, "id".to_string(), Arc::new(500_i64), false);
+        assert!(db.get(&key, |_| Some(())).await.unwrap().is_none());
+
+        assert!(matches!(
+            db.insert_record_batch(&new_batch("points", vec![Some(1)]))
+                .await,
+            Err(CommitError::Database(DbError::InvalidRecord(
+                RecordValidationError::BatchColumnMismatch { index: 2, .. }
+            )))
+        ));
+        assert!(matches!(
+            db.insert_record_batch(&new_batch("score", vec![Some(1), None]))
+                .await,
+            Err(CommitError::Database(DbError::InvalidRecord(
+                RecordValidationError::NullValue { .. }
+            )))
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_scan_to_batches() {
+        use arrow::datatypes::{Int32Type, Int64Type};
+
+        let temp_dir = TempDir::new().unwrap();
+        let schema = dyn_schema!(
+            ("name", String, true),
+            ("id", Int64, false),
+            ("score", Int32, false),
+            1
+        );
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema,
+        );
+        let db: DB<DynRecord, TokioExecutor> = DB::new(option, TokioExecutor::current(), schema)
+            .await
+            .unwrap();
+
+        let new_record = |id: i32| {
+            let name = (id % 7 != 0).then(|| format!("user-{}", id));
+            dyn_record!(
+                ("name", String, true, name),
+                ("id", Int64, false, id as i64),
+                ("score", Int32, false, id * 2),
+                1
+            )
+        };
+        db.insert_batch((0..150).map(new_record)).await.unwrap();
+        db.flush().await.unwrap();
+        db.insert_batch((150..260).map(new_record)).await.unwrap();
+        for id in 250..260 {
+            db.remove(Value::new(
+                DataType::Int64,
+                "id".to_string(),
+                Arc::new(id as i64),
+                false,
+            ))
+            .await
+            .unwrap();
+        }
+
+        let batches = db
+            .scan_to_batches((Bound::Unbounded, Bound::Unbounded), 100)
+            .await
+            .try_collect::<Vec<_>>()
+            .await
+            .unwrap();
+        assert_eq!(
+            batches
+                .iter()
+                .map(RecordBatch::num_rows)
+                .collect::<Vec<_>>(),
+            vec![100, 100, 50]
+        );
+
+        let mut id = 0;
+        for batch in batches {
+            let schema = batch.schema();
+            let names = schema.fields().iter().map(|field| field.name().as_str());
+            assert_eq!(names.collect::<Vec<_>>(), vec!["name", "id", "score"]);
+            let name_column = batch.column(0).as_string::<i32>();
+            let id_column = batch.column(1).as_primitive::<Int64Type>();
+            let score_column = batch.column(2).as_primitive::<Int32Type>();
+            for row in 0..batch.num_rows() {
+                assert_eq!(id_column.value(row), id as i64);
+                assert_eq!(name_column.is_null(row), id % 7 == 0);
+                if id % 7 != 0 {
+                    assert_eq!(name_column.value(row), format!("user-{}", id));
+                }
+                assert_eq!(score_column.value(row), id * 2);
+                id += 1;
+            }
+        }
+        assert_eq!(id, 250);
+    }
+
+    #[cfg(feature = "bytes")]
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_with_projection() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        use counting::CountingCache;
+
+        let schema = || {
+            dyn_schema!(
+                ("id", Int64, false),
+                ("n", Int32, true),
+                ("payload", String, true),
+                0
+            )
+        };
+
+        let mut reads = Vec::new();
+        for projection in [None, Some(["n"])] {
+            let temp_dir = TempDir::new().unwrap();
+            let mut option = DbOption::new(
+                Path::from_filesystem_path(temp_dir.path()).unwrap(),
+                &schema(),
+            );
+            if let Some(projection) = projection {
+                option = option.with_projection(&projection);
+            }
+            option.immutable_chunk_num = 1;
+            option.immutable_chunk_max_num = 1;
+
+            let read = Arc::new(AtomicUsize::new(0));
+            let db: DB<DynRecord, TokioExecutor> = DB::build(
+                Arc::new(option),
+                TokioExecutor::current(),
+                schema(),
+                Arc::new(CountingCache { read: read.clone() }),
+            )
+            .await
+            .unwrap();
+            db.insert_batch(wide_records(&schema()).into_iter())
+                .await
+                .unwrap();
+            db.flush().await.unwrap();
+
+            read.store(0, Ordering::Relaxed);
+            let txn = db.transaction().await;
+            let mut scan = txn
+                .scan((Bound::Unbounded, Bound::Unbounded))
+                .take()
+                .await
+                .unwrap();
+            let mut count = 0;
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                let record = entry.value().unwrap();
+                assert_eq!(
+                    *cast_arc_value!(record.columns[1].value, Option<i32>),
+                    Some(count)
+                );
+                assert_eq!(
+                    cast_arc_value!(record.columns[2].value, Option<String>).is_some(),
+                    projection.is_none()
+                );
+                count += 1;
+            }
+            assert_eq!(count, 64);
+            reads.push(read.load(Ordering::Relaxed));
+        }
+        assert!(reads[1] < reads[0]);
+
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &schema(),
+        )
+        .with_projection(&["missing"]);
+        assert!(matches!(
+            DB::<DynRecord, TokioExecutor>::new(option, TokioExecutor::current(), schema()).await,
+            Err(DbError::UnknownColumn(name)) if name == "missing"
+        ));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remove_range() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let key = |i: u32| format!("{:02}", i);
+        let insert = |i: u32| {
+            db.insert(Test {
+                vstring: key(i),
+                vu32: i,
+                vbool: None,
+            })
+        };
+        // two SSTables and the memtable
+        for i in 0..30 {
+            insert(i).await.unwrap();
+            if i == 9 || i == 19 {
+                db.flush().await.unwrap();
+            }
+        }
+        assert_eq!(db.ctx.version_set.current().await.level_slice[0].len(), 2);
+        let scan_keys = || {
+            let db = &db;
+            async move {
+                let txn = db.transaction().await;
+                let mut scan = txn
+                    .scan((Bound::Unbounded, Bound::Unbounded))
+                    .take()
+                    .await
+                    .unwrap();
+                let mut keys = Vec::new();
+                while let Some(entry) = scan.next().await.transpose().unwrap() {
+                    if let Some(record) = entry.value() {
+                        keys.push(record.vu32.unwrap());
+                    }
+                }
+                keys
             }
+        };
+
+        let (lower, upper) = (key(5), key(25));
+        let removed = db.remove_range((Bound::Included(&lower), Bound::Excluded(&upper)));
+        assert_eq!(removed.await.unwrap(), 20);
+        assert_eq!(scan_keys().await, (0..5).chain(25..30).collect::<Vec<_>>());
+        // the keys of the range are already removed
+        let removed = db.remove_range((Bound::Included(&lower), Bound::Excluded(&upper)));
+        assert_eq!(removed.await.unwrap(), 0);
+
+        let lower = key(26);
+        let removed = db.remove_range((Bound::Excluded(&lower), Bound::Unbounded));
+        assert_eq!(removed.await.unwrap(), 3);
+        let upper = key(1);
+        let removed = db.remove_range((Bound::Unbounded, Bound::Included(&upper)));
+        assert_eq!(removed.await.unwrap(), 2);
+        assert_eq!(scan_keys().await, vec![2, 3, 4, 25, 26]);
+
+        // empty ranges
+        let (lower, upper) = (key(3), key(2));
+        let removed = db.remove_range((Bound::Included(&lower), Bound::Included(&upper)));
+        assert_eq!(removed.await.unwrap(), 0);
+        let removed = db.remove_range((Bound::Included(&lower), Bound::Excluded(&lower)));
+        assert_eq!(removed.await.unwrap(), 0);
+        let removed = db.remove_range((Bound::Excluded(&lower), Bound::Included(&lower)));
+        assert_eq!(removed.await.unwrap(), 0);
+        assert_eq!(scan_keys().await, vec![2, 3, 4, 25, 26]);
+
+        // a key inserted after the delete is visible again, also once flushed
+        insert(10).await.unwrap();
+        assert_eq!(scan_keys().await, vec![2, 3, 4, 10, 25, 26]);
+        db.flush().await.unwrap();
+        assert_eq!(scan_keys().await, vec![2, 3, 4, 10, 25, 26]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_remove_range_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let key = |i: usize| format!("{:05}", i);
+        let total = REMOVE_RANGE_CHUNK * 2 + 10;
+        db.insert_batch((0..total).map(|i| Test {
+            vstring: key(i),
+            vu32: i as u32,
+            vbool: None,
+        }))
+        .await
+        .unwrap();
+        db.flush().await.unwrap();
+        // removed keys are walked over but not counted again
+        for i in (0..total).step_by(100) {
+            db.remove(key(i)).await.unwrap();
+        }
+
+        let removed = db.remove_range((Bound::Unbounded, Bound::Unbounded));
+        assert_eq!(removed.await.unwrap(), total - total.div_ceil(100));
+        let txn = db.transaction().await;
+        let mut scan = txn
+            .scan((Bound::Unbounded, Bound::Unbounded))
+            .take()
+            .await
+            .unwrap();
+        while let Some(entry) = scan.next().await.transpose().unwrap() {
+            assert!(entry.value().is_none());
         }
     }
 
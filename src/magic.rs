@@ -1,2 +1,6 @@
 pub const TS: &str = "_ts";
+/// virtual column holding the level of the SSTable a scanned row was read from
+pub const LEVEL: &str = "_level";
+/// virtual column holding the id of the SSTable a scanned row was read from
+pub const FILE_ID: &str = "_file_id";
 pub(crate) const USER_COLUMN_OFFSET: usize = 2;
@@ -1,4 +1,4 @@
-use std::ops::Bound;
+use std::{ops::Bound, sync::Arc};
 
 use arrow::{
     array::{BooleanArray, Datum},
@@ -19,10 +19,23 @@ use crate::{
     timestamp::Timestamp,
 };
 
-unsafe fn get_range_bound_fn<R>(
+#[cfg(test)]
+thread_local! {
+    /// number of bound datums built by [`get_range_filter`] on this thread
+    pub(crate) static BOUND_DATUM_BUILDS: std::cell::Cell<usize> =
+        const { std::cell::Cell::new(0) };
+}
+
+fn bound_datum<K: Key>(key: &K) -> Arc<dyn Datum + Send + Sync> {
+    #[cfg(test)]
+    BOUND_DATUM_BUILDS.with(|builds| builds.set(builds.get() + 1));
+    key.to_arrow_datum()
+}
+
+fn get_range_bound_fn<R>(
     range: Bound<&<R::Schema as Schema>::Key>,
 ) -> (
-    Option<&'static <R::Schema as Schema>::Key>,
+    Option<&<R::Schema as Schema>::Key>,
     &'static (dyn Fn(&dyn Datum, &dyn Datum) -> Result<BooleanArray, ArrowError> + Sync),
 )
 where
@@ -32,11 +45,11 @@ where
     let key = match range {
         Bound::Included(key) => {
             cmp = &gt_eq;
-            Some(&*(key as *const _))
+            Some(key)
         }
         Bound::Excluded(key) => {
             cmp = &gt;
-            Some(&*(key as *const _))
+            Some(key)
         }
         Bound::Unbounded => {
             cmp = &|this, _| {
@@ -52,7 +65,9 @@ where
     (key, cmp)
 }
 
-pub(crate) unsafe fn get_range_filter<R>(
+/// build the row filter of a scan. The bound datums are built once here and shared by every
+/// batch the filter is evaluated on
+pub(crate) fn get_range_filter<R>(
     schema_descriptor: &SchemaDescriptor,
     range: (
         Bound<&<R::Schema as Schema>::Key>,
@@ -66,26 +81,98 @@ where
     let (lower_key, lower_cmp) = get_range_bound_fn::<R>(range.0);
     let (upper_key, upper_cmp) = get_range_bound_fn::<R>(range.1);
 
+    let ts = ts.to_arrow_scalar();
     let mut predictions: Vec<Box<dyn ArrowPredicate>> = vec![Box::new(ArrowPredicateFn::new(
         ProjectionMask::roots(schema_descriptor, [1]),
-        move |record_batch| lt_eq(record_batch.column(0), &ts.to_arrow_scalar() as &dyn Datum),
+        move |record_batch| lt_eq(record_batch.column(0), &ts as &dyn Datum),
     ))];
     if let Some(lower_key) = lower_key {
+        let lower = bound_datum(lower_key);
         predictions.push(Box::new(ArrowPredicateFn::new(
             ProjectionMask::roots(schema_descriptor, [2]),
-            move |record_batch| {
-                lower_cmp(record_batch.column(0), lower_key.to_arrow_datum().as_ref())
-            },
+            move |record_batch| lower_cmp(record_batch.column(0), lower.as_ref()),
         )));
     }
     if let Some(upper_key) = upper_key {
+        let upper = bound_datum(upper_key);
         predictions.push(Box::new(ArrowPredicateFn::new(
             ProjectionMask::roots(schema_descriptor, [2]),
-            move |record_batch| {
-                upper_cmp(upper_key.to_arrow_datum().as_ref(), record_batch.column(0))
-            },
+            move |record_batch| upper_cmp(upper.as_ref(), record_batch.column(0)),
         )));
     }
 
     RowFilter::new(predictions)
 }
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::ops::Bound;
+
+    use fusio::path::Path;
+    use futures_util::StreamExt;
+    use parquet::file::properties::WriterProperties;
+    use tempfile::TempDir;
+
+    use super::BOUND_DATUM_BUILDS;
+    use crate::{
+        executor::tokio::TokioExecutor, inmem::immutable::tests::TestSchema, tests::Test, DbOption,
+        DB,
+    };
+
+    /// scan `rows` rows spread over row groups of 8 rows and return how many bound datums were
+    /// built
+    async fn bound_datum_builds(rows: u32) -> usize {
+        let temp_dir = TempDir::new().unwrap();
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        option.write_parquet_properties = WriterProperties::builder()
+            .set_max_row_group_size(8)
+            .build();
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+        for i in 0..rows {
+            db.insert(Test {
+                vstring: format!("{:05}", i),
+                vu32: i,
+                vbool: None,
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+
+        let lower = format!("{:05}", 0);
+        let upper = format!("{:05}", rows);
+        BOUND_DATUM_BUILDS.with(|builds| builds.set(0));
+
+        let txn = db.transaction().await;
+        let mut scan = txn
+            .scan((Bound::Included(&lower), Bound::Excluded(&upper)))
+            .take()
+            .await
+            .unwrap();
+        let mut count = 0;
+        while let Some(entry) = scan.next().await {
+            entry.unwrap();
+            count += 1;
+        }
+        assert_eq!(count, rows as usize);
+
+        BOUND_DATUM_BUILDS.with(|builds| builds.get())
+    }
+
+    // the counter is thread local, the scan is set up and polled on the test thread
+    #[tokio::test(flavor = "multi_thread")]
+    async fn bound_datum_is_built_once_per_table() {
+        let few = bound_datum_builds(16).await;
+        let many = bound_datum_builds(512).await;
+
+        // one lower and one upper bound for the single flushed table
+        assert_eq!(few, 2);
+        assert_eq!(many, few);
+    }
+}
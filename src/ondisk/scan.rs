@@ -14,6 +14,7 @@ use parquet::arrow::{
 use pin_project_lite::pin_project;
 
 use crate::{
+    fs::FileId,
     record::Record,
     stream::record_batch::{RecordBatchEntry, RecordBatchIterator},
 };
@@ -26,6 +27,7 @@ pin_project! {
         iter: Option<RecordBatchIterator<R>>,
         projection_mask: ProjectionMask,
         full_schema: Arc<Schema>,
+        location: Option<(usize, FileId)>,
         _marker: PhantomData<&'scan ()>
     }
 }
@@ -41,9 +43,18 @@ impl<R> SsTableScan<'_, R> {
             iter: None,
             projection_mask,
             full_schema,
+            location: None,
             _marker: PhantomData,
         }
     }
+
+    /// tag every entry of the scan with the level and id of the SSTable
+    pub(crate) fn located(self, level: usize, gen: FileId) -> Self {
+        SsTableScan {
+            location: Some((level, gen)),
+            ..self
+        }
+    }
 }
 
 impl<'scan, R> Stream for SsTableScan<'scan, R>
@@ -58,7 +69,7 @@ where
             match this.iter {
                 Some(iter) => {
                     if let Some(entry) = iter.next() {
-                        return Poll::Ready(Some(Ok(entry)));
+                        return Poll::Ready(Some(Ok(entry.located(*this.location))));
                     }
                     *this.iter = None;
                 }
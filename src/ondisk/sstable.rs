@@ -1,5 +1,6 @@
 use std::{marker::PhantomData, ops::Bound, sync::Arc};
 
+use arrow::datatypes::Schema as ArrowSchema;
 use fusio::{dynamic::DynFile, DynRead};
 use fusio_parquet::reader::AsyncReader;
 use futures_util::StreamExt;
@@ -26,6 +27,7 @@ where
     R: Record,
 {
     reader: BoxedFileReader,
+    schema: Option<Arc<ArrowSchema>>,
     _marker: PhantomData<R>,
 }
 
@@ -47,10 +49,21 @@ where
                     BoxedFileReader::new(AsyncReader::new(file, size).await?),
                 )
                 .await,
+            schema: None,
             _marker: PhantomData,
         })
     }
 
+    /// read records with `schema` instead of the schema stored in the file, columns the file
+    /// does not have are filled with their default, see
+    /// [`DynSchema::add_column`](crate::record::DynSchema::add_column)
+    pub(crate) fn with_schema(self, schema: Arc<ArrowSchema>) -> Self {
+        Self {
+            schema: Some(schema),
+            ..self
+        }
+    }
+
     async fn into_parquet_builder(
         self,
         limit: Option<usize>,
@@ -95,16 +108,15 @@ where
         limit: Option<usize>,
         projection_mask: ProjectionMask,
     ) -> Result<SsTableScan<'scan, R>, parquet::errors::ParquetError> {
+        let schema = self.schema.clone();
         let builder = self
             .into_parquet_builder(limit, projection_mask.clone())
             .await?;
 
         let schema_descriptor = builder.metadata().file_metadata().schema_descr();
-        let full_schema = builder.schema().clone();
+        let full_schema = schema.unwrap_or_else(|| builder.schema().clone());
 
-        // Safety: filter's lifetime relies on range's lifetime, sstable must not live longer than
-        // it
-        let filter = unsafe { get_range_filter::<R>(schema_descriptor, range, ts) };
+        let filter = get_range_filter::<R>(schema_descriptor, range, ts);
 
         Ok(SsTableScan::new(
             builder.with_row_filter(filter).build()?,
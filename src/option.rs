@@ -1,4 +1,7 @@
-use std::fmt::{Debug, Formatter};
+use std::{
+    collections::HashMap,
+    fmt::{Debug, Formatter},
+};
 
 pub use fusio::path::Path;
 #[cfg(feature = "aws")]
@@ -7,6 +10,7 @@ pub use fusio_dispatch::FsOptions;
 use parquet::{
     basic::Compression,
     file::properties::{EnabledStatistics, WriterProperties},
+    schema::types::ColumnPath,
 };
 use thiserror::Error;
 
@@ -24,6 +28,48 @@ pub enum CompactionOption {
     Leveled,
 }
 
+/// when the [`DB`](crate::DB) runs major compaction, flushing memtables to level 0 is not affected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompactionMode {
+    /// writes that fill the mutable memtable return right away, the memtable is flushed and
+    /// compacted by a background task
+    #[default]
+    Background,
+    /// major compaction only runs when [`DB::compact`](crate::DB::compact) is called
+    Manual,
+    /// writes that fill the mutable memtable wait until it is flushed and the eligible
+    /// compaction is done
+    Synchronous,
+}
+
+/// when removals are applied to the records they shadow
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TombstoneMode {
+    /// removals are kept by compaction and skipped by reads, which merge them with the records
+    /// they shadow
+    #[default]
+    Lazy,
+    /// major compaction drops removals, along with the older versions of their keys, once no
+    /// table left in the output level or below holds their key range. Reads over removed ranges
+    /// merge fewer entries, at the cost of the extra checks during compaction. Snapshots older
+    /// than a dropped removal no longer see the versions it shadowed.
+    Eager,
+}
+
+/// filename format of SSTables written by the [`DB`](crate::DB)
+///
+/// The manifest stays the source of truth: every SSTable is located through the `(gen, level)`
+/// recorded in the version log, so the scheme only changes how the file is named on disk and must
+/// not be changed for an existing [`DB`](crate::DB).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SstableNaming {
+    /// `{gen}.parquet`
+    #[default]
+    Ulid,
+    /// `L{level}-{gen}.parquet`, `gen` is monotonic so files of a level sort by creation order
+    Leveled,
+}
+
 /// configure the operating parameters of each component in the [`DB`](crate::DB)
 #[derive(Clone)]
 pub struct DbOption {
@@ -38,12 +84,24 @@ pub struct DbOption {
     pub(crate) major_l_selection_table_max_num: usize,
     pub(crate) major_threshold_with_sst_size: usize,
     pub(crate) max_sst_file_size: usize,
+    pub(crate) max_files_per_level: Option<usize>,
     pub(crate) version_log_snapshot_threshold: u32,
     pub(crate) trigger_type: TriggerType,
     pub(crate) use_wal: bool,
     pub(crate) wal_buffer_size: usize,
+    pub(crate) wal_segment_size: Option<usize>,
+    pub(crate) max_wal_bytes: Option<usize>,
+    pub(crate) versions_per_key: usize,
+    pub(crate) projection: Option<Vec<String>>,
     pub(crate) write_parquet_properties: WriterProperties,
+    pub(crate) column_dictionary_enabled: HashMap<ColumnPath, bool>,
     pub(crate) compaction_option: CompactionOption,
+    pub(crate) compaction_mode: CompactionMode,
+    pub(crate) tombstone_mode: TombstoneMode,
+    pub(crate) sstable_naming: SstableNaming,
+    pub(crate) quarantine_sstables: bool,
+    pub(crate) read_concurrency: usize,
+    pub(crate) verify_compaction: bool,
 }
 
 impl DbOption {
@@ -57,6 +115,7 @@ impl DbOption {
             major_threshold_with_sst_size: 4,
             level_sst_magnification: 10,
             max_sst_file_size: 256 * 1024 * 1024,
+            max_files_per_level: None,
             clean_channel_buffer: 10,
             base_path,
             write_parquet_properties: WriterProperties::builder()
@@ -66,9 +125,14 @@ impl DbOption {
                 .set_sorting_columns(Some(sorting_columns))
                 .set_created_by(concat!("tonbo version ", env!("CARGO_PKG_VERSION")).to_owned())
                 .build(),
+            column_dictionary_enabled: HashMap::new(),
 
             use_wal: true,
             wal_buffer_size: DEFAULT_WAL_BUFFER_SIZE,
+            wal_segment_size: None,
+            max_wal_bytes: None,
+            versions_per_key: 1,
+            projection: None,
             major_default_oldest_table_num: 3,
             major_l_selection_table_max_num: 4,
             trigger_type: TriggerType::SizeOfMem(64 * 1024 * 1024),
@@ -76,6 +140,12 @@ impl DbOption {
             level_paths: vec![None; MAX_LEVEL],
             base_fs: FsOptions::Local,
             compaction_option: CompactionOption::Leveled,
+            compaction_mode: CompactionMode::Background,
+            tombstone_mode: TombstoneMode::Lazy,
+            sstable_naming: SstableNaming::Ulid,
+            quarantine_sstables: false,
+            read_concurrency: 1,
+            verify_compaction: false,
         }
     }
 }
@@ -113,6 +183,18 @@ impl DbOption {
         }
     }
 
+    /// upper bound of the number of SSTables in each level, on top of the threshold given by
+    /// `major_threshold_with_sst_size` and `level_sst_magnification`. A level reaching it is
+    /// merged into the next one, so a lower bound means fewer SSTables to look a key up in at the
+    /// cost of more compaction work. The bound is at least 1 and doesn't apply to the last two
+    /// levels, which are never compacted further.
+    pub fn max_files_per_level(self, max_files_per_level: usize) -> Self {
+        DbOption {
+            max_files_per_level: Some(max_files_per_level.max(1)),
+            ..self
+        }
+    }
+
     /// Maximum size of each parquet
     pub fn max_sst_file_size(self, max_sst_file_size: usize) -> Self {
         DbOption {
@@ -137,12 +219,21 @@ impl DbOption {
         }
     }
 
-    /// disable WAL
+    /// enable or disable dictionary encoding of `column` in SSTables, over the setting of
+    /// [`DbOption::write_parquet_option`]. Dictionary encoding helps columns with few distinct
+    /// values and costs space for the others, columns that are not set keep the parquet default
+    pub fn column_dictionary_enabled(mut self, column: ColumnPath, enabled: bool) -> Self {
+        self.column_dictionary_enabled.insert(column, enabled);
+        self
+    }
+
+    /// disable WAL, inserts then skip the WAL entirely
     ///
-    /// tips: risk of data loss during downtime
-    pub fn disable_wal(self) -> Self {
+    /// tips: data is only durable after [`DB::flush`](crate::DB::flush), everything still in memory
+    /// is lost if the process crashes. Only use it for data that can be rebuilt.
+    pub fn disable_wal(self, disable_wal: bool) -> Self {
         DbOption {
-            use_wal: false,
+            use_wal: !disable_wal,
             ..self
         }
     }
@@ -157,6 +248,47 @@ impl DbOption {
         }
     }
 
+    /// Maximum size of a single WAL segment, unbounded by default
+    ///
+    /// Once the active segment grows past this size, writes roll over into a new segment.
+    /// Segments are removed after the memtable they belong to has been flushed into SSTables.
+    pub fn wal_segment_size(self, wal_segment_size: usize) -> Self {
+        DbOption {
+            wal_segment_size: Some(wal_segment_size),
+            ..self
+        }
+    }
+
+    /// freeze the memtable once its WAL grows past `max_wal_bytes`, even if the memtable itself
+    /// is still below its size threshold. This bounds the WAL replayed on recovery when many
+    /// small records are written.
+    pub fn max_wal_bytes(self, max_wal_bytes: usize) -> Self {
+        DbOption {
+            max_wal_bytes: Some(max_wal_bytes),
+            ..self
+        }
+    }
+
+    /// number of the newest versions of each key that survive major compaction, older versions
+    /// are dropped. Default to 1, which only keeps the latest version.
+    pub fn versions_per_key(self, versions_per_key: usize) -> Self {
+        DbOption {
+            versions_per_key,
+            ..self
+        }
+    }
+
+    /// only read the primary key and the given columns, other columns are skipped at the Parquet
+    /// level and are always `None` in the records returned by [`DB`](crate::DB).
+    ///
+    /// Records are still written with all columns.
+    pub fn with_projection(self, columns: &[&str]) -> Self {
+        DbOption {
+            projection: Some(columns.iter().map(|column| column.to_string()).collect()),
+            ..self
+        }
+    }
+
     /// When selecting the compaction level during major compaction, if there are no sstables with
     /// intersecting targets, the oldest sstables will be selected by default.
     pub fn major_default_oldest_table_num(self, major_default_oldest_table_num: usize) -> Self {
@@ -203,6 +335,64 @@ impl DbOption {
             ..self
         }
     }
+
+    /// filename format of SSTables, default is [`SstableNaming::Ulid`]
+    pub fn sstable_naming(self, sstable_naming: SstableNaming) -> Self {
+        DbOption {
+            sstable_naming,
+            ..self
+        }
+    }
+
+    /// when major compaction runs, see [`CompactionMode`]. Defaults to
+    /// [`CompactionMode::Background`]
+    pub fn compaction_mode(self, compaction_mode: CompactionMode) -> Self {
+        DbOption {
+            compaction_mode,
+            ..self
+        }
+    }
+
+    /// when removals are applied, see [`TombstoneMode`]. Defaults to [`TombstoneMode::Lazy`]
+    pub fn tombstone_mode(self, tombstone_mode: TombstoneMode) -> Self {
+        DbOption {
+            tombstone_mode,
+            ..self
+        }
+    }
+
+    /// skip SSTables that scans fail to open instead of failing the scan. A skipped SSTable is
+    /// logged and reported by [`DB::verify`](crate::DB::verify) along with its key range, which
+    /// may be missing from scan results. Each scan reports the ranges it skipped through
+    /// [`Scan::gaps`](crate::Scan::gaps). Errors in the middle of reading a table still fail the
+    /// scan. Disabled by default.
+    pub fn quarantine_unreadable_sstables(self, quarantine_sstables: bool) -> Self {
+        DbOption {
+            quarantine_sstables,
+            ..self
+        }
+    }
+
+    /// the number of SSTables a scan opens concurrently, opening a table fetches its parquet
+    /// metadata, which dominates the latency of scans on remote storage like S3. Results are
+    /// merged in the same order regardless of the concurrency. Default is 1.
+    pub fn read_concurrency(self, read_concurrency: usize) -> Self {
+        DbOption {
+            read_concurrency: read_concurrency.max(1),
+            ..self
+        }
+    }
+
+    /// read back the tables written by a major compaction and check that they hold the same live
+    /// keys as its inputs, keys whose newest version is not a removal. On a mismatch the new
+    /// tables are deleted and the compaction fails, keeping its inputs. Doubles the reads of a
+    /// compaction. Disabled by default.
+    pub fn verify_compaction(self, verify_compaction: bool) -> Self {
+        DbOption {
+            verify_compaction,
+            ..self
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -210,12 +400,32 @@ impl DbOption {
 pub struct ExceedsMaxLevel;
 
 impl DbOption {
+    /// the writer properties of SSTables, with the dictionary encoding of each column set by
+    /// [`DbOption::column_dictionary_enabled`]
+    pub(crate) fn sstable_writer_properties(&self) -> WriterProperties {
+        if self.column_dictionary_enabled.is_empty() {
+            return self.write_parquet_properties.clone();
+        }
+        self.column_dictionary_enabled
+            .iter()
+            .fold(
+                self.write_parquet_properties.clone().into_builder(),
+                |builder, (column, enabled)| {
+                    builder.set_column_dictionary_enabled(column.clone(), *enabled)
+                },
+            )
+            .build()
+    }
+
     pub(crate) fn table_path(&self, gen: FileId, level: usize) -> Path {
         self.level_paths[level]
             .as_ref()
             .map(|(path, _)| path)
             .unwrap_or(&self.base_path)
-            .child(format!("{}.{}", gen, FileType::Parquet))
+            .child(match self.sstable_naming {
+                SstableNaming::Ulid => format!("{}.{}", gen, FileType::Parquet),
+                SstableNaming::Leveled => format!("L{}-{}.{}", level, gen, FileType::Parquet),
+            })
     }
 
     pub(crate) fn wal_dir_path(&self) -> Path {
@@ -264,13 +474,25 @@ impl Debug for DbOption {
                 &self.major_threshold_with_sst_size,
             )
             .field("max_sst_file_size", &self.max_sst_file_size)
+            .field("max_files_per_level", &self.max_files_per_level)
             .field(
                 "version_log_snapshot_threshold",
                 &self.version_log_snapshot_threshold,
             )
             .field("trigger_type", &self.trigger_type)
             .field("use_wal", &self.use_wal)
+            .field("wal_segment_size", &self.wal_segment_size)
+            .field("max_wal_bytes", &self.max_wal_bytes)
+            .field("versions_per_key", &self.versions_per_key)
+            .field("projection", &self.projection)
             .field("write_parquet_properties", &self.write_parquet_properties)
+            .field("column_dictionary_enabled", &self.column_dictionary_enabled)
+            .field("compaction_mode", &self.compaction_mode)
+            .field("tombstone_mode", &self.tombstone_mode)
+            .field("sstable_naming", &self.sstable_naming)
+            .field("quarantine_sstables", &self.quarantine_sstables)
+            .field("read_concurrency", &self.read_concurrency)
+            .field("verify_compaction", &self.verify_compaction)
             .finish()
     }
 }
@@ -0,0 +1,366 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    str::FromStr,
+    sync::Arc,
+};
+
+use arrow::array::{BinaryArray, Datum};
+use fusio::{SeqRead, Write};
+use fusio_log::{Decode, Encode};
+use thiserror::Error;
+
+use super::{Key, KeyRef, OrderedEncode};
+
+const NEGATIVE: u8 = 0;
+const ZERO: u8 = 1;
+const POSITIVE: u8 = 2;
+/// the sign byte and the length of the magnitude
+const HEADER_LEN: usize = 1 + size_of::<u32>();
+
+/// An arbitrary-precision integer.
+///
+/// It is stored in a sign-magnitude encoding that sorts like the integer when compared
+/// byte-wise: a sign byte, the length of the magnitude as a big-endian `u32` and the big-endian
+/// magnitude without leading zeros. The length and magnitude of negative integers are inverted,
+/// so a larger magnitude sorts first. Arrow stores the encoding in a binary column, whose
+/// comparisons therefore order integers numerically.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BigInt {
+    pub(crate) bytes: Vec<u8>,
+}
+
+impl BigInt {
+    /// build an integer from its sign and its big-endian magnitude, leading zeros are ignored
+    pub fn from_bytes_be(negative: bool, magnitude: &[u8]) -> Self {
+        let start = magnitude
+            .iter()
+            .position(|byte| *byte != 0)
+            .unwrap_or(magnitude.len());
+        let magnitude = &magnitude[start..];
+        if magnitude.is_empty() {
+            return Self::default();
+        }
+
+        let len = u32::try_from(magnitude.len()).expect("magnitude exceeds u32::MAX bytes");
+        let mut bytes = Vec::with_capacity(HEADER_LEN + magnitude.len());
+        if negative {
+            bytes.push(NEGATIVE);
+            bytes.extend_from_slice(&(!len).to_be_bytes());
+            bytes.extend(magnitude.iter().map(|byte| !byte));
+        } else {
+            bytes.push(POSITIVE);
+            bytes.extend_from_slice(&len.to_be_bytes());
+            bytes.extend_from_slice(magnitude);
+        }
+        Self { bytes }
+    }
+
+    /// the sign and the big-endian magnitude without leading zeros, zero has an empty magnitude
+    pub fn to_bytes_be(&self) -> (bool, Vec<u8>) {
+        match self.bytes[0] {
+            NEGATIVE => (
+                true,
+                self.bytes[HEADER_LEN..].iter().map(|byte| !byte).collect(),
+            ),
+            POSITIVE => (false, self.bytes[HEADER_LEN..].to_vec()),
+            _ => (false, Vec::new()),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.bytes[0] == NEGATIVE
+    }
+
+    /// the order-preserving encoding of the integer
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// build an integer from the output of [`BigInt::as_bytes`], returns `None` if `bytes` is
+    /// not a valid encoding
+    pub fn from_encoded(bytes: &[u8]) -> Option<Self> {
+        let (sign, rest) = bytes.split_first()?;
+        match *sign {
+            ZERO if rest.is_empty() => {}
+            NEGATIVE | POSITIVE if bytes.len() > HEADER_LEN => {
+                let (len, magnitude) = rest.split_at(HEADER_LEN - 1);
+                let mut len = u32::from_be_bytes(len.try_into().unwrap());
+                let mut leading = magnitude[0];
+                if *sign == NEGATIVE {
+                    len = !len;
+                    leading = !leading;
+                }
+                if len as usize != magnitude.len() || leading == 0 {
+                    return None;
+                }
+            }
+            _ => return None,
+        }
+        Some(Self::from_encoded_unchecked(bytes))
+    }
+
+    /// build an integer from an encoding written by tonbo, e.g. read from a [`BigInt`] column
+    pub(crate) fn from_encoded_unchecked(bytes: &[u8]) -> Self {
+        Self {
+            bytes: bytes.to_vec(),
+        }
+    }
+}
+
+impl Default for BigInt {
+    fn default() -> Self {
+        Self { bytes: vec![ZERO] }
+    }
+}
+
+impl AsRef<[u8]> for BigInt {
+    /// the order-preserving encoding of the integer
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl From<u128> for BigInt {
+    fn from(value: u128) -> Self {
+        Self::from_bytes_be(false, &value.to_be_bytes())
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(value: i128) -> Self {
+        Self::from_bytes_be(value < 0, &value.unsigned_abs().to_be_bytes())
+    }
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum ParseBigIntError {
+    #[error("cannot parse integer from empty string")]
+    Empty,
+    #[error("invalid digit found in string")]
+    InvalidDigit,
+}
+
+impl FromStr for BigInt {
+    type Err = ParseBigIntError;
+
+    /// parse a decimal integer with an optional `+` or `-` sign
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, digits) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        if digits.is_empty() {
+            return Err(ParseBigIntError::Empty);
+        }
+
+        // big-endian, a carry past the most significant byte becomes a new leading byte
+        let mut magnitude = Vec::<u8>::new();
+        for digit in digits.bytes() {
+            if !digit.is_ascii_digit() {
+                return Err(ParseBigIntError::InvalidDigit);
+            }
+            let mut carry = u16::from(digit - b'0');
+            for byte in magnitude.iter_mut().rev() {
+                let value = u16::from(*byte) * 10 + carry;
+                *byte = value as u8;
+                carry = value >> 8;
+            }
+            if carry > 0 {
+                magnitude.insert(0, carry as u8);
+            }
+        }
+        Ok(Self::from_bytes_be(negative, &magnitude))
+    }
+}
+
+impl Display for BigInt {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (negative, mut magnitude) = self.to_bytes_be();
+        if magnitude.is_empty() {
+            return f.pad("0");
+        }
+
+        // long division by 10 yields the decimal digits from the least significant one
+        let mut digits = Vec::new();
+        while !magnitude.is_empty() {
+            let mut remainder = 0_u16;
+            for byte in magnitude.iter_mut() {
+                let value = (remainder << 8) | u16::from(*byte);
+                *byte = (value / 10) as u8;
+                remainder = value % 10;
+            }
+            digits.push(b'0' + remainder as u8);
+            let start = magnitude
+                .iter()
+                .position(|byte| *byte != 0)
+                .unwrap_or(magnitude.len());
+            magnitude.drain(..start);
+        }
+        if negative {
+            digits.push(b'-');
+        }
+        digits.reverse();
+        f.pad(std::str::from_utf8(&digits).unwrap())
+    }
+}
+
+impl Key for BigInt {
+    type Ref<'r> = BigInt;
+
+    fn as_key_ref(&self) -> Self::Ref<'_> {
+        self.clone()
+    }
+
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
+        Arc::new(BinaryArray::new_scalar(&self.bytes))
+    }
+}
+
+impl<'r> KeyRef<'r> for BigInt {
+    type Key = BigInt;
+
+    fn to_key(self) -> Self::Key {
+        self
+    }
+}
+
+impl OrderedEncode for BigInt {
+    fn encode_ordered(&self) -> Vec<u8> {
+        self.bytes.clone()
+    }
+
+    fn decode_ordered(bytes: &[u8]) -> Option<Self> {
+        Self::from_encoded(bytes)
+    }
+}
+
+impl Decode for BigInt {
+    type Error = fusio::Error;
+
+    async fn decode<R>(reader: &mut R) -> Result<Self, Self::Error>
+    where
+        R: SeqRead,
+    {
+        Ok(Self {
+            bytes: Vec::<u8>::decode(reader).await?,
+        })
+    }
+}
+
+impl Encode for BigInt {
+    type Error = fusio::Error;
+
+    async fn encode<W>(&self, writer: &mut W) -> Result<(), Self::Error>
+    where
+        W: Write,
+    {
+        self.bytes.encode(writer).await
+    }
+
+    fn size(&self) -> usize {
+        self.bytes.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use arrow::array::{AsArray, Datum};
+    use fusio_log::{Decode, Encode};
+    use tokio::io::AsyncSeekExt;
+
+    use super::{BigInt, ParseBigIntError};
+    use crate::record::{Key, OrderedEncode};
+
+    const SORTED: [&str; 11] = [
+        "-340282366920938463463374607431768211456000",
+        "-100",
+        "-99",
+        "-1",
+        "0",
+        "1",
+        "99",
+        "100",
+        "255",
+        "256",
+        "340282366920938463463374607431768211456000",
+    ];
+
+    #[test]
+    fn test_numeric_order() {
+        let keys = SORTED.map(|s| s.parse::<BigInt>().unwrap());
+        for (i, key) in keys.iter().enumerate() {
+            assert_eq!(key.to_string(), SORTED[i]);
+            for other in keys[i + 1..].iter() {
+                assert!(key < other, "{} < {}", key, other);
+                assert!(key.encode_ordered() < other.encode_ordered());
+            }
+        }
+        // unlike strings, where "100" < "99"
+        assert!("99".parse::<BigInt>().unwrap() < "100".parse::<BigInt>().unwrap());
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!("-0".parse::<BigInt>().unwrap(), BigInt::from(0_i128));
+        assert_eq!("+007".parse::<BigInt>().unwrap(), BigInt::from(7_i128));
+        assert_eq!(
+            i128::MIN.to_string().parse::<BigInt>().unwrap(),
+            BigInt::from(i128::MIN)
+        );
+        assert_eq!(BigInt::from(u128::MAX).to_string(), u128::MAX.to_string());
+        assert_eq!("".parse::<BigInt>(), Err(ParseBigIntError::Empty));
+        assert_eq!("-".parse::<BigInt>(), Err(ParseBigIntError::Empty));
+        assert_eq!("1e3".parse::<BigInt>(), Err(ParseBigIntError::InvalidDigit));
+    }
+
+    #[test]
+    fn test_bytes_be() {
+        let key = BigInt::from_bytes_be(true, &[0, 0, 1, 0]);
+        assert_eq!(key, BigInt::from(-256_i128));
+        assert!(key.is_negative());
+        assert_eq!(key.to_bytes_be(), (true, vec![1, 0]));
+        assert_eq!(
+            BigInt::from_bytes_be(true, &[0]).to_bytes_be(),
+            (false, vec![])
+        );
+
+        for s in SORTED {
+            let key = s.parse::<BigInt>().unwrap();
+            assert_eq!(BigInt::decode_ordered(&key.encode_ordered()), Some(key));
+        }
+        assert_eq!(BigInt::decode_ordered(&[]), None);
+        assert_eq!(BigInt::decode_ordered(&[1, 0]), None);
+        // leading zero in the magnitude
+        assert_eq!(BigInt::decode_ordered(&[2, 0, 0, 0, 2, 0, 1]), None);
+    }
+
+    #[test]
+    fn test_arrow_datum() {
+        let key = "-12345678901234567890123456789012345678901"
+            .parse::<BigInt>()
+            .unwrap();
+        let datum = key.to_arrow_datum();
+        let (array, is_scalar) = datum.get();
+        assert!(is_scalar);
+        assert_eq!(array.as_binary::<i32>().value(0), key.as_bytes());
+    }
+
+    #[tokio::test]
+    async fn test_encode_decode() {
+        for s in SORTED {
+            let key = s.parse::<BigInt>().unwrap();
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes.len(), key.size());
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(BigInt::decode(&mut cursor).await.unwrap(), key);
+        }
+    }
+}
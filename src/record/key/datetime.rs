@@ -145,7 +145,7 @@ macro_rules! make_date_type {
                 *self
             }
 
-            fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum> {
+            fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum + Send + Sync> {
                 Arc::new(<$array_ty>::new_scalar(self.0))
             }
         }
@@ -215,7 +215,7 @@ impl Key for Time32 {
         *self
     }
 
-    fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum> {
+    fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum + Send + Sync> {
         match self.unit {
             TimeUnit::Second => Arc::new(Time32SecondArray::new_scalar(self.time)),
             TimeUnit::Millisecond => Arc::new(Time32MillisecondArray::new_scalar(self.time)),
@@ -233,7 +233,7 @@ impl Key for Time64 {
         *self
     }
 
-    fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum> {
+    fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum + Send + Sync> {
         match self.unit {
             TimeUnit::Microsecond => Arc::new(Time64MicrosecondArray::new_scalar(self.time)),
             TimeUnit::Nanosecond => Arc::new(Time64NanosecondArray::new_scalar(self.time)),
@@ -365,12 +365,10 @@ impl Date32 {
 }
 
 impl Date64 {
+    /// converts [`Date64`] to [`chrono::NaiveDate`], milliseconds before 1970 round down to the
+    /// previous day
     pub fn to_naive_date(&self) -> Option<NaiveDate> {
-        Some(
-            DateTime::from_timestamp(self.0 / MILLISECONDS, 0)?
-                .naive_utc()
-                .date(),
-        )
+        Some(self.to_naive_datetime()?.date())
     }
 
     /// converts [`Date64`] to [`chrono::NaiveDateTime`]
@@ -381,10 +379,66 @@ impl Date64 {
 
 #[cfg(test)]
 mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use arrow::{array::AsArray, datatypes::Date32Type};
     use chrono::{Datelike, Duration, Utc};
+    use tokio::io::AsyncSeekExt;
 
     use super::*;
 
+    #[tokio::test]
+    async fn test_date_key() {
+        let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+
+        let days = [i32::MIN, -719_162, -1, 0, 1, i32::MAX].map(Date32::from);
+        for (i, date) in days.iter().enumerate() {
+            for other in days[i + 1..].iter() {
+                assert!(date < other);
+                assert!(date.as_key_ref() < other.as_key_ref());
+            }
+            assert_eq!(date.as_key_ref().to_key(), *date);
+
+            let datum = date.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(array.as_primitive::<Date32Type>().value(0), date.value());
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            date.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes.len(), date.size());
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(Date32::decode(&mut cursor).await.unwrap(), *date);
+        }
+        // day -719162 is January 1 of year 1
+        assert_eq!(
+            Date32::new(-719_162).to_naive_date(),
+            NaiveDate::from_ymd_opt(1, 1, 1)
+        );
+        assert_eq!(Date32::new(-1).to_naive_date(), epoch.pred_opt());
+
+        let millis = [-MILLISECONDS * SECONDS_IN_DAY, -1, 0, 1].map(Date64::from);
+        for (i, date) in millis.iter().enumerate() {
+            for other in millis[i + 1..].iter() {
+                assert!(date < other);
+            }
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            date.encode(&mut cursor).await.unwrap();
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(Date64::decode(&mut cursor).await.unwrap(), *date);
+        }
+        // the last millisecond before the epoch belongs to December 31, 1969
+        assert_eq!(Date64::new(-1).to_naive_date(), epoch.pred_opt());
+        assert_eq!(Date64::new(0).to_naive_date(), Some(epoch));
+    }
+
     #[test]
     fn test_time_to_naive_time() {
         let datetime = Utc::now();
@@ -0,0 +1,177 @@
+use std::sync::Arc;
+
+use arrow::{
+    array::{Datum, Decimal128Array, Scalar},
+    datatypes::{validate_decimal_precision_and_scale, Decimal128Type},
+};
+use fusio::{SeqRead, Write};
+use fusio_log::{Decode, Encode};
+
+use crate::record::{Key, KeyRef};
+
+/// A fixed-point decimal stored as its unscaled value, e.g. `12.34` with a scale of 2 is `1234`.
+/// `precision` is the number of significant digits and `scale` the number of digits after the
+/// decimal point.
+///
+/// Precision and scale are fixed per column, so decimals of a column are ordered by their
+/// unscaled value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Decimal128 {
+    pub(crate) value: i128,
+    pub(crate) precision: u8,
+    pub(crate) scale: i8,
+}
+
+impl Decimal128 {
+    /// # Panics
+    ///
+    /// Panics if `precision` and `scale` are not valid for an arrow `Decimal128` column, the
+    /// precision must be in `1..=38` and the scale at most the precision
+    pub fn new(value: i128, precision: u8, scale: i8) -> Self {
+        if let Err(err) = validate_decimal_precision_and_scale::<Decimal128Type>(precision, scale) {
+            panic!("invalid decimal: {}", err);
+        }
+        Self {
+            value,
+            precision,
+            scale,
+        }
+    }
+
+    /// the unscaled value
+    pub fn value(&self) -> i128 {
+        self.value
+    }
+
+    pub fn precision(&self) -> u8 {
+        self.precision
+    }
+
+    pub fn scale(&self) -> i8 {
+        self.scale
+    }
+}
+
+impl Key for Decimal128 {
+    type Ref<'r> = Decimal128;
+
+    fn as_key_ref(&self) -> Self::Ref<'_> {
+        *self
+    }
+
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
+        Arc::new(Scalar::new(
+            Decimal128Array::from_iter_values([self.value])
+                .with_precision_and_scale(self.precision, self.scale)
+                .unwrap(),
+        ))
+    }
+}
+
+impl<'a> KeyRef<'a> for Decimal128 {
+    type Key = Decimal128;
+
+    fn to_key(self) -> Self::Key {
+        self
+    }
+}
+
+impl Decode for Decimal128 {
+    type Error = fusio::Error;
+
+    async fn decode<R>(reader: &mut R) -> Result<Self, Self::Error>
+    where
+        R: SeqRead,
+    {
+        let mut bytes = [0u8; size_of::<i128>()];
+        let (result, _) = reader.read_exact(&mut bytes[..]).await;
+        result?;
+        let precision = u8::decode(reader).await?;
+        let scale = i8::decode(reader).await?;
+
+        Ok(Self {
+            value: i128::from_le_bytes(bytes),
+            precision,
+            scale,
+        })
+    }
+}
+
+impl Encode for Decimal128 {
+    type Error = fusio::Error;
+
+    async fn encode<W>(&self, writer: &mut W) -> Result<(), Self::Error>
+    where
+        W: Write,
+    {
+        let (result, _) = writer.write_all(&self.value.to_le_bytes()[..]).await;
+        result?;
+        self.precision.encode(writer).await?;
+        self.scale.encode(writer).await
+    }
+
+    fn size(&self) -> usize {
+        size_of::<i128>() + self.precision.size() + self.scale.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use arrow::{
+        array::{AsArray, Datum},
+        datatypes::{DataType, Decimal128Type},
+    };
+    use fusio_log::{Decode, Encode};
+    use tokio::io::AsyncSeekExt;
+
+    use super::Decimal128;
+    use crate::record::{Key, KeyRef};
+
+    #[test]
+    fn test_sign_boundary_order() {
+        let max = 10_i128.pow(38) - 1;
+        let keys = [-max, -100, -1, 0, 1, 100, max].map(|value| Decimal128::new(value, 38, 2));
+        for (i, key) in keys.iter().enumerate() {
+            for other in keys[i + 1..].iter() {
+                assert!(key < other, "{:?} < {:?}", key, other);
+            }
+            assert_eq!(key.as_key_ref().to_key(), *key);
+        }
+    }
+
+    #[test]
+    fn test_arrow_datum() {
+        let key = Decimal128::new(-1234, 10, 2);
+        let datum = key.to_arrow_datum();
+        let (array, is_scalar) = datum.get();
+        assert!(is_scalar);
+        assert_eq!(array.data_type(), &DataType::Decimal128(10, 2));
+
+        let array = array.as_primitive::<Decimal128Type>();
+        assert_eq!(array.value(0), -1234);
+        assert_eq!(array.precision(), 10);
+        assert_eq!(array.scale(), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid decimal")]
+    fn test_invalid_precision() {
+        Decimal128::new(1, 39, 0);
+    }
+
+    #[tokio::test]
+    async fn test_encode_decode() {
+        let key = Decimal128::new(-1_500, 12, -3);
+
+        let mut bytes = Vec::new();
+        let mut cursor = Cursor::new(&mut bytes);
+        key.encode(&mut cursor).await.unwrap();
+        assert_eq!(bytes.len(), key.size());
+
+        let mut cursor = Cursor::new(&mut bytes);
+        cursor.seek(SeekFrom::Start(0)).await.unwrap();
+        assert_eq!(Decimal128::decode(&mut cursor).await.unwrap(), key);
+    }
+}
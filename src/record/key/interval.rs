@@ -0,0 +1,113 @@
+use arrow::datatypes::IntervalMonthDayNano;
+use fusio::{SeqRead, Write};
+use fusio_log::{Decode, Encode};
+
+/// A calendar interval made of months, days and nanoseconds, e.g. "1 month 3 days". The
+/// components are kept separately because a month or a day does not have a fixed length.
+///
+/// Intervals can only be used as non-key columns: the derived ordering compares the components
+/// one by one, which is not a meaningful order of durations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interval {
+    pub(crate) months: i32,
+    pub(crate) days: i32,
+    pub(crate) nanoseconds: i64,
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, nanoseconds: i64) -> Self {
+        Self {
+            months,
+            days,
+            nanoseconds,
+        }
+    }
+
+    pub fn months(&self) -> i32 {
+        self.months
+    }
+
+    pub fn days(&self) -> i32 {
+        self.days
+    }
+
+    pub fn nanoseconds(&self) -> i64 {
+        self.nanoseconds
+    }
+
+    /// the value stored in an arrow `IntervalMonthDayNanoArray`
+    pub fn value(&self) -> IntervalMonthDayNano {
+        IntervalMonthDayNano::new(self.months, self.days, self.nanoseconds)
+    }
+}
+
+impl From<IntervalMonthDayNano> for Interval {
+    fn from(interval: IntervalMonthDayNano) -> Self {
+        Self::new(interval.months, interval.days, interval.nanoseconds)
+    }
+}
+
+impl From<Interval> for IntervalMonthDayNano {
+    fn from(interval: Interval) -> Self {
+        interval.value()
+    }
+}
+
+impl Decode for Interval {
+    type Error = fusio::Error;
+
+    async fn decode<R>(reader: &mut R) -> Result<Self, Self::Error>
+    where
+        R: SeqRead,
+    {
+        let months = i32::decode(reader).await?;
+        let days = i32::decode(reader).await?;
+        let nanoseconds = i64::decode(reader).await?;
+
+        Ok(Self::new(months, days, nanoseconds))
+    }
+}
+
+impl Encode for Interval {
+    type Error = fusio::Error;
+
+    async fn encode<W>(&self, writer: &mut W) -> Result<(), Self::Error>
+    where
+        W: Write,
+    {
+        self.months.encode(writer).await?;
+        self.days.encode(writer).await?;
+        self.nanoseconds.encode(writer).await
+    }
+
+    fn size(&self) -> usize {
+        self.months.size() + self.days.size() + self.nanoseconds.size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use fusio_log::{Decode, Encode};
+    use tokio::io::AsyncSeekExt;
+
+    use super::Interval;
+
+    #[tokio::test]
+    async fn test_encode_decode() {
+        let interval = Interval::new(-1, 3, -1_500_000_000);
+
+        let mut bytes = Vec::new();
+        let mut cursor = Cursor::new(&mut bytes);
+        interval.encode(&mut cursor).await.unwrap();
+        assert_eq!(bytes.len(), interval.size());
+
+        let mut cursor = Cursor::new(&mut bytes);
+        cursor.seek(SeekFrom::Start(0)).await.unwrap();
+        let decoded = Interval::decode(&mut cursor).await.unwrap();
+
+        assert_eq!(decoded, interval);
+        assert_eq!(Interval::from(interval.value()), interval);
+    }
+}
@@ -1 +1,78 @@
+use std::sync::Arc;
+
+use arrow::array::{BinaryArray, Datum};
+
+use super::{Key, KeyRef};
+
 pub type LargeBinary = Vec<u8>;
+
+impl Key for Vec<u8> {
+    type Ref<'r> = &'r [u8];
+
+    fn as_key_ref(&self) -> Self::Ref<'_> {
+        self
+    }
+
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
+        Arc::new(BinaryArray::new_scalar(self))
+    }
+}
+
+impl<'r> KeyRef<'r> for &'r [u8] {
+    type Key = Vec<u8>;
+
+    fn to_key(self) -> Self::Key {
+        self.to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use arrow::array::{AsArray, Datum};
+    use fusio_log::{Decode, Encode};
+    use tokio::io::AsyncSeekExt;
+
+    use crate::record::{Key, KeyRef};
+
+    fn keys() -> Vec<Vec<u8>> {
+        vec![vec![], vec![0], vec![1], vec![1, 2], vec![1; 1024], vec![2]]
+    }
+
+    #[test]
+    fn test_key_ref() {
+        let keys = keys();
+        for (i, key) in keys.iter().enumerate() {
+            let key_ref = key.as_key_ref();
+            assert_eq!(key_ref, key.as_slice());
+            assert_eq!(&key_ref.to_key(), key);
+
+            // keys order lexicographically, a prefix orders before the keys it prefixes
+            for other in keys[i + 1..].iter() {
+                assert!(key_ref < other.as_key_ref());
+                assert!(key < other);
+            }
+
+            let datum = key.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(array.as_binary::<i32>().value(0), key.as_slice());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encode_decode() {
+        for key in keys() {
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.as_key_ref().encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes.len(), key.as_key_ref().size());
+            assert_eq!(bytes.len(), key.size());
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(Vec::<u8>::decode(&mut cursor).await.unwrap(), key);
+        }
+    }
+}
@@ -1,4 +1,7 @@
+mod bigint;
 mod datetime;
+mod decimal;
+mod interval;
 mod list;
 mod num;
 mod str;
@@ -7,8 +10,11 @@ mod timestamp;
 use std::{hash::Hash, sync::Arc};
 
 use arrow::array::Datum;
+pub use bigint::*;
 pub use datetime::*;
+pub use decimal::*;
 use fusio_log::{Decode, Encode};
+pub use interval::*;
 pub use list::*;
 pub use num::*;
 pub use str::*;
@@ -23,7 +29,7 @@ pub trait Key:
 
     fn as_key_ref(&self) -> Self::Ref<'_>;
 
-    fn to_arrow_datum(&self) -> Arc<dyn Datum>;
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync>;
 }
 
 pub trait KeyRef<'r>: Clone + Encode + Send + Sync + Ord + std::fmt::Debug {
@@ -31,3 +37,15 @@ pub trait KeyRef<'r>: Clone + Encode + Send + Sync + Ord + std::fmt::Debug {
 
     fn to_key(self) -> Self::Key;
 }
+
+/// a byte encoding of a key that sorts like the key, so stores comparing raw bytes, e.g. an
+/// external ordered KV, order keys the same way as tonbo. Unlike [`Encode`], numbers are written
+/// big-endian with the sign bit flipped.
+pub trait OrderedEncode: Key {
+    /// comparing the encodings of two keys byte-wise gives the same result as [`Ord`]
+    fn encode_ordered(&self) -> Vec<u8>;
+
+    /// decode a key from the output of [`OrderedEncode::encode_ordered`], returns `None` if
+    /// `bytes` doesn't have the length of an encoded key
+    fn decode_ordered(bytes: &[u8]) -> Option<Self>;
+}
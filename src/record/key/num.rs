@@ -1,13 +1,15 @@
 use std::{hash::Hash, ops::Deref, sync::Arc};
 
 use arrow::array::{
-    Datum, Float32Array, Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, UInt16Array,
-    UInt32Array, UInt64Array, UInt8Array,
+    BooleanArray, Datum, Decimal128Array, FixedSizeBinaryArray, Float16Array, Float32Array,
+    Float64Array, Int16Array, Int32Array, Int64Array, Int8Array, Scalar, UInt16Array, UInt32Array,
+    UInt64Array, UInt8Array,
 };
 use fusio::{SeqRead, Write};
 use fusio_log::{Decode, Encode};
+use half::f16;
 
-use crate::record::{Key, KeyRef};
+use crate::record::{Key, KeyRef, OrderedEncode};
 
 #[macro_export]
 macro_rules! implement_key {
@@ -19,7 +21,7 @@ macro_rules! implement_key {
                 *self
             }
 
-            fn to_arrow_datum(&self) -> Arc<dyn Datum> {
+            fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
                 Arc::new($array_name::new_scalar(*self))
             }
         }
@@ -42,10 +44,56 @@ implement_key!(u8, UInt8Array);
 implement_key!(u16, UInt16Array);
 implement_key!(u32, UInt32Array);
 implement_key!(u64, UInt64Array);
+implement_key!(bool, BooleanArray);
+
+macro_rules! implement_ordered_int {
+    ($($ty:ty => $unsigned:ty),* $(,)?) => {
+        $(
+            impl OrderedEncode for $ty {
+                fn encode_ordered(&self) -> Vec<u8> {
+                    // `MIN` only has the sign bit set for signed types and is 0 for unsigned ones
+                    ((*self as $unsigned) ^ (<$ty>::MIN as $unsigned)).to_be_bytes().to_vec()
+                }
+
+                fn decode_ordered(bytes: &[u8]) -> Option<Self> {
+                    let flipped = <$unsigned>::from_be_bytes(bytes.try_into().ok()?);
+                    Some((flipped ^ (<$ty>::MIN as $unsigned)) as $ty)
+                }
+            }
+        )*
+    };
+}
+
+implement_ordered_int!(
+    i8 => u8,
+    i16 => u16,
+    i32 => u32,
+    i64 => u64,
+    u8 => u8,
+    u16 => u16,
+    u32 => u32,
+    u64 => u64,
+);
+
+impl OrderedEncode for bool {
+    fn encode_ordered(&self) -> Vec<u8> {
+        vec![*self as u8]
+    }
+
+    fn decode_ordered(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [0] => Some(false),
+            [1] => Some(true),
+            _ => None,
+        }
+    }
+}
 
 #[derive(Debug, Default, Clone, Copy)]
 pub struct FloatType<T>(pub T);
 
+/// half-precision float, e.g. for quantized embeddings
+pub type F16 = FloatType<f16>;
 pub type F32 = FloatType<f32>;
 pub type F64 = FloatType<f64>;
 
@@ -86,13 +134,13 @@ macro_rules! implement_float_key {
     ($ty:ty, $array_name:ident) => {
         impl Ord for FloatType<$ty> {
             fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                self.0.total_cmp(&other.0)
+                self.canonical().total_cmp(&other.canonical())
             }
         }
 
         impl PartialEq for FloatType<$ty> {
             fn eq(&self, other: &Self) -> bool {
-                self.0.to_bits() == other.0.to_bits()
+                self.canonical().to_bits() == other.canonical().to_bits()
             }
         }
 
@@ -105,13 +153,13 @@ macro_rules! implement_float_key {
 
         impl Hash for FloatType<$ty> {
             fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-                state.write(&<$ty>::from_le_bytes(self.0.to_le_bytes()).to_le_bytes())
+                state.write(&self.canonical().to_le_bytes())
             }
         }
 
         impl From<$ty> for FloatType<$ty> {
             fn from(value: $ty) -> Self {
-                Self(value)
+                Self(Self(value).canonical())
             }
         }
 
@@ -142,8 +190,8 @@ macro_rules! implement_float_key {
                 *self
             }
 
-            fn to_arrow_datum(&self) -> Arc<dyn Datum> {
-                Arc::new($array_name::new_scalar(self.0))
+            fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
+                Arc::new($array_name::new_scalar(self.canonical()))
             }
         }
 
@@ -159,23 +207,248 @@ macro_rules! implement_float_key {
             pub fn value(&self) -> $ty {
                 self.0
             }
+
+            /// the value with every NaN replaced by the positive quiet NaN, so NaNs with
+            /// different signs or payloads compare and hash as the same key
+            fn canonical(&self) -> $ty {
+                if self.0.is_nan() {
+                    <$ty>::NAN
+                } else {
+                    self.0
+                }
+            }
+
+            /// scan bounds of every value except NaNs, from negative to positive infinity
+            pub fn range_excluding_nan() -> (std::ops::Bound<Self>, std::ops::Bound<Self>) {
+                (
+                    std::ops::Bound::Included(Self(<$ty>::NEG_INFINITY)),
+                    std::ops::Bound::Included(Self(<$ty>::INFINITY)),
+                )
+            }
+
+            /// scan bounds of the values `x` with `lower <= x && x <= upper` in float comparison.
+            /// Keys are ordered by [`total_cmp`](f64::total_cmp), which puts `-0.0` before `0.0`
+            /// and every NaN after positive infinity, so a zero bound includes both zeros and NaNs
+            /// are never included.
+            ///
+            /// # Panics
+            /// if a bound is NaN or `lower > upper`
+            pub fn range_inclusive(
+                lower: $ty,
+                upper: $ty,
+            ) -> (std::ops::Bound<Self>, std::ops::Bound<Self>) {
+                assert!(
+                    !lower.is_nan() && !upper.is_nan(),
+                    "NaN is not a range bound"
+                );
+                assert!(lower <= upper, "lower bound is greater than upper bound");
+                // `x == -x` only holds for the zeros
+                let lower = if lower == -lower { -lower.abs() } else { lower };
+                let upper = if upper == -upper { upper.abs() } else { upper };
+                (
+                    std::ops::Bound::Included(Self(lower)),
+                    std::ops::Bound::Included(Self(upper)),
+                )
+            }
+
+            /// like [`Self::range_inclusive`], but the infinities are excluded as well
+            ///
+            /// # Panics
+            /// if a bound is NaN or `lower > upper`
+            pub fn finite_range_inclusive(
+                lower: $ty,
+                upper: $ty,
+            ) -> (std::ops::Bound<Self>, std::ops::Bound<Self>) {
+                assert!(
+                    !lower.is_nan() && !upper.is_nan(),
+                    "NaN is not a range bound"
+                );
+                Self::range_inclusive(lower.max(<$ty>::MIN), upper.min(<$ty>::MAX))
+            }
         }
     };
 }
 
+implement_float_encode_decode!(f16);
 implement_float_encode_decode!(f32);
 implement_float_encode_decode!(f64);
 
+implement_float_key!(f16, Float16Array);
 implement_float_key!(f32, Float32Array);
 implement_float_key!(f64, Float64Array);
 
+macro_rules! implement_ordered_float {
+    ($ty:ty, $bits:ty) => {
+        impl OrderedEncode for FloatType<$ty> {
+            /// negative floats have all their bits flipped and positive ones only the sign bit,
+            /// which sorts like [`total_cmp`](f64::total_cmp)
+            fn encode_ordered(&self) -> Vec<u8> {
+                let sign: $bits = 1 << (<$bits>::BITS - 1);
+                let bits = self.canonical().to_bits();
+                let ordered = if bits & sign != 0 { !bits } else { bits | sign };
+                ordered.to_be_bytes().to_vec()
+            }
+
+            fn decode_ordered(bytes: &[u8]) -> Option<Self> {
+                let sign: $bits = 1 << (<$bits>::BITS - 1);
+                let ordered = <$bits>::from_be_bytes(bytes.try_into().ok()?);
+                let bits = if ordered & sign != 0 {
+                    ordered ^ sign
+                } else {
+                    !ordered
+                };
+                // keys encoded before NaNs were canonical may hold a negative NaN
+                Some(FloatType::<$ty>::from(<$ty>::from_bits(bits)))
+            }
+        }
+    };
+}
+
+implement_ordered_float!(f16, u16);
+implement_ordered_float!(f32, u32);
+implement_ordered_float!(f64, u64);
+
+/// 128-bit signed integer key, e.g. Snowflake-style or UUID-derived ids. Stored as a
+/// `Decimal128(38, 0)` column.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct I128(pub i128);
+
+/// 128-bit unsigned integer key. Stored as a `FixedSizeBinary(16)` column of big-endian bytes,
+/// which order like the numbers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct U128(pub u128);
+
+#[macro_export]
+macro_rules! implement_wide_int {
+    ($struct_name:ident, $ty:ty) => {
+        impl $struct_name {
+            pub fn value(&self) -> $ty {
+                self.0
+            }
+        }
+
+        impl From<$ty> for $struct_name {
+            fn from(value: $ty) -> Self {
+                Self(value)
+            }
+        }
+
+        impl From<$struct_name> for $ty {
+            fn from(value: $struct_name) -> Self {
+                value.0
+            }
+        }
+
+        impl Encode for $struct_name {
+            type Error = fusio::Error;
+
+            async fn encode<W: Write>(&self, writer: &mut W) -> Result<(), Self::Error> {
+                let (result, _) = writer.write_all(&self.0.to_le_bytes()[..]).await;
+                result?;
+
+                Ok(())
+            }
+
+            fn size(&self) -> usize {
+                size_of::<$ty>()
+            }
+        }
+
+        impl Decode for $struct_name {
+            type Error = fusio::Error;
+
+            async fn decode<R: SeqRead>(reader: &mut R) -> Result<Self, Self::Error> {
+                let mut bytes = [0u8; size_of::<$ty>()];
+                let (result, _) = reader.read_exact(&mut bytes[..]).await;
+                result?;
+
+                Ok(Self(<$ty>::from_le_bytes(bytes)))
+            }
+        }
+
+        impl<'a> KeyRef<'a> for $struct_name {
+            type Key = $struct_name;
+
+            fn to_key(self) -> Self::Key {
+                self
+            }
+        }
+    };
+}
+
+implement_wide_int!(I128, i128);
+implement_wide_int!(U128, u128);
+
+impl OrderedEncode for I128 {
+    fn encode_ordered(&self) -> Vec<u8> {
+        ((self.0 as u128) ^ (i128::MIN as u128))
+            .to_be_bytes()
+            .to_vec()
+    }
+
+    fn decode_ordered(bytes: &[u8]) -> Option<Self> {
+        let flipped = u128::from_be_bytes(bytes.try_into().ok()?);
+        Some(I128((flipped ^ (i128::MIN as u128)) as i128))
+    }
+}
+
+impl OrderedEncode for U128 {
+    fn encode_ordered(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn decode_ordered(bytes: &[u8]) -> Option<Self> {
+        Some(U128(u128::from_be_bytes(bytes.try_into().ok()?)))
+    }
+}
+
+impl Key for I128 {
+    type Ref<'r> = I128;
+
+    fn as_key_ref(&self) -> Self::Ref<'_> {
+        *self
+    }
+
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
+        Arc::new(Scalar::new(
+            Decimal128Array::from_iter_values([self.0])
+                .with_precision_and_scale(38, 0)
+                .unwrap(),
+        ))
+    }
+}
+
+impl Key for U128 {
+    type Ref<'r> = U128;
+
+    fn as_key_ref(&self) -> Self::Ref<'_> {
+        *self
+    }
+
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
+        Arc::new(FixedSizeBinaryArray::new_scalar(self.0.to_be_bytes()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::f32;
+    use std::{
+        cmp::Ordering,
+        collections::{BTreeMap, HashMap},
+        io::{Cursor, SeekFrom},
+        ops::{Bound, RangeBounds},
+    };
 
-    use arrow::array::ArrowNativeTypeOp;
+    use arrow::array::{ArrowNativeTypeOp, AsArray, Datum};
+    use fusio_log::{Decode, Encode};
+    use half::f16;
+    use tokio::io::AsyncSeekExt;
 
-    use crate::record::key::num::F32;
+    use crate::record::{
+        key::num::{FloatType, F16, F32, F64, I128, U128},
+        Key, KeyRef, OrderedEncode,
+    };
 
     #[tokio::test]
     async fn test_zero() {
@@ -197,31 +470,354 @@ mod tests {
     async fn test_nan_cmp() {
         let f1 = F32::from(f32::NAN);
         let f2 = F32::from(f32::NAN);
-        let f3 = F32::from(-f32::NAN);
+        let f3 = FloatType(-f32::NAN);
         let inf = F32::from(f32::INFINITY);
-        let neg_inf = F32::from(f32::NEG_INFINITY);
 
         // This is not consistent with the IEEE
         // assert_eq!(f32::NAN, f32::NAN);
         assert_eq!(f1.cmp(&f2), f1.0.compare(f2.0));
         assert_eq!(f1, f2);
 
-        assert_eq!(f1.cmp(&f3), f1.0.compare(f3.0));
-        assert_eq!(f1, f2);
+        // NANs of any sign are the same key
+        assert_eq!(f1.cmp(&f3), Ordering::Equal);
+        assert_eq!(f1, f3);
+        assert_eq!(F32::from(-f32::NAN).0.to_bits(), f32::NAN.to_bits());
 
-        // positive NAN should be greater than positive infinity
+        // NAN should be greater than positive infinity
         assert_eq!(f1.cmp(&inf), f1.0.compare(inf.0));
         assert!(f1 > inf);
-
-        // negative NAN should be less than negative infinity
-        assert_eq!(f3.cmp(&neg_inf), f3.0.compare(neg_inf.0));
-        assert!(f3 < neg_inf);
+        assert!(f3 > inf);
 
         let f4 = F32::from(1.0_f32);
         assert_eq!(f1.cmp(&f4), f1.0.compare(f4.0));
         assert!(f1 > f4);
 
-        assert_eq!(f3.cmp(&f4), f3.0.compare(f4.0));
-        assert!(f3 < f4);
+        assert!(f3 > f4);
+    }
+
+    #[test]
+    fn test_nan_lookup() {
+        // `0.0 / 0.0` is a negative NaN on x86, unlike `f64::NAN`
+        let computed = FloatType(-f64::NAN);
+        let quiet = F64::from(f64::NAN);
+
+        let mut hashed = HashMap::new();
+        hashed.insert(computed, "nan");
+        assert_eq!(hashed.get(&quiet), Some(&"nan"));
+        assert_eq!(
+            hashed.get(&FloatType(f64::from_bits(f64::NAN.to_bits() | 1))),
+            Some(&"nan")
+        );
+
+        let mut ordered = BTreeMap::new();
+        ordered.insert(computed, "nan");
+        ordered.insert(F64::from(f64::INFINITY), "inf");
+        assert_eq!(ordered.get(&quiet), Some(&"nan"));
+        assert_eq!(ordered.len(), 2);
+        ordered.insert(quiet, "quiet");
+        assert_eq!(ordered.len(), 2);
+        assert_eq!(ordered.last_key_value(), Some((&computed, &"quiet")));
+
+        assert_eq!(computed.encode_ordered(), quiet.encode_ordered());
+    }
+
+    #[tokio::test]
+    async fn test_f16_zero() {
+        let f1 = F16::from(f16::ZERO);
+        let f2 = F16::from(f16::NEG_ZERO);
+        // +0 should be greater than -0
+        assert_eq!(f1.cmp(&f2), f1.0.compare(f2.0));
+        assert!(f1 > f2);
+    }
+
+    #[tokio::test]
+    async fn test_f16_nan_cmp() {
+        let f1 = F16::from(f16::NAN);
+        let f2 = F16::from(f16::NAN);
+        let f3 = FloatType(-f16::NAN);
+        let inf = F16::from(f16::INFINITY);
+
+        assert_eq!(f1.cmp(&f2), f1.0.compare(f2.0));
+        assert_eq!(f1, f2);
+
+        // NANs of any sign are the same key
+        assert_eq!(f1.cmp(&f3), Ordering::Equal);
+        assert_eq!(f1, f3);
+
+        // NAN should be greater than positive infinity
+        assert_eq!(f1.cmp(&inf), f1.0.compare(inf.0));
+        assert!(f1 > inf);
+        assert!(f3 > inf);
+
+        let f4 = F16::from(f16::ONE);
+        assert_eq!(f1.cmp(&f4), f1.0.compare(f4.0));
+        assert!(f1 > f4);
+
+        assert!(f3 > f4);
+    }
+
+    #[tokio::test]
+    async fn test_f16_key() {
+        for key in [
+            f16::NEG_INFINITY,
+            f16::from_f32(-1.5),
+            f16::ZERO,
+            f16::from_f32(0.25),
+            f16::MAX,
+        ]
+        .map(F16::from)
+        {
+            assert_eq!(key.as_key_ref().to_key(), key);
+
+            let datum = key.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(
+                array
+                    .as_primitive::<arrow::datatypes::Float16Type>()
+                    .value(0)
+                    .to_bits(),
+                key.value().to_bits()
+            );
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes, key.value().to_le_bytes());
+            assert_eq!(key.size(), 2);
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(F16::decode(&mut cursor).await.unwrap(), key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bool_key() {
+        assert!(false < true);
+        assert!(false.as_key_ref() < true.as_key_ref());
+
+        for key in [false, true] {
+            assert_eq!(key.as_key_ref().to_key(), key);
+
+            let datum = key.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(array.as_boolean().value(0), key);
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes.len(), 1);
+            assert_eq!(key.size(), 1);
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(bool::decode(&mut cursor).await.unwrap(), key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_i128_key() {
+        let keys = [i128::MIN, -1, 0, 1, i128::MAX].map(I128::from);
+        for (i, key) in keys.iter().enumerate() {
+            for other in keys[i + 1..].iter() {
+                assert!(key < other);
+            }
+            assert_eq!(key.as_key_ref().to_key(), *key);
+
+            let datum = key.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(
+                array
+                    .as_primitive::<arrow::datatypes::Decimal128Type>()
+                    .value(0),
+                key.value()
+            );
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes, key.value().to_le_bytes());
+            assert_eq!(key.size(), 16);
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(I128::decode(&mut cursor).await.unwrap(), *key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_u128_key() {
+        let keys = [0, 1, 256, u64::MAX as u128 + 1, u128::MAX].map(U128::from);
+        for (i, key) in keys.iter().enumerate() {
+            for other in keys[i + 1..].iter() {
+                assert!(key < other);
+                // the arrow representation orders like the numbers
+                assert!(key.value().to_be_bytes() < other.value().to_be_bytes());
+            }
+            assert_eq!(key.as_key_ref().to_key(), *key);
+
+            let datum = key.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(
+                array.as_fixed_size_binary().value(0),
+                key.value().to_be_bytes()
+            );
+
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes, key.value().to_le_bytes());
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(U128::decode(&mut cursor).await.unwrap(), *key);
+        }
+    }
+
+    /// sorting the ordered encodings of `keys` gives the same order as sorting the keys
+    fn assert_ordered_encoding<K: OrderedEncode>(keys: &[K]) {
+        let mut sorted = keys.to_vec();
+        sorted.sort();
+        let mut encoded = keys.iter().map(K::encode_ordered).collect::<Vec<_>>();
+        encoded.sort();
+        assert_eq!(
+            encoded,
+            sorted.iter().map(K::encode_ordered).collect::<Vec<_>>()
+        );
+        for (bytes, key) in encoded.iter().zip(sorted.iter()) {
+            assert_eq!(&K::decode_ordered(bytes).unwrap(), key);
+        }
+    }
+
+    #[test]
+    fn test_ordered_encode_float() {
+        assert_ordered_encoding(&[1.0, f32::NAN, -0.0, 0.0, -1.0].map(F32::from));
+        assert_ordered_encoding(&[1.0, f64::NAN, -0.0, 0.0, -1.0].map(F64::from));
+        assert_ordered_encoding(
+            &[1.0, f32::NAN, -0.0, 0.0, -1.0].map(|f| F16::from(f16::from_f32(f))),
+        );
+        assert_ordered_encoding(
+            &[
+                -f64::NAN,
+                f64::NEG_INFINITY,
+                f64::MIN,
+                -f64::MIN_POSITIVE,
+                f64::MIN_POSITIVE,
+                f64::MAX,
+                f64::INFINITY,
+            ]
+            .map(F64::from),
+        );
+
+        let sorted = [-1.0, -0.0, 0.0, 1.0, f32::NAN].map(F32::from);
+        for pair in sorted.windows(2) {
+            assert!(pair[0] < pair[1]);
+            assert!(pair[0].encode_ordered() < pair[1].encode_ordered());
+        }
+        assert!(F32::decode_ordered(&[0; 3]).is_none());
+    }
+
+    #[test]
+    fn test_ordered_encode_int() {
+        assert_ordered_encoding(&[0_i8, -1, i8::MIN, 1, i8::MAX]);
+        assert_ordered_encoding(&[0_i16, -1, i16::MIN, 1, i16::MAX]);
+        assert_ordered_encoding(&[0_i32, -1, i32::MIN, 1, i32::MAX, -256, 256]);
+        assert_ordered_encoding(&[0_i64, -1, i64::MIN, 1, i64::MAX]);
+        assert_ordered_encoding(&[0_u8, 1, u8::MAX]);
+        assert_ordered_encoding(&[0_u16, 1, 256, u16::MAX]);
+        assert_ordered_encoding(&[0_u32, 1, 256, u32::MAX]);
+        assert_ordered_encoding(&[0_u64, 1, 256, u64::MAX]);
+        assert_ordered_encoding(&[0_i128, -1, i128::MIN, 1, i128::MAX].map(I128::from));
+        assert_ordered_encoding(&[0_u128, 1, 256, u128::MAX].map(U128::from));
+        assert_ordered_encoding(&[true, false]);
+
+        // little-endian `Encode` bytes of -1 sort after those of 1
+        assert!((-1_i32).encode_ordered() < 1_i32.encode_ordered());
+        assert!(i32::decode_ordered(&[0; 8]).is_none());
+        assert!(bool::decode_ordered(&[2]).is_none());
+    }
+
+    #[test]
+    fn test_float_ranges() {
+        let values = [
+            f64::NEG_INFINITY,
+            f64::MIN,
+            -1.5,
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE,
+            1.5,
+            2.0,
+            f64::MAX,
+            f64::INFINITY,
+            f64::NAN,
+        ]
+        .map(F64::from);
+        let included = |range: (Bound<F64>, Bound<F64>)| {
+            values
+                .iter()
+                .filter(|value| range.contains(*value))
+                .map(|value| value.0)
+                .collect::<Vec<_>>()
+        };
+
+        // keys are ordered by `total_cmp`, NaNs sort after positive infinity
+        assert!(values.windows(2).all(|pair| pair[0] < pair[1]));
+
+        let all = included(F64::range_excluding_nan());
+        assert_eq!(all.len(), values.len() - 1);
+        assert!(all.iter().all(|value| !value.is_nan()));
+
+        assert_eq!(
+            included(F64::range_inclusive(-1.5, 1.5)),
+            [-1.5, -0.0, 0.0, f64::MIN_POSITIVE, 1.5]
+        );
+        // either zero includes both zeros
+        let zeros = included(F64::range_inclusive(0.0, -0.0));
+        assert_eq!(zeros.len(), 2);
+        assert!(zeros[0].is_sign_negative() && zeros[1].is_sign_positive());
+        assert_eq!(included(F64::range_inclusive(-0.0, 0.0)), zeros);
+
+        let finite = included(F64::finite_range_inclusive(
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+        ));
+        assert_eq!(finite.first(), Some(&f64::MIN));
+        assert_eq!(finite.last(), Some(&f64::MAX));
+        assert_eq!(finite.len(), values.len() - 3);
+        assert_eq!(
+            included(F64::range_inclusive(1.5, f64::INFINITY)),
+            [1.5, 2.0, f64::MAX, f64::INFINITY]
+        );
+
+        let ranges = F16::finite_range_inclusive(f16::NEG_INFINITY, f16::from_f32(0.0));
+        assert_eq!(ranges.0, Bound::Included(F16::from(f16::MIN)));
+        assert_eq!(ranges.1, Bound::Included(F16::from(f16::ZERO)));
+        assert!(!ranges.contains(&F16::from(f16::NAN)));
+    }
+
+    #[test]
+    #[should_panic(expected = "NaN is not a range bound")]
+    fn test_nan_range_bound() {
+        F32::range_inclusive(f32::NAN, 1.0);
+    }
+
+    #[test]
+    fn test_decode_negative_nan() {
+        // the ordered encoding of a negative NaN, as written before NaNs were canonical
+        let sign = 1_u64 << 63;
+        let bits = (-f64::NAN).to_bits();
+        let encoded = (if bits & sign != 0 { !bits } else { bits | sign }).to_be_bytes();
+
+        let decoded = F64::decode_ordered(&encoded).unwrap();
+        assert_eq!(decoded.0.to_bits(), f64::NAN.to_bits());
+        assert_eq!(
+            decoded.encode_ordered(),
+            F64::from(f64::NAN).encode_ordered()
+        );
     }
 }
@@ -13,7 +13,7 @@ impl Key for String {
         self
     }
 
-    fn to_arrow_datum(&self) -> Arc<dyn Datum> {
+    fn to_arrow_datum(&self) -> Arc<dyn Datum + Send + Sync> {
         Arc::new(StringArray::new_scalar(self))
     }
 }
@@ -25,3 +25,51 @@ impl<'r> KeyRef<'r> for &'r str {
         self.to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, SeekFrom};
+
+    use arrow::array::{AsArray, Datum};
+    use fusio_log::{Decode, Encode};
+    use tokio::io::AsyncSeekExt;
+
+    use crate::record::{Key, KeyRef};
+
+    const KEYS: [&str; 5] = ["", "a", "ab", "é", "日本語🦀"];
+
+    #[test]
+    fn test_key_ref() {
+        for (i, key) in KEYS.iter().enumerate() {
+            let owned = key.to_string();
+            let key_ref = owned.as_key_ref();
+            assert_eq!(key_ref, *key);
+            assert_eq!(key_ref.to_key(), owned);
+
+            // borrowed keys order like the owned ones, i.e. by their utf-8 bytes
+            for other in KEYS[i + 1..].iter() {
+                assert!(key_ref < other.to_string().as_key_ref());
+                assert!(owned < other.to_string());
+            }
+
+            let datum = owned.to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(array.as_string::<i32>().value(0), *key);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_encode_decode() {
+        for key in KEYS {
+            let mut bytes = Vec::new();
+            let mut cursor = Cursor::new(&mut bytes);
+            key.encode(&mut cursor).await.unwrap();
+            assert_eq!(bytes.len(), key.size());
+
+            let mut cursor = Cursor::new(&mut bytes);
+            cursor.seek(SeekFrom::Start(0)).await.unwrap();
+            assert_eq!(String::decode(&mut cursor).await.unwrap(), key);
+        }
+    }
+}
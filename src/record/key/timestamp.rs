@@ -90,7 +90,7 @@ impl Key for Timestamp {
         *self
     }
 
-    fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum> {
+    fn to_arrow_datum(&self) -> std::sync::Arc<dyn arrow::array::Datum + Send + Sync> {
         match self.unit {
             TimeUnit::Second => Arc::new(TimestampSecondArray::new_scalar(self.ts)),
             TimeUnit::Millisecond => Arc::new(TimestampMillisecondArray::new_scalar(self.ts)),
@@ -207,40 +207,49 @@ impl Timestamp {
         }
     }
 
-    pub fn timestamp(&self) -> i64 {
-        match self.unit {
-            TimeUnit::Second => self.ts,
-            TimeUnit::Millisecond => self.ts.saturating_mul(1000),
-            TimeUnit::Microsecond => self.ts.saturating_mul(1_000_000),
-            TimeUnit::Nanosecond => self.ts.saturating_mul(1_000_000_000),
+    pub fn unit(&self) -> TimeUnit {
+        self.unit
+    }
+
+    /// rescale the [`Timestamp`] to `unit`. Converting to a coarser unit truncates towards zero,
+    /// converting to a finer unit returns `None` if the value overflows `i64`.
+    pub fn convert(self, unit: TimeUnit) -> Option<Self> {
+        let (from, to) = (self.unit.factor(), unit.factor());
+        let ts = if from >= to {
+            self.ts.checked_mul(from / to)?
+        } else {
+            self.ts / (to / from)
+        };
+        Some(Self { ts, unit })
+    }
+
+    /// value in `unit`, saturated to `i64::MIN` or `i64::MAX` on overflow
+    fn saturating_value(&self, unit: TimeUnit) -> i64 {
+        match self.convert(unit) {
+            Some(timestamp) => timestamp.ts,
+            None if self.ts < 0 => i64::MIN,
+            None => i64::MAX,
         }
     }
 
+    /// seconds since the epoch, saturated on overflow
+    pub fn timestamp(&self) -> i64 {
+        self.saturating_value(TimeUnit::Second)
+    }
+
+    /// milliseconds since the epoch, saturated on overflow
     pub fn timestamp_millis(&self) -> i64 {
-        match self.unit {
-            TimeUnit::Second => self.ts.saturating_div(1_000),
-            TimeUnit::Millisecond => self.ts,
-            TimeUnit::Microsecond => self.ts.saturating_mul(1_000),
-            TimeUnit::Nanosecond => self.ts.saturating_mul(1_000_000),
-        }
+        self.saturating_value(TimeUnit::Millisecond)
     }
 
+    /// microseconds since the epoch, saturated on overflow
     pub fn timestamp_micros(&self) -> i64 {
-        match self.unit {
-            TimeUnit::Second => self.ts.saturating_div(1_000_000),
-            TimeUnit::Millisecond => self.ts.saturating_div(1_000),
-            TimeUnit::Microsecond => self.ts,
-            TimeUnit::Nanosecond => self.ts.saturating_mul(1_000),
-        }
+        self.saturating_value(TimeUnit::Microsecond)
     }
 
+    /// nanoseconds since the epoch, saturated on overflow
     pub fn timestamp_nanos(&self) -> i64 {
-        match self.unit {
-            TimeUnit::Second => self.ts.saturating_div(1_000_000_000),
-            TimeUnit::Millisecond => self.ts.saturating_div(1_000_000),
-            TimeUnit::Microsecond => self.ts.saturating_div(1_000),
-            TimeUnit::Nanosecond => self.ts,
-        }
+        self.saturating_value(TimeUnit::Nanosecond)
     }
 
     /// build [`Timestamp`] from [`NaiveDateTime`]
@@ -333,12 +342,73 @@ impl Timestamp {
 mod tests {
     use std::io::{Cursor, SeekFrom};
 
+    use arrow::{array::Datum, datatypes::DataType};
     use chrono::Utc;
     use fusio_log::{Decode, Encode};
     use tokio::io::AsyncSeekExt;
 
     use super::*;
 
+    #[test]
+    fn test_timestamp_convert() {
+        let seconds = Timestamp::new_seconds(1717507203);
+        assert_eq!(seconds.unit(), TimeUnit::Second);
+
+        let nanos = seconds.convert(TimeUnit::Nanosecond).unwrap();
+        assert_eq!(nanos.unit(), TimeUnit::Nanosecond);
+        assert_eq!(nanos.ts, 1717507203_000_000_000);
+        assert_eq!(nanos, seconds);
+
+        // upscaling near `i64::MAX` overflows
+        let max_seconds = i64::MAX / 1_000_000_000;
+        assert!(Timestamp::new_seconds(max_seconds)
+            .convert(TimeUnit::Nanosecond)
+            .is_some());
+        assert_eq!(
+            Timestamp::new_seconds(max_seconds + 1).convert(TimeUnit::Nanosecond),
+            None
+        );
+        assert_eq!(
+            Timestamp::new_seconds(-max_seconds - 1).convert(TimeUnit::Nanosecond),
+            None
+        );
+        assert_eq!(
+            Timestamp::new_seconds(max_seconds + 1).timestamp_nanos(),
+            i64::MAX
+        );
+
+        // downscaling truncates towards zero
+        let truncated = Timestamp::new_nanos(1_999_999_999)
+            .convert(TimeUnit::Second)
+            .unwrap();
+        assert_eq!(truncated.ts, 1);
+        assert_eq!(
+            Timestamp::new_nanos(-1_500_000_000)
+                .convert(TimeUnit::Second)
+                .unwrap()
+                .ts,
+            -1
+        );
+        assert_eq!(Timestamp::new_micros(1_500).timestamp_millis(), 1);
+        assert_eq!(Timestamp::new_millis(1_500).timestamp(), 1);
+        assert_eq!(Timestamp::new_seconds(2).timestamp_micros(), 2_000_000);
+    }
+
+    #[test]
+    fn test_timestamp_datum_type() {
+        for unit in [
+            TimeUnit::Second,
+            TimeUnit::Millisecond,
+            TimeUnit::Microsecond,
+            TimeUnit::Nanosecond,
+        ] {
+            let datum = Timestamp::new(1717507203, unit).to_arrow_datum();
+            let (array, is_scalar) = datum.get();
+            assert!(is_scalar);
+            assert_eq!(array.data_type(), &DataType::Timestamp(unit.into(), None));
+        }
+    }
+
     #[tokio::test]
     async fn test_timestamp_encode_decode() {
         let ts = Timestamp::new_millis(1717507203412);
@@ -4,9 +4,12 @@ pub mod runtime;
 #[cfg(test)]
 pub(crate) mod test;
 
-use std::{error::Error, fmt::Debug, io, sync::Arc};
+use std::{borrow::Cow, error::Error, fmt::Debug, io, sync::Arc};
 
-use arrow::{array::RecordBatch, datatypes::Schema as ArrowSchema};
+use arrow::{
+    array::RecordBatch,
+    datatypes::{FieldRef, Schema as ArrowSchema},
+};
 use fusio_log::{Decode, Encode};
 pub use key::*;
 use option::OptionRecordRef;
@@ -35,6 +38,29 @@ pub trait Schema: Debug + Send + Sync {
     /// the location of the primary key column in the parquet schema and the sort order within a
     /// RowGroup of a leaf column
     fn primary_key_path(&self) -> (ColumnPath, Vec<SortingColumn>);
+
+    /// Checks that the record can be written under this schema. Statically typed records are
+    /// always valid, dynamic ones are checked against the declared columns.
+    fn validate_record(&self, _record: &Self::Record) -> Result<(), RecordValidationError> {
+        Ok(())
+    }
+
+    /// Returns the index of the column `name` in [`Schema::arrow_schema`]
+    fn column_index(&self, name: &str) -> Option<usize> {
+        self.arrow_schema().index_of(name).ok()
+    }
+
+    /// Checks that `key` has the datatype of the primary key column. Statically typed keys are
+    /// always valid.
+    fn validate_key(&self, _key: &Self::Key) -> Result<(), KeyTypeMismatch> {
+        Ok(())
+    }
+
+    /// Returns the key to look up for `key`, converting it to the primary key datatype when the
+    /// schema allows it. Defaults to [`Schema::validate_key`].
+    fn coerce_key<'k>(&self, key: &'k Self::Key) -> Result<Cow<'k, Self::Key>, KeyTypeMismatch> {
+        self.validate_key(key).map(|_| Cow::Borrowed(key))
+    }
 }
 
 pub trait Record: 'static + Sized + Decode + Debug + Send + Sync {
@@ -69,6 +95,11 @@ pub trait RecordRef<'r>: Clone + Sized + Encode + Send + Sync {
     /// **Note**: Primary key column are always kept.
     fn projection(&mut self, projection_mask: &ProjectionMask);
 
+    /// Returns whether the field at `index` is null, the first field of the [`Schema`] is `0`.
+    ///
+    /// **Note**: Fields removed by [`RecordRef::projection`] are null, the primary key never is.
+    fn is_null(&self, index: usize) -> bool;
+
     /// Get the [`RecordRef`] from the [`RecordBatch`] at the given offset.
     ///
     /// `full_schema` is the combination of `_null`, `_ts` and all fields defined in the [`Schema`].
@@ -105,3 +136,48 @@ pub enum RecordDecodeError {
     #[error("record fusio error: {0}")]
     Fusio(#[from] fusio::Error),
 }
+
+#[derive(Debug, Error)]
+pub enum RecordValidationError {
+    #[error("record has no columns")]
+    Empty,
+    #[error("record has {actual} columns, but the schema requires {expected}")]
+    ColumnCount { expected: usize, actual: usize },
+    #[error("record primary key index is {actual}, but the schema requires {expected}")]
+    PrimaryIndex { expected: usize, actual: usize },
+    #[error("column {column} stores timestamps in {expected:?}, but the value is in {actual:?}")]
+    TimestampUnitMismatch {
+        column: String,
+        expected: TimeUnit,
+        actual: TimeUnit,
+    },
+    #[error("column {column} has datatype {expected:?}, but the value has datatype {actual:?}")]
+    ColumnTypeMismatch {
+        column: String,
+        expected: DataType,
+        actual: DataType,
+    },
+    #[error("column {column} has nullable set to {expected}, but the value has {actual}")]
+    NullableMismatch {
+        column: String,
+        expected: bool,
+        actual: bool,
+    },
+    #[error("column {column} is not nullable, but the value is null")]
+    NullValue { column: String },
+    #[error("value of column {column} is not stored as a {datatype:?} value")]
+    InvalidValue { column: String, datatype: DataType },
+    #[error("record batch column {index} is {actual:?}, but the schema requires {expected:?}")]
+    BatchColumnMismatch {
+        index: usize,
+        expected: FieldRef,
+        actual: FieldRef,
+    },
+}
+
+#[derive(Debug, Error)]
+#[error("key datatype is {actual:?}, but the primary key datatype is {expected:?}")]
+pub struct KeyTypeMismatch {
+    pub expected: DataType,
+    pub actual: DataType,
+}
@@ -3,29 +3,38 @@ use std::{any::Any, mem, sync::Arc};
 use arrow::{
     array::{
         Array, ArrayBuilder, ArrayRef, ArrowPrimitiveType, BooleanArray, BooleanBufferBuilder,
-        BooleanBuilder, Date32Builder, Date64Builder, Float32Builder, Float64Builder,
-        GenericBinaryArray, GenericBinaryBuilder, LargeStringArray, LargeStringBuilder,
-        PrimitiveArray, PrimitiveBuilder, StringArray, StringBuilder, Time32MillisecondArray,
-        Time32MillisecondBuilder, Time32SecondArray, Time32SecondBuilder, Time64MicrosecondArray,
-        Time64MicrosecondBuilder, Time64NanosecondArray, Time64NanosecondBuilder,
-        TimestampMicrosecondArray, TimestampMicrosecondBuilder, TimestampMillisecondArray,
-        TimestampMillisecondBuilder, TimestampNanosecondArray, TimestampNanosecondBuilder,
-        TimestampSecondArray, TimestampSecondBuilder, UInt32Builder,
+        BooleanBuilder, Date32Builder, Date64Builder, Decimal128Array, Decimal128Builder,
+        Float32Builder, Float64Builder, GenericBinaryArray, GenericBinaryBuilder,
+        IntervalMonthDayNanoArray, IntervalMonthDayNanoBuilder, LargeStringArray,
+        LargeStringBuilder, ListArray, PrimitiveArray, PrimitiveBuilder, StringArray,
+        StringBuilder, Time32MillisecondArray, Time32MillisecondBuilder, Time32SecondArray,
+        Time32SecondBuilder, Time64MicrosecondArray, Time64MicrosecondBuilder,
+        Time64NanosecondArray, Time64NanosecondBuilder, TimestampMicrosecondArray,
+        TimestampMicrosecondBuilder, TimestampMillisecondArray, TimestampMillisecondBuilder,
+        TimestampNanosecondArray, TimestampNanosecondBuilder, TimestampSecondArray,
+        TimestampSecondBuilder, UInt32Builder,
     },
     datatypes::{
-        Date32Type, Date64Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
-        Int8Type, Schema as ArrowSchema, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+        DataType as ArrowDataType, Date32Type, Date64Type, Decimal128Type, Float32Type,
+        Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, IntervalMonthDayNanoType,
+        Schema as ArrowSchema, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
     },
 };
 
-use super::{record::DynRecord, record_ref::DynRecordRef, value::Value, DataType};
+use super::{
+    list::{append_list, list_builder, list_items, list_written_size, DynListBuilder},
+    record::DynRecord,
+    record_ref::DynRecordRef,
+    value::Value,
+    DataType,
+};
 use crate::{
     cast_arc_value,
     inmem::immutable::{ArrowArrays, Builder},
     magic::USER_COLUMN_OFFSET,
     record::{
-        Date32, Date64, Key, LargeBinary, LargeString, Record, Schema, Time32, Time64, TimeUnit,
-        Timestamp, F32, F64,
+        BigInt, Date32, Date64, Decimal128, Interval, Key, LargeBinary, LargeString, Record,
+        Schema, Time32, Time64, TimeUnit, Timestamp, F32, F64,
     },
     timestamp::Ts,
 };
@@ -86,7 +95,7 @@ macro_rules! implement_arrow_array {
                 let mut builders: Vec<Box<dyn ArrayBuilder + Send + Sync>> = vec![];
                 let mut datatypes = vec![];
                 for field in schema.fields().iter().skip(2) {
-                    let datatype = DataType::from(field.data_type());
+                    let datatype = DataType::from_field(field);
                     match &datatype {
                         $(
                             $primitive_pat => {
@@ -108,6 +117,20 @@ macro_rules! implement_arrow_array {
                                 builders.push(Box::new(<$builder_ty2>::with_capacity(capacity)));
                             }
                         )*
+                        DataType::BigInt => {
+                            builders.push(Box::new(GenericBinaryBuilder::<i32>::with_capacity(
+                                capacity, 0,
+                            )));
+                        }
+                        DataType::Decimal128(precision, scale) => {
+                            builders.push(Box::new(
+                                Decimal128Builder::with_capacity(capacity)
+                                    .with_data_type(ArrowDataType::Decimal128(*precision, *scale)),
+                            ));
+                        }
+                        DataType::List(item) => {
+                            builders.push(Box::new(list_builder(item, capacity)));
+                        }
                         DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                     }
                     datatypes.push(datatype);
@@ -189,6 +212,34 @@ macro_rules! implement_arrow_array {
                                     }
                                 }
                             )*
+                            DataType::BigInt => {
+                                let v = BigInt::from_encoded_unchecked(
+                                    cast_arc_value!(col.value, GenericBinaryArray<i32>).value(offset),
+                                );
+                                if primary_key_index == idx {
+                                    Arc::new(v)
+                                } else {
+                                    Arc::new(Some(v))
+                                }
+                            }
+                            DataType::Decimal128(precision, scale) => {
+                                let v = Decimal128 {
+                                    value: Self::primitive_value::<Decimal128Type>(col, offset),
+                                    precision: *precision,
+                                    scale: *scale,
+                                };
+                                if primary_key_index == idx {
+                                    Arc::new(v)
+                                } else {
+                                    Arc::new(Some(v))
+                                }
+                            }
+                            DataType::List(item) => {
+                                let array = cast_arc_value!(col.value, ListArray);
+                                let v = (!array.is_null(offset))
+                                    .then(|| list_items(item, array, offset));
+                                Arc::new(v)
+                            }
                             DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                         };
 
@@ -281,6 +332,15 @@ macro_rules! implement_builder_array {
                                         }
                                     }
                                 )*
+                                DataType::List(_) => {
+                                    let bd =
+                                        Self::as_builder_mut::<DynListBuilder>(builder.as_mut());
+                                    match cast_arc_value!(col.value, Option<Vec<Value>>) {
+                                        Some(items) => append_list(bd, items),
+                                        None if col.is_nullable() => bd.append_null(),
+                                        None => bd.append(true),
+                                    }
+                                }
                                 DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                             }
                         }
@@ -318,6 +378,10 @@ macro_rules! implement_builder_array {
                                             .append_value(Default::default());
                                     }
                                 )*
+                                DataType::List(_) => {
+                                    Self::as_builder_mut::<DynListBuilder>(builder.as_mut())
+                                        .append(true);
+                                }
                                 DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                             }
                         }
@@ -353,6 +417,10 @@ macro_rules! implement_builder_array {
                                         .values_slice()
                                 ),
                             )*
+                            DataType::List(item) => list_written_size(
+                                item,
+                                Self::as_builder::<DynListBuilder>(builder.as_ref()),
+                            ),
                             DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                         }
                     })
@@ -380,7 +448,7 @@ macro_rules! implement_builder_array {
                                         .finish(),
                                 );
                                 columns.push(Value::new(
-                                    *datatype,
+                                    datatype.clone(),
                                     field.name().to_owned(),
                                     value.clone(),
                                     is_nullable,
@@ -405,7 +473,7 @@ macro_rules! implement_builder_array {
                                 let value =
                                     Arc::new(Self::as_builder_mut::<$builder_ty>(builder.as_mut()).finish());
                                 columns.push(Value::new(
-                                    *datatype,
+                                    datatype.clone(),
                                     field.name().to_owned(),
                                     value.clone(),
                                     is_nullable,
@@ -421,13 +489,25 @@ macro_rules! implement_builder_array {
                                 );
                                 array_refs.push(value.clone());
                                 columns.push(Value::new(
-                                    *datatype,
+                                    datatype.clone(),
                                     field.name().to_owned(),
                                     value,
                                     is_nullable,
                                 ));
                             }
                         )*
+                        DataType::List(_) => {
+                            let value = Arc::new(
+                                Self::as_builder_mut::<DynListBuilder>(builder.as_mut()).finish(),
+                            );
+                            columns.push(Value::new(
+                                datatype.clone(),
+                                field.name().to_owned(),
+                                value.clone(),
+                                is_nullable,
+                            ));
+                            array_refs.push(value);
+                        }
                         DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                     };
                 }
@@ -480,6 +560,7 @@ macro_rules! implement_builder_array {
                                 .append_value(cast_arc_value!(col.value, $alt_ty2).$value_fn())
                         }
                     )*
+                    DataType::List(_) => unreachable!("list can not be used as primary key"),
                     DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 }
             }
@@ -502,6 +583,7 @@ implement_arrow_array!(
         { f64, F64, DataType::Float64, Float64Type },
         { i32, Date32, DataType::Date32, Date32Type },
         { i64, Date64, DataType::Date64, Date64Type },
+        { arrow::datatypes::IntervalMonthDayNano, Interval, DataType::Interval, IntervalMonthDayNanoType },
     },
     // f32, f64, and bool are special cases, they are handled separately
     {
@@ -542,13 +624,16 @@ implement_builder_array!(
         { String, DataType::String, StringBuilder, StringArray },
         { LargeString, DataType::LargeString, LargeStringBuilder, LargeStringArray },
         { Vec<u8>, DataType::Bytes, GenericBinaryBuilder<i32>, GenericBinaryArray<i32> },
-        { LargeBinary, DataType::LargeBinary, GenericBinaryBuilder<i64>, GenericBinaryArray<i64> }
+        { LargeBinary, DataType::LargeBinary, GenericBinaryBuilder<i64>, GenericBinaryArray<i64> },
+        { BigInt, DataType::BigInt, GenericBinaryBuilder<i32>, GenericBinaryArray<i32> }
     },
     {
         { F32, DataType::Float32, Float32Builder, Float32Array, value },
         { F64, DataType::Float64, Float64Builder, Float64Array, value },
         { Date32, DataType::Date32, Date32Builder, Date32Array, value },
         { Date64, DataType::Date64, Date64Builder, Date64Array, value },
+        { Interval, DataType::Interval, IntervalMonthDayNanoBuilder, IntervalMonthDayNanoArray, value },
+        { Decimal128, DataType::Decimal128(..), Decimal128Builder, Decimal128Array, value },
         { Timestamp, DataType::Timestamp(TimeUnit::Second), TimestampSecondBuilder, TimestampSecondArray, timestamp },
         { Timestamp, DataType::Timestamp(TimeUnit::Millisecond), TimestampMillisecondBuilder,  TimestampMillisecondArray, timestamp_millis },
         { Timestamp, DataType::Timestamp(TimeUnit::Microsecond),TimestampMicrosecondBuilder, TimestampMicrosecondArray, timestamp_micros },
@@ -563,12 +648,17 @@ implement_builder_array!(
 #[cfg(test)]
 mod tests {
 
+    use arrow::datatypes::DataType as ArrowDataType;
     use parquet::arrow::ProjectionMask;
 
     use crate::{
         dyn_record, dyn_schema,
         inmem::immutable::{ArrowArrays, Builder},
-        record::{DynRecordImmutableArrays, DynRecordRef, Record, RecordRef, Schema, F32, F64},
+        make_dyn_record, make_dyn_schema,
+        record::{
+            BigInt, DataType, Decimal128, DynRecordImmutableArrays, DynRecordRef, Interval, Record,
+            RecordRef, Schema, F32, F64,
+        },
     };
 
     #[tokio::test]
@@ -687,4 +777,176 @@ mod tests {
             );
         }
     }
+
+    #[tokio::test]
+    async fn test_build_interval_array() {
+        let schema = dyn_schema!(
+            ("id", UInt32, false),
+            ("period", Interval, false),
+            ("offset", Interval, true),
+            ("none", Interval, true),
+            0
+        );
+        let record = dyn_record!(
+            ("id", UInt32, false, 1_u32),
+            (
+                "period",
+                Interval,
+                false,
+                Interval::new(-1, 3, -1_500_000_000)
+            ),
+            (
+                "offset",
+                Interval,
+                true,
+                Some(Interval::new(2, -7, i64::MIN))
+            ),
+            ("none", Interval, true, None::<Interval>),
+            0
+        );
+
+        let mut builder = DynRecordImmutableArrays::builder(schema.arrow_schema().clone(), 2);
+        let key = crate::timestamp::Ts {
+            ts: 0.into(),
+            value: record.key(),
+        };
+        builder.push(key, Some(record.as_record_ref()));
+        let arrays = builder.finish(None);
+
+        let res = arrays.get(0, &ProjectionMask::all());
+        let cols = res.unwrap().unwrap().columns;
+        assert_eq!(
+            cols[1].value.downcast_ref::<Option<Interval>>(),
+            Some(&Some(Interval::new(-1, 3, -1_500_000_000)))
+        );
+        assert_eq!(
+            cols[2].value.downcast_ref::<Option<Interval>>(),
+            Some(&Some(Interval::new(2, -7, i64::MIN)))
+        );
+
+        let mask = ProjectionMask::all();
+        let record_ref = DynRecordRef::from_record_batch(
+            arrays.as_record_batch(),
+            0,
+            &mask,
+            schema.arrow_schema(),
+        );
+        assert_eq!(
+            record_ref.get().unwrap().columns,
+            record.as_record_ref().columns
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_decimal_array() {
+        let schema = make_dyn_schema!(
+            ("id", DataType::Decimal128(10, 2), false),
+            ("price", DataType::Decimal128(20, -3), true),
+            0
+        );
+        let record = make_dyn_record!(
+            (
+                "id",
+                DataType::Decimal128(10, 2),
+                false,
+                Decimal128::new(-12_345, 10, 2)
+            ),
+            (
+                "price",
+                DataType::Decimal128(20, -3),
+                true,
+                Some(Decimal128::new(42, 20, -3))
+            ),
+            0
+        );
+
+        let mut builder = DynRecordImmutableArrays::builder(schema.arrow_schema().clone(), 1);
+        let key = crate::timestamp::Ts {
+            ts: 0.into(),
+            value: record.key(),
+        };
+        builder.push(key, Some(record.as_record_ref()));
+        let arrays = builder.finish(None);
+
+        let record_batch = arrays.as_record_batch();
+        assert_eq!(
+            record_batch.column(2).data_type(),
+            &ArrowDataType::Decimal128(10, 2)
+        );
+        assert_eq!(
+            record_batch.column(3).data_type(),
+            &ArrowDataType::Decimal128(20, -3)
+        );
+
+        let cols = arrays
+            .get(0, &ProjectionMask::all())
+            .unwrap()
+            .unwrap()
+            .columns;
+        assert_eq!(
+            cols[0].value.downcast_ref::<Decimal128>(),
+            Some(&Decimal128::new(-12_345, 10, 2))
+        );
+        assert_eq!(
+            cols[1].value.downcast_ref::<Option<Decimal128>>(),
+            Some(&Some(Decimal128::new(42, 20, -3)))
+        );
+
+        let mask = ProjectionMask::all();
+        let record_ref =
+            DynRecordRef::from_record_batch(record_batch, 0, &mask, schema.arrow_schema());
+        assert_eq!(
+            record_ref.get().unwrap().columns,
+            record.as_record_ref().columns
+        );
+    }
+
+    #[tokio::test]
+    async fn test_build_bigint_array() {
+        let schema = dyn_schema!(("id", BigInt, false), ("balance", BigInt, true), 0);
+        let field = schema.arrow_schema().field(2);
+        assert_eq!(field.data_type(), &ArrowDataType::Binary);
+        assert_eq!(DataType::from_field(field), DataType::BigInt);
+
+        let id = "123456789012345678901234567890123456789012"
+            .parse::<BigInt>()
+            .unwrap();
+        let balance = BigInt::from(-99_i128);
+        let record = dyn_record!(
+            ("id", BigInt, false, id.clone()),
+            ("balance", BigInt, true, Some(balance.clone())),
+            0
+        );
+
+        let mut builder = DynRecordImmutableArrays::builder(schema.arrow_schema().clone(), 1);
+        let key = crate::timestamp::Ts {
+            ts: 0.into(),
+            value: record.key(),
+        };
+        builder.push(key, Some(record.as_record_ref()));
+        let arrays = builder.finish(None);
+
+        let cols = arrays
+            .get(0, &ProjectionMask::all())
+            .unwrap()
+            .unwrap()
+            .columns;
+        assert_eq!(cols[0].value.downcast_ref::<BigInt>(), Some(&id));
+        assert_eq!(
+            cols[1].value.downcast_ref::<Option<BigInt>>(),
+            Some(&Some(balance))
+        );
+
+        let mask = ProjectionMask::all();
+        let record_ref = DynRecordRef::from_record_batch(
+            arrays.as_record_batch(),
+            0,
+            &mask,
+            schema.arrow_schema(),
+        );
+        assert_eq!(
+            record_ref.get().unwrap().columns,
+            record.as_record_ref().columns
+        );
+    }
 }
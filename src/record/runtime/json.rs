@@ -0,0 +1,386 @@
+use std::{any::Any, sync::Arc};
+
+use serde_json::{json, Map, Number, Value as Json};
+use thiserror::Error;
+
+use super::{list::LIST_ITEM_NAME, DataType, DynRecord, DynSchema, Value, ValueDesc};
+use crate::record::{BigInt, Date32, Date64, Decimal128, Interval, TimeUnit, Timestamp, F32, F64};
+
+#[derive(Debug, Error)]
+pub enum JsonValueError {
+    #[error("json {actual} of column {column} can not be converted to {expected:?}")]
+    TypeMismatch {
+        column: String,
+        expected: DataType,
+        actual: Json,
+    },
+    #[error("null json for non-nullable column {0}")]
+    NullNotAllowed(String),
+    #[error("json of a record must be an object, got {0}")]
+    NotAnObject(Json),
+    #[error("column {0} does not exist in the schema")]
+    UnknownColumn(String),
+}
+
+fn unit_name(unit: TimeUnit) -> &'static str {
+    match unit {
+        TimeUnit::Second => "second",
+        TimeUnit::Millisecond => "millisecond",
+        TimeUnit::Microsecond => "microsecond",
+        TimeUnit::Nanosecond => "nanosecond",
+    }
+}
+
+fn parse_unit(name: &str) -> Option<TimeUnit> {
+    match name {
+        "second" => Some(TimeUnit::Second),
+        "millisecond" => Some(TimeUnit::Millisecond),
+        "microsecond" => Some(TimeUnit::Microsecond),
+        "nanosecond" => Some(TimeUnit::Nanosecond),
+        _ => None,
+    }
+}
+
+/// json numbers can not be NaN or infinite, such floats are written as strings
+fn float_json(value: f64) -> Json {
+    Number::from_f64(value).map_or_else(|| Json::String(value.to_string()), Json::Number)
+}
+
+fn float(json: &Json) -> Option<f64> {
+    json.as_f64().or_else(|| json.as_str()?.parse().ok())
+}
+
+fn unsigned<T: TryFrom<u64>>(json: &Json) -> Option<T> {
+    json.as_u64()?.try_into().ok()
+}
+
+fn signed<T: TryFrom<i64>>(json: &Json) -> Option<T> {
+    json.as_i64()?.try_into().ok()
+}
+
+impl Value {
+    /// convert to json according to the datatype: numbers, booleans and strings as they are,
+    /// binaries as arrays of bytes, lists as arrays and null values as `null`. Big integers and
+    /// the unscaled values of decimals are decimal strings, timestamps are objects holding the
+    /// `value` and its `unit` and intervals are objects of `months`, `days` and `nanoseconds`
+    pub fn to_json(&self) -> Json {
+        let json = match self.datatype() {
+            DataType::UInt8 => self.get::<u8>().map(|v| json!(v)),
+            DataType::UInt16 => self.get::<u16>().map(|v| json!(v)),
+            DataType::UInt32 => self.get::<u32>().map(|v| json!(v)),
+            DataType::UInt64 => self.get::<u64>().map(|v| json!(v)),
+            DataType::Int8 => self.get::<i8>().map(|v| json!(v)),
+            DataType::Int16 => self.get::<i16>().map(|v| json!(v)),
+            DataType::Int32 => self.get::<i32>().map(|v| json!(v)),
+            DataType::Int64 => self.get::<i64>().map(|v| json!(v)),
+            DataType::Float32 => self.get::<F32>().map(|v| float_json(v.value().into())),
+            DataType::Float64 => self.get::<F64>().map(|v| float_json(v.value())),
+            DataType::String | DataType::LargeString => self.get::<String>().map(|v| json!(v)),
+            DataType::Boolean => self.get::<bool>().map(|v| json!(v)),
+            DataType::Bytes | DataType::LargeBinary => self.get::<Vec<u8>>().map(|v| json!(v)),
+            DataType::Timestamp(_) => self
+                .get::<Timestamp>()
+                .map(|v| json!({ "value": v.ts, "unit": unit_name(v.unit()) })),
+            DataType::Date32 => self.get::<Date32>().map(|v| json!(v.value())),
+            DataType::Date64 => self.get::<Date64>().map(|v| json!(v.value())),
+            DataType::Interval => self.get::<Interval>().map(|v| {
+                json!({ "months": v.months(), "days": v.days(), "nanoseconds": v.nanoseconds() })
+            }),
+            DataType::Decimal128(..) => self
+                .get::<Decimal128>()
+                .map(|v| json!(v.value().to_string())),
+            DataType::BigInt => self.get::<BigInt>().map(|v| json!(v.to_string())),
+            DataType::List(_) => self
+                .get::<Vec<Value>>()
+                .map(|items| Json::Array(items.iter().map(Value::to_json).collect())),
+            DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
+        };
+        json.unwrap_or(Json::Null)
+    }
+
+    /// build a value described by `desc` from json written by [`Value::to_json`]. `null` is only
+    /// accepted for nullable columns, timestamps are converted to the unit of the column
+    pub fn from_json(json: &Json, desc: ValueDesc) -> Result<Self, JsonValueError> {
+        fn wrap<T: Send + Sync + 'static>(
+            value: T,
+            desc: &ValueDesc,
+        ) -> Arc<dyn Any + Send + Sync> {
+            if desc.is_nullable {
+                Arc::new(Some(value))
+            } else {
+                Arc::new(value)
+            }
+        }
+
+        if json.is_null() {
+            if !desc.is_nullable {
+                return Err(JsonValueError::NullNotAllowed(desc.name));
+            }
+            return Ok(Value::with_none_value(desc.datatype, desc.name, true));
+        }
+        let value = match &desc.datatype {
+            DataType::UInt8 => unsigned::<u8>(json).map(|v| wrap(v, &desc)),
+            DataType::UInt16 => unsigned::<u16>(json).map(|v| wrap(v, &desc)),
+            DataType::UInt32 => unsigned::<u32>(json).map(|v| wrap(v, &desc)),
+            DataType::UInt64 => unsigned::<u64>(json).map(|v| wrap(v, &desc)),
+            DataType::Int8 => signed::<i8>(json).map(|v| wrap(v, &desc)),
+            DataType::Int16 => signed::<i16>(json).map(|v| wrap(v, &desc)),
+            DataType::Int32 => signed::<i32>(json).map(|v| wrap(v, &desc)),
+            DataType::Int64 => signed::<i64>(json).map(|v| wrap(v, &desc)),
+            DataType::Float32 => float(json).map(|v| wrap(F32::from(v as f32), &desc)),
+            DataType::Float64 => float(json).map(|v| wrap(F64::from(v), &desc)),
+            DataType::String | DataType::LargeString => {
+                json.as_str().map(|v| wrap(v.to_string(), &desc))
+            }
+            DataType::Boolean => json.as_bool().map(|v| wrap(v, &desc)),
+            DataType::Bytes | DataType::LargeBinary => json
+                .as_array()
+                .and_then(|bytes| bytes.iter().map(unsigned::<u8>).collect::<Option<Vec<_>>>())
+                .map(|v| wrap(v, &desc)),
+            DataType::Timestamp(unit) => {
+                let value = json.get("value").and_then(Json::as_i64);
+                let from = json.get("unit").and_then(Json::as_str).and_then(parse_unit);
+                value
+                    .zip(from)
+                    .and_then(|(value, from)| Timestamp::new(value, from).convert(*unit))
+                    .map(|v| wrap(v, &desc))
+            }
+            DataType::Date32 => signed::<i32>(json).map(|v| wrap(Date32::new(v), &desc)),
+            DataType::Date64 => signed::<i64>(json).map(|v| wrap(Date64::new(v), &desc)),
+            DataType::Interval => {
+                let months = json.get("months").and_then(signed::<i32>);
+                let days = json.get("days").and_then(signed::<i32>);
+                let nanoseconds = json.get("nanoseconds").and_then(signed::<i64>);
+                months
+                    .zip(days)
+                    .zip(nanoseconds)
+                    .map(|((months, days), nanoseconds)| {
+                        wrap(Interval::new(months, days, nanoseconds), &desc)
+                    })
+            }
+            DataType::Decimal128(precision, scale) => json
+                .as_str()
+                .and_then(|v| v.parse::<i128>().ok())
+                .map(|v| wrap(Decimal128::new(v, *precision, *scale), &desc)),
+            DataType::BigInt => json
+                .as_str()
+                .and_then(|v| v.parse::<BigInt>().ok())
+                .map(|v| wrap(v, &desc)),
+            DataType::List(item) => match json.as_array() {
+                Some(items) => {
+                    let item_desc =
+                        ValueDesc::new(LIST_ITEM_NAME.to_string(), *item.clone(), false);
+                    let items = items
+                        .iter()
+                        .map(|item| Value::from_json(item, item_desc.clone()))
+                        .collect::<Result<Vec<_>, _>>()?;
+                    Some(wrap(items, &desc))
+                }
+                None => None,
+            },
+            DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
+        };
+
+        match value {
+            Some(value) => Ok(Value { desc, value }),
+            None => Err(JsonValueError::TypeMismatch {
+                column: desc.name,
+                expected: desc.datatype,
+                actual: json.clone(),
+            }),
+        }
+    }
+}
+
+impl DynRecord {
+    /// a json object mapping the name of each column to its value, see [`Value::to_json`]
+    pub fn to_json(&self) -> Json {
+        let columns = self
+            .values
+            .iter()
+            .map(|value| (value.desc.name.clone(), value.to_json()))
+            .collect::<Map<_, _>>();
+        Json::Object(columns)
+    }
+
+    /// build a record of `schema` from a json object written by [`DynRecord::to_json`], see
+    /// [`Value::from_json`]. Missing columns are null, names of other columns are rejected
+    pub fn from_json(json: &Json, schema: &DynSchema) -> Result<Self, JsonValueError> {
+        let columns = json
+            .as_object()
+            .ok_or_else(|| JsonValueError::NotAnObject(json.clone()))?;
+        if let Some(name) = columns
+            .keys()
+            .find(|name| schema.schema.iter().all(|desc| &desc.name != *name))
+        {
+            return Err(JsonValueError::UnknownColumn(name.clone()));
+        }
+
+        let values = schema
+            .schema
+            .iter()
+            .map(|desc| {
+                Value::from_json(columns.get(&desc.name).unwrap_or(&Json::Null), desc.clone())
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(DynRecord::new(values, schema.primary_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value as Json};
+
+    use super::JsonValueError;
+    use crate::{
+        dyn_schema, make_dyn_schema,
+        record::{
+            BigInt, DataType, Decimal128, DynRecord, Interval, TimeUnit, Timestamp, Value,
+            ValueDesc, F64,
+        },
+    };
+
+    #[test]
+    fn test_record_json_round_trip() {
+        let schema = make_dyn_schema!(
+            ("name", DataType::String, false),
+            ("age", DataType::Int32, true),
+            ("created", DataType::Timestamp(TimeUnit::Millisecond), false),
+            0
+        );
+        let record = DynRecord::new(
+            vec![
+                Value::new(
+                    DataType::String,
+                    "name".to_string(),
+                    Arc::new("alice".to_string()),
+                    false,
+                ),
+                Value::new(
+                    DataType::Int32,
+                    "age".to_string(),
+                    Arc::new(None::<i32>),
+                    true,
+                ),
+                Value::new(
+                    DataType::Timestamp(TimeUnit::Millisecond),
+                    "created".to_string(),
+                    Arc::new(Timestamp::new_millis(1_700_000_000_000)),
+                    false,
+                ),
+            ],
+            0,
+        );
+
+        let json = record.to_json();
+        assert_eq!(
+            json,
+            json!({
+                "name": "alice",
+                "age": null,
+                "created": { "value": 1_700_000_000_000_i64, "unit": "millisecond" },
+            })
+        );
+        let decoded = DynRecord::from_json(&json, &schema).unwrap();
+        assert!(decoded.diff(&record).is_empty());
+        assert_eq!(decoded.to_json(), json);
+
+        // missing nullable columns are null, timestamps are converted to the unit of the column
+        let json = json!({
+            "name": "bob",
+            "created": { "value": 1_700_000_000, "unit": "second" },
+        });
+        let decoded = DynRecord::from_json(&json, &schema).unwrap();
+        assert!(decoded.values[1].is_null());
+        assert_eq!(
+            decoded.values[2].get::<Timestamp>(),
+            Some(&Timestamp::new_millis(1_700_000_000_000))
+        );
+    }
+
+    #[test]
+    fn test_value_json_round_trip() {
+        let values = [
+            Value::new(DataType::UInt64, "a".to_string(), Arc::new(u64::MAX), false),
+            Value::new(
+                DataType::Float64,
+                "b".to_string(),
+                Arc::new(Some(F64::from(f64::NAN))),
+                true,
+            ),
+            Value::new(
+                DataType::Bytes,
+                "c".to_string(),
+                Arc::new(vec![0_u8, 255]),
+                false,
+            ),
+            Value::new(
+                DataType::Interval,
+                "d".to_string(),
+                Arc::new(Interval::new(1, -2, 3)),
+                false,
+            ),
+            Value::new(
+                DataType::Decimal128(38, 2),
+                "e".to_string(),
+                Arc::new(Decimal128::new(i128::MAX, 38, 2)),
+                false,
+            ),
+            Value::new(
+                DataType::BigInt,
+                "f".to_string(),
+                Arc::new(Some(
+                    "-123456789012345678901234567890".parse::<BigInt>().unwrap(),
+                )),
+                true,
+            ),
+            Value::new(
+                DataType::List(Box::new(DataType::Int16)),
+                "g".to_string(),
+                Arc::new(Some(vec![Value::new(
+                    DataType::Int16,
+                    "item".to_string(),
+                    Arc::new(-1_i16),
+                    false,
+                )])),
+                true,
+            ),
+        ];
+        for value in values {
+            let json = value.to_json();
+            let desc = ValueDesc::new(value.name(), value.datatype(), value.is_nullable());
+            let decoded = Value::from_json(&json, desc).unwrap();
+            assert_eq!(decoded.to_json(), json);
+            assert!(decoded.is_well_formed());
+        }
+    }
+
+    #[test]
+    fn test_json_errors() {
+        let schema = dyn_schema!(("id", Int64, false), ("flag", Boolean, true), 0);
+
+        let desc = ValueDesc::new("id".to_string(), DataType::Int8, false);
+        assert!(matches!(
+            Value::from_json(&json!(300), desc.clone()),
+            Err(JsonValueError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            Value::from_json(&Json::Null, desc),
+            Err(JsonValueError::NullNotAllowed(name)) if name == "id"
+        ));
+        assert!(matches!(
+            DynRecord::from_json(&json!([1]), &schema),
+            Err(JsonValueError::NotAnObject(_))
+        ));
+        assert!(matches!(
+            DynRecord::from_json(&json!({ "id": 1, "other": true }), &schema),
+            Err(JsonValueError::UnknownColumn(name)) if name == "other"
+        ));
+        assert!(matches!(
+            DynRecord::from_json(&json!({ "id": 1, "flag": "yes" }), &schema),
+            Err(JsonValueError::TypeMismatch { column, .. }) if column == "flag"
+        ));
+    }
+}
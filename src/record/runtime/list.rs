@@ -0,0 +1,163 @@
+use std::{any::Any, mem, sync::Arc};
+
+use arrow::{
+    array::{
+        Array, ArrayBuilder, BinaryArray, BinaryBuilder, BooleanArray, BooleanBuilder,
+        Float32Array, Float32Builder, Float64Array, Float64Builder, Int16Array, Int16Builder,
+        Int32Array, Int32Builder, Int64Array, Int64Builder, Int8Array, Int8Builder, ListArray,
+        ListBuilder, StringArray, StringBuilder, UInt16Array, UInt16Builder, UInt32Array,
+        UInt32Builder, UInt64Array, UInt64Builder, UInt8Array, UInt8Builder,
+    },
+    datatypes::Field,
+};
+use fusio::{SeqRead, Write};
+use fusio_log::{Decode, Encode};
+
+use super::{DataType, Value, ValueDesc};
+use crate::{
+    cast_arc_value,
+    record::{F32, F64},
+};
+
+/// the name of the item field of list columns, the one arrow uses by default
+pub(crate) const LIST_ITEM_NAME: &str = "item";
+
+/// the builder of a list column, the items are built by the builder of their datatype
+pub(crate) type DynListBuilder = ListBuilder<Box<dyn ArrayBuilder>>;
+
+/// the arrow field of the items of a list column, list items are never null
+pub(crate) fn item_field(item: &DataType) -> Field {
+    ValueDesc::new(LIST_ITEM_NAME.to_string(), item.clone(), false).arrow_field()
+}
+
+fn new_item(item: &DataType, value: Arc<dyn Any + Send + Sync>) -> Value {
+    Value::new(item.clone(), LIST_ITEM_NAME.to_string(), value, false)
+}
+
+macro_rules! implement_list {
+    ($( { $ty:ty, $pat:pat, $builder_ty:ty, $array_ty:ty, $native_fn:ident } ),* $(,)?) => {
+        pub(crate) fn list_builder(item: &DataType, capacity: usize) -> DynListBuilder {
+            let values: Box<dyn ArrayBuilder> = match item {
+                $(
+                    $pat => Box::new(<$builder_ty>::new()),
+                )*
+                _ => unreachable!("list of {:?} is not supported", item),
+            };
+            ListBuilder::with_capacity(values, capacity).with_field(item_field(item))
+        }
+
+        /// append a non-null list of `items`
+        pub(crate) fn append_list(builder: &mut DynListBuilder, items: &[Value]) {
+            let values = builder.values().as_any_mut();
+            for item in items {
+                match &item.desc.datatype {
+                    $(
+                        $pat => values
+                            .downcast_mut::<$builder_ty>()
+                            .unwrap()
+                            .append_value(cast_arc_value!(item.value, $ty).$native_fn()),
+                    )*
+                    datatype => unreachable!("list of {:?} is not supported", datatype),
+                }
+            }
+            builder.append(true);
+        }
+
+        /// the items of the list at `offset` of `array`
+        pub(crate) fn list_items(item: &DataType, array: &ListArray, offset: usize) -> Vec<Value> {
+            let values = array.value(offset);
+            match item {
+                $(
+                    $pat => {
+                        let values = values.as_any().downcast_ref::<$array_ty>().unwrap();
+                        (0..values.len())
+                            .map(|index| new_item(item, Arc::new(<$ty>::from(values.value(index)))))
+                            .collect()
+                    }
+                )*
+                _ => unreachable!("list of {:?} is not supported", item),
+            }
+        }
+
+        pub(crate) fn list_written_size(item: &DataType, builder: &DynListBuilder) -> usize {
+            let values = builder.values_ref().as_any();
+            mem::size_of_val(builder.offsets_slice())
+                + match item {
+                    $(
+                        $pat => mem::size_of_val(
+                            values.downcast_ref::<$builder_ty>().unwrap().values_slice(),
+                        ),
+                    )*
+                    _ => unreachable!("list of {:?} is not supported", item),
+                }
+        }
+
+        /// encode the number of `items` followed by the items
+        pub(crate) async fn encode_items<W>(
+            items: &[Value],
+            writer: &mut W,
+        ) -> Result<(), fusio::Error>
+        where
+            W: Write,
+        {
+            (items.len() as u32).encode(writer).await?;
+            for item in items {
+                match &item.desc.datatype {
+                    $(
+                        $pat => cast_arc_value!(item.value, $ty).encode(writer).await?,
+                    )*
+                    datatype => unreachable!("list of {:?} is not supported", datatype),
+                }
+            }
+            Ok(())
+        }
+
+        pub(crate) fn items_size(items: &[Value]) -> usize {
+            items.iter().fold(mem::size_of::<u32>(), |acc, item| {
+                acc + match &item.desc.datatype {
+                    $(
+                        $pat => cast_arc_value!(item.value, $ty).size(),
+                    )*
+                    datatype => unreachable!("list of {:?} is not supported", datatype),
+                }
+            })
+        }
+
+        pub(crate) async fn decode_items<R>(
+            item: &DataType,
+            reader: &mut R,
+        ) -> Result<Vec<Value>, fusio::Error>
+        where
+            R: SeqRead,
+        {
+            let len = u32::decode(reader).await? as usize;
+            let mut items = Vec::with_capacity(len);
+            for _ in 0..len {
+                let value: Arc<dyn Any + Send + Sync> = match item {
+                    $(
+                        $pat => Arc::new(<$ty>::decode(reader).await?),
+                    )*
+                    _ => unreachable!("list of {:?} is not supported", item),
+                };
+                items.push(new_item(item, value));
+            }
+            Ok(items)
+        }
+    };
+}
+
+implement_list!(
+    { u8, DataType::UInt8, UInt8Builder, UInt8Array, to_owned },
+    { u16, DataType::UInt16, UInt16Builder, UInt16Array, to_owned },
+    { u32, DataType::UInt32, UInt32Builder, UInt32Array, to_owned },
+    { u64, DataType::UInt64, UInt64Builder, UInt64Array, to_owned },
+    { i8, DataType::Int8, Int8Builder, Int8Array, to_owned },
+    { i16, DataType::Int16, Int16Builder, Int16Array, to_owned },
+    { i32, DataType::Int32, Int32Builder, Int32Array, to_owned },
+    { i64, DataType::Int64, Int64Builder, Int64Array, to_owned },
+    { F32, DataType::Float32, Float32Builder, Float32Array, value },
+    { F64, DataType::Float64, Float64Builder, Float64Array, value },
+    { bool, DataType::Boolean, BooleanBuilder, BooleanArray, to_owned },
+    { String, DataType::String, StringBuilder, StringArray, as_str },
+    { Vec<u8>, DataType::Bytes, BinaryBuilder, BinaryArray, as_slice },
+);
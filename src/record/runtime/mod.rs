@@ -1,19 +1,28 @@
 pub(crate) mod array;
+#[cfg(feature = "json")]
+mod json;
+mod list;
 mod record;
 mod record_ref;
+#[cfg(feature = "datafusion")]
+mod scalar;
 mod schema;
 mod value;
 
 pub use array::*;
-use arrow::datatypes::DataType as ArrowDataType;
+use arrow::datatypes::{DataType as ArrowDataType, Field, IntervalUnit};
+#[cfg(feature = "json")]
+pub use json::*;
 pub use record::*;
 pub use record_ref::*;
+#[cfg(feature = "datafusion")]
+pub use scalar::*;
 pub use schema::*;
 pub use value::*;
 
 use crate::record::TimeUnit;
 
-#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub enum DataType {
     UInt8,
     UInt16,
@@ -47,11 +56,84 @@ pub enum DataType {
     ///
     /// See [`arrow::datatypes::DataType::Date64`] for more details.
     Date64,
+    /// A calendar interval of months, days and nanoseconds, which has no meaningful ordering and
+    /// can not be used as the primary key.
+    ///
+    /// See [`arrow::datatypes::IntervalUnit::MonthDayNano`] for more details.
+    Interval,
+    /// A fixed-point decimal with the given precision and scale, stored as a 128-bit unscaled
+    /// value.
+    ///
+    /// See [`arrow::datatypes::DataType::Decimal128`] for more details.
+    Decimal128(u8, i8),
+    /// An arbitrary-precision integer, see [`BigInt`](crate::record::BigInt). It is stored in a
+    /// binary arrow field carrying the [`BIGINT_EXTENSION_NAME`] extension name.
+    BigInt,
+    /// A list of non-null items of the given datatype, stored as a `Vec<Value>`. Only integer,
+    /// float, boolean, string and binary items are supported, see
+    /// [`DataType::can_be_list_item`]. A list can not be used as the primary key.
+    ///
+    /// See [`arrow::datatypes::DataType::List`] for more details.
+    List(Box<DataType>),
 }
 
-impl From<&ArrowDataType> for DataType {
-    fn from(datatype: &ArrowDataType) -> Self {
-        match datatype {
+/// the arrow extension name of [`DataType::BigInt`] fields
+pub const BIGINT_EXTENSION_NAME: &str = "tonbo.bigint";
+
+pub(crate) const EXTENSION_NAME_KEY: &str = "ARROW:extension:name";
+
+impl DataType {
+    /// whether a column of this type can be the primary key
+    pub fn can_be_key(&self) -> bool {
+        !matches!(self, DataType::Interval | DataType::List(_))
+    }
+
+    /// whether a list column can have items of this type
+    pub fn can_be_list_item(&self) -> bool {
+        matches!(
+            self,
+            DataType::UInt8
+                | DataType::UInt16
+                | DataType::UInt32
+                | DataType::UInt64
+                | DataType::Int8
+                | DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::Float32
+                | DataType::Float64
+                | DataType::Boolean
+                | DataType::String
+                | DataType::Bytes
+        )
+    }
+
+    /// the datatype of an arrow field, unlike the conversion from an arrow datatype this
+    /// recognizes [`DataType::BigInt`] fields by their extension name
+    pub fn from_field(field: &Field) -> Self {
+        if field.data_type() == &ArrowDataType::Binary && Self::is_bigint(field) {
+            DataType::BigInt
+        } else {
+            DataType::from(field.data_type())
+        }
+    }
+
+    /// like [`DataType::from_field`], but `None` if tonbo has no datatype for the field, e.g. a
+    /// map or a timestamp with a timezone
+    pub(crate) fn try_from_field(field: &Field) -> Option<Self> {
+        match field.data_type() {
+            ArrowDataType::Binary if Self::is_bigint(field) => Some(DataType::BigInt),
+            ArrowDataType::Timestamp(_, Some(_)) => None,
+            datatype => Self::try_from_arrow(datatype),
+        }
+    }
+
+    fn is_bigint(field: &Field) -> bool {
+        field.metadata().get(EXTENSION_NAME_KEY).map(String::as_str) == Some(BIGINT_EXTENSION_NAME)
+    }
+
+    fn try_from_arrow(datatype: &ArrowDataType) -> Option<Self> {
+        Some(match datatype {
             ArrowDataType::UInt8 => DataType::UInt8,
             ArrowDataType::UInt16 => DataType::UInt16,
             ArrowDataType::UInt32 => DataType::UInt32,
@@ -65,18 +147,30 @@ impl From<&ArrowDataType> for DataType {
             ArrowDataType::Utf8 => DataType::String,
             ArrowDataType::Boolean => DataType::Boolean,
             ArrowDataType::Binary => DataType::Bytes,
-            ArrowDataType::Timestamp(unit, tz) => {
-                debug_assert!(tz.is_none(), "expected timezone is none, get {:?}", tz);
-                DataType::Timestamp(unit.into())
-            }
+            ArrowDataType::Timestamp(unit, _) => DataType::Timestamp(unit.into()),
             ArrowDataType::Time32(unit) => DataType::Time32(unit.into()),
             ArrowDataType::Time64(unit) => DataType::Time64(unit.into()),
             ArrowDataType::Date32 => DataType::Date32,
             ArrowDataType::Date64 => DataType::Date64,
             ArrowDataType::LargeBinary => DataType::LargeBinary,
             ArrowDataType::LargeUtf8 => DataType::LargeString,
-            _ => todo!(),
+            ArrowDataType::Interval(IntervalUnit::MonthDayNano) => DataType::Interval,
+            ArrowDataType::Decimal128(precision, scale) => DataType::Decimal128(*precision, *scale),
+            // list items are never null
+            ArrowDataType::List(item) if !item.is_nullable() => DataType::List(Box::new(
+                Self::try_from_arrow(item.data_type()).filter(DataType::can_be_list_item)?,
+            )),
+            _ => return None,
+        })
+    }
+}
+
+impl From<&ArrowDataType> for DataType {
+    fn from(datatype: &ArrowDataType) -> Self {
+        if let ArrowDataType::Timestamp(_, tz) = datatype {
+            debug_assert!(tz.is_none(), "expected timezone is none, get {:?}", tz);
         }
+        DataType::try_from_arrow(datatype).unwrap_or_else(|| todo!())
     }
 }
 
@@ -1,21 +1,22 @@
-use std::sync::Arc;
+use std::{any::Any, mem, sync::Arc};
 
 use fusio::SeqRead;
 use fusio_log::{Decode, Encode};
+use thiserror::Error;
 
 use super::{schema::DynSchema, DataType, DynRecordRef, Value};
 use crate::{
     cast_arc_value,
     record::{
-        Date32, Date64, LargeBinary, LargeString, Record, RecordDecodeError, Time32, Time64,
-        Timestamp, F32, F64,
+        BigInt, Date32, Date64, Decimal128, Interval, LargeBinary, LargeString, Record,
+        RecordDecodeError, Time32, Time64, Timestamp, F32, F64,
     },
 };
 
 #[derive(Debug)]
 pub struct DynRecord {
-    values: Vec<Value>,
-    primary_index: usize,
+    pub(crate) values: Vec<Value>,
+    pub(crate) primary_index: usize,
 }
 
 #[allow(unused)]
@@ -26,6 +27,105 @@ impl DynRecord {
             primary_index,
         }
     }
+
+    /// indices of the columns whose values differ from the ones of `other`, the first column is
+    /// `0`. Values are compared like [`Value`]s: floats by their bits, so a `NaN` equals itself
+    /// and `-0.0` differs from `0.0`, and two nulls are equal. Columns that only one of the
+    /// records has differ as well.
+    pub fn diff(&self, other: &DynRecord) -> Vec<usize> {
+        diff_values(&self.values, &other.values)
+    }
+
+    /// the column named `name`, names are compared exactly
+    pub fn column_by_name(&self, name: &str) -> Option<&Value> {
+        self.values.iter().find(|value| value.desc.name == name)
+    }
+
+    /// the column `name` of `schema`, the name is resolved like [`DynSchema`] does, e.g. ignoring
+    /// case if the schema is case insensitive. The internal `_null` and `_ts` columns are not
+    /// user columns and can't be looked up
+    pub fn value_by_name(&self, schema: &DynSchema, name: &str) -> Option<&Value> {
+        schema
+            .value_index(name)
+            .and_then(|index| self.values.get(index))
+    }
+}
+
+pub(crate) fn diff_values(values: &[Value], other: &[Value]) -> Vec<usize> {
+    (0..values.len().max(other.len()))
+        .filter(|index| values.get(*index) != other.get(*index))
+        .collect()
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum DynRecordBuildError {
+    #[error("column {0} does not exist in the schema")]
+    UnknownColumn(String),
+    #[error("non-nullable column {0} is not set")]
+    MissingColumn(String),
+}
+
+/// build a [`DynRecord`] by column name, the columns are placed in schema order regardless of
+/// the order they are set in
+pub struct DynRecordBuilder<'s> {
+    schema: &'s DynSchema,
+    values: Vec<Option<Value>>,
+    unknown: Option<String>,
+}
+
+impl<'s> DynRecordBuilder<'s> {
+    pub fn new(schema: &'s DynSchema) -> Self {
+        Self {
+            schema,
+            values: vec![None; schema.schema.len()],
+            unknown: None,
+        }
+    }
+
+    /// set the value of column `name`. The value of a nullable column is wrapped in `Some`,
+    /// nullable columns that are never set are null
+    pub fn set<T: Any + Send + Sync>(mut self, name: &str, value: T) -> Self {
+        match self.schema.value_index(name) {
+            Some(index) => {
+                let desc = &self.schema.schema[index];
+                let value: Arc<dyn Any + Send + Sync> = if desc.is_nullable {
+                    Arc::new(Some(value))
+                } else {
+                    Arc::new(value)
+                };
+                self.values[index] = Some(Value {
+                    desc: desc.clone(),
+                    value,
+                });
+            }
+            None => {
+                self.unknown.get_or_insert_with(|| name.to_string());
+            }
+        }
+        self
+    }
+
+    pub fn build(self) -> Result<DynRecord, DynRecordBuildError> {
+        if let Some(name) = self.unknown {
+            return Err(DynRecordBuildError::UnknownColumn(name));
+        }
+        let values = self
+            .values
+            .into_iter()
+            .zip(self.schema.schema.iter())
+            .map(|(value, desc)| match value {
+                Some(value) => Ok(value),
+                None if desc.is_nullable => Ok(Value::with_none_value(
+                    desc.datatype.clone(),
+                    desc.name.clone(),
+                    true,
+                )),
+                None => Err(DynRecordBuildError::MissingColumn(desc.name.clone())),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(DynRecord::new(values, self.schema.primary_index))
+    }
 }
 
 macro_rules! implement_record {
@@ -33,6 +133,36 @@ macro_rules! implement_record {
         { $( { $copy_ty:ty, $copy_pat:pat}), * $(,)? },
         { $( { $clone_ty:ty, $clone_pat:pat}), * $(,)? },
     ) => {
+        /// unwrap a non-nullable value read as an `Option`
+        fn unwrap_non_nullable(col: &mut Value) {
+            col.value = match col.datatype() {
+                $(
+                    $copy_pat => Arc::new(cast_arc_value!(col.value, Option<$copy_ty>).unwrap()),
+                )*
+                $(
+                    $clone_pat => {
+                        Arc::new(cast_arc_value!(col.value, Option<$clone_ty>).clone().unwrap())
+                    }
+                )*
+            };
+        }
+
+        impl DynRecord {
+            /// own the values of `record`, every non-nullable column must hold a value
+            pub(crate) fn from_record_ref(record: DynRecordRef<'_>) -> Self {
+                let mut values = record.columns;
+                for (i, col) in values.iter_mut().enumerate() {
+                    if i != record.primary_index && !col.is_nullable() {
+                        unwrap_non_nullable(col);
+                    }
+                }
+                DynRecord {
+                    values,
+                    primary_index: record.primary_index,
+                }
+            }
+        }
+
         impl Decode for DynRecord {
             type Error = RecordDecodeError;
 
@@ -47,18 +177,7 @@ macro_rules! implement_record {
                 for i in 0..len {
                     let mut col = Value::decode(reader).await?;
                     if i != primary_index && !col.is_nullable() {
-                        col.value = match col.datatype() {
-                            $(
-                                $copy_pat => {
-                                    Arc::new(cast_arc_value!(col.value, Option<$copy_ty>).unwrap())
-                                }
-                            )*
-                            $(
-                                $clone_pat => {
-                                    Arc::new(cast_arc_value!(col.value, Option<$clone_ty>).clone().unwrap())
-                                }
-                            )*
-                        };
+                        unwrap_non_nullable(&mut col);
                     }
                     values.push(col);
                 }
@@ -107,8 +226,12 @@ macro_rules! implement_record {
                 DynRecordRef::new(columns, self.primary_index)
             }
 
+            /// the memory held by the record, variable-length values are counted by their
+            /// allocated size
             fn size(&self) -> usize {
-                self.values.iter().fold(0, |acc, col| acc + col.size())
+                self.values.iter().fold(mem::size_of::<Self>(), |acc, col| {
+                    acc + mem::size_of::<Value>() + col.heap_size()
+                })
             }
         }
     };
@@ -132,14 +255,18 @@ implement_record!(
         { Time32, DataType::Time32(_) },
         { Time64, DataType::Time64(_) },
         { Date32, DataType::Date32 },
-        { Date64, DataType::Date64 }
+        { Date64, DataType::Date64 },
+        { Interval, DataType::Interval },
+        { Decimal128, DataType::Decimal128(..) }
     },
     {
         // types that can be cloned
         { Vec<u8>, DataType::Bytes },
         { LargeBinary, DataType::LargeBinary },
         { String, DataType::String },
-        { LargeString, DataType::LargeString }
+        { LargeString, DataType::LargeString },
+        { BigInt, DataType::BigInt },
+        { Vec<Value>, DataType::List(_) }
     },
 );
 
@@ -216,9 +343,9 @@ pub(crate) mod test {
     use fusio_log::{Decode, Encode};
     use tokio::io::AsyncSeekExt;
 
-    use super::{DynRecord, DynSchema, Record};
+    use super::{DynRecord, DynRecordBuildError, DynRecordBuilder, DynSchema, Record};
     use crate::{
-        make_dyn_schema,
+        magic, make_dyn_schema,
         record::{DataType, DynRecordRef, TimeUnit, Timestamp, Value, F32, F64},
     };
 
@@ -421,4 +548,161 @@ pub(crate) mod test {
             actual.as_record_ref().columns
         );
     }
+
+    #[test]
+    fn test_value_by_name() {
+        let schema = test_dyn_item_schema();
+        let record = test_dyn_record();
+
+        assert_eq!(record.column_by_name("id"), Some(&record.values[0]));
+        assert_eq!(record.column_by_name("email"), Some(&record.values[5]));
+        assert_eq!(record.column_by_name("EMAIL"), None);
+        assert_eq!(record.column_by_name("missing"), None);
+
+        // the user columns start at 0, after the internal columns of the arrow schema
+        assert_eq!(record.value_by_name(&schema, "id"), Some(&record.values[0]));
+        assert_eq!(
+            record.value_by_name(&schema, "email"),
+            Some(&record.values[5])
+        );
+        assert_eq!(record.value_by_name(&schema, "missing"), None);
+        assert_eq!(record.value_by_name(&schema, "_null"), None);
+        assert_eq!(record.value_by_name(&schema, magic::TS), None);
+
+        let schema = schema.case_insensitive().unwrap();
+        assert_eq!(
+            record.value_by_name(&schema, "EMAIL"),
+            Some(&record.values[5])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_encode_decode_list_record() {
+        let ints = DataType::List(Box::new(DataType::Int32));
+        let strings = DataType::List(Box::new(DataType::String));
+        let int = |value: i32| Value::new(DataType::Int32, "item".into(), Arc::new(value), false);
+        let record = DynRecord::new(
+            vec![
+                Value::new(DataType::Int64, "id".into(), Arc::new(1_i64), false),
+                Value::new(
+                    ints.clone(),
+                    "ints".into(),
+                    Arc::new(vec![int(1), int(2), int(3)]),
+                    false,
+                ),
+                Value::new(
+                    strings.clone(),
+                    "strings".into(),
+                    Arc::new(Some(vec![Value::new(
+                        DataType::String,
+                        "item".into(),
+                        Arc::new("tonbo".to_string()),
+                        false,
+                    )])),
+                    true,
+                ),
+                Value::new(
+                    ints.clone(),
+                    "empty".into(),
+                    Arc::new(Some(Vec::<Value>::new())),
+                    true,
+                ),
+                Value::new(ints, "null".into(), Arc::new(None::<Vec<Value>>), true),
+            ],
+            0,
+        );
+
+        let mut bytes = Vec::new();
+        let mut buf = Cursor::new(&mut bytes);
+        let record_ref = record.as_record_ref();
+        record_ref.encode(&mut buf).await.unwrap();
+        assert_eq!(bytes.len(), record.as_record_ref().size());
+
+        let mut buf = Cursor::new(&mut bytes);
+        let actual = DynRecord::decode(&mut buf).await.unwrap();
+        assert_eq!(record.values, actual.values);
+    }
+
+    #[test]
+    fn test_dyn_record_builder() {
+        let schema = test_dyn_item_schema();
+        let expected = test_dyn_record();
+
+        let record = DynRecordBuilder::new(&schema)
+            .set("timestamp", Timestamp::new_millis(1717507203412))
+            .set("price", F64::from(1.01))
+            .set("grade", F32::from(1.1234))
+            .set("bytes", b"hello tonbo".to_vec())
+            .set("enabled", true)
+            .set("email", "contact@tonbo.io".to_string())
+            .set("name", "tonbo".to_string())
+            .set("weight", 56_i32)
+            .set("height", 183_i16)
+            .set("age", 10_i8)
+            .set("id", 10_i64)
+            .build()
+            .unwrap();
+        assert_eq!(record.primary_index, expected.primary_index);
+        assert_eq!(record.values, expected.values);
+
+        // unset nullable columns are null
+        let record = DynRecordBuilder::new(&schema)
+            .set("name", "tonbo".to_string())
+            .set("id", 10_i64)
+            .set("grade", F32::from(1.1234))
+            .set("weight", 56_i32)
+            .set("enabled", true)
+            .build()
+            .unwrap();
+        assert_eq!(
+            record.values[1],
+            Value::new(DataType::Int8, "age".into(), Arc::new(None::<i8>), true)
+        );
+
+        assert_eq!(
+            DynRecordBuilder::new(&schema)
+                .set("id", 10_i64)
+                .build()
+                .unwrap_err(),
+            DynRecordBuildError::MissingColumn("weight".into())
+        );
+        assert_eq!(
+            DynRecordBuilder::new(&schema)
+                .set("unknown", 10_i64)
+                .build()
+                .unwrap_err(),
+            DynRecordBuildError::UnknownColumn("unknown".into())
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let record = test_dyn_record();
+        assert!(record.diff(&test_dyn_record()).is_empty());
+        assert!(record
+            .as_record_ref()
+            .diff(&test_dyn_record().as_record_ref())
+            .is_empty());
+
+        // floats are compared by their bits
+        let mut left = test_dyn_record();
+        let mut right = test_dyn_record();
+        left.values[8].value = Arc::new(F32::from(f32::NAN));
+        right.values[8].value = Arc::new(F32::from(f32::NAN));
+        assert!(left.diff(&right).is_empty());
+        left.values[9].value = Arc::new(Some(F64::from(0.0)));
+        right.values[9].value = Arc::new(Some(F64::from(-0.0)));
+        assert_eq!(left.diff(&right), vec![9]);
+
+        // nulls are equal to each other only
+        left.values[5].value = Arc::new(None::<String>);
+        assert_eq!(left.diff(&right), vec![5, 9]);
+        right.values[5].value = Arc::new(None::<String>);
+        right.values[1].value = Arc::new(None::<i8>);
+        assert_eq!(right.diff(&left), vec![1, 9]);
+        assert_eq!(
+            left.as_record_ref().diff(&right.as_record_ref()),
+            vec![1, 9]
+        );
+    }
 }
@@ -3,22 +3,28 @@ use std::{any::Any, marker::PhantomData, mem, sync::Arc};
 use arrow::{
     array::{Array, ArrayRef, ArrowPrimitiveType, AsArray},
     datatypes::{
-        Date32Type, Date64Type, Float32Type, Float64Type, Int16Type, Int32Type, Int64Type,
-        Int8Type, Schema as ArrowSchema, Time32MillisecondType, Time32SecondType,
-        Time64MicrosecondType, Time64NanosecondType, TimestampMicrosecondType,
-        TimestampMillisecondType, TimestampNanosecondType, TimestampSecondType, UInt16Type,
-        UInt32Type, UInt64Type, UInt8Type,
+        Date32Type, Date64Type, Decimal128Type, Float32Type, Float64Type, Int16Type, Int32Type,
+        Int64Type, Int8Type, IntervalMonthDayNanoType, Schema as ArrowSchema,
+        Time32MillisecondType, Time32SecondType, Time64MicrosecondType, Time64NanosecondType,
+        TimestampMicrosecondType, TimestampMillisecondType, TimestampNanosecondType,
+        TimestampSecondType, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
     },
 };
 use fusio::Write;
 use fusio_log::Encode;
 
-use super::{DataType, DynRecord, Value};
+use super::{
+    list::list_items,
+    record::diff_values,
+    value::{parse_default, DEFAULT_VALUE_KEY},
+    DataType, DynRecord, Value,
+};
 use crate::{
     magic::USER_COLUMN_OFFSET,
     record::{
-        option::OptionRecordRef, Date32, Date64, Key, LargeBinary, LargeString, Record,
-        RecordEncodeError, RecordRef, Schema, Time32, Time64, TimeUnit, Timestamp, F32, F64,
+        option::OptionRecordRef, BigInt, Date32, Date64, Decimal128, Interval, Key, LargeBinary,
+        LargeString, Record, RecordEncodeError, RecordRef, Schema, Time32, Time64, TimeUnit,
+        Timestamp, F32, F64,
     },
 };
 
@@ -38,6 +44,12 @@ impl<'r> DynRecordRef<'r> {
             _marker: PhantomData,
         }
     }
+
+    /// indices of the columns whose values differ from the ones of `other`, see
+    /// [`DynRecord::diff`]
+    pub fn diff(&self, other: &DynRecordRef<'_>) -> Vec<usize> {
+        diff_values(&self.columns, &other.columns)
+    }
 }
 
 impl<'r> Encode for DynRecordRef<'r> {
@@ -125,20 +137,39 @@ macro_rules! implement_record_ref {
                 let mut columns = vec![];
 
                 let schema = record_batch.schema();
-                let flattened_fields = schema.flattened_fields();
+                let fields = schema.fields();
 
-                for (idx, field) in full_schema.flattened_fields().iter().enumerate().skip(2) {
-                    let datatype = DataType::from(field.data_type());
-                    let batch_field = flattened_fields
+                // a list column is a single parquet leaf, so the index of a column is also the
+                // index of its leaf in `projection_mask`
+                for (idx, field) in full_schema.fields().iter().enumerate().skip(2) {
+                    let datatype = DataType::from_field(field);
+                    let batch_field = fields
                         .iter()
                         .enumerate()
-                        .find(|(_idx, f)| field.contains(f));
+                        .find(|(_idx, f)| {
+                            f.name() == field.name() && f.data_type() == field.data_type()
+                        });
                     if batch_field.is_none() {
-                        columns.push(Value::with_none_value(
-                            datatype,
-                            field.name().to_owned(),
-                            field.is_nullable(),
-                        ));
+                        // the column is projected out, or was added after the file was written.
+                        // Like the other columns, the default is read as an `Option`
+                        let default = projection_mask
+                            .leaf_included(idx)
+                            .then(|| field.metadata().get(DEFAULT_VALUE_KEY))
+                            .flatten()
+                            .and_then(|text| parse_default(&datatype, text, true));
+                        columns.push(match default {
+                            Some(default) => Value::new(
+                                datatype,
+                                field.name().to_owned(),
+                                default,
+                                field.is_nullable(),
+                            ),
+                            None => Value::with_none_value(
+                                datatype,
+                                field.name().to_owned(),
+                                field.is_nullable(),
+                            ),
+                        });
                         continue;
                     }
                     let col = record_batch.column(batch_field.unwrap().0);
@@ -178,6 +209,29 @@ macro_rules! implement_record_ref {
                                 }
                             },
                         )*
+                        DataType::Decimal128(precision, scale) => {
+                            let array = col.as_primitive::<Decimal128Type>();
+                            let new_decimal = |value| Decimal128 {
+                                value,
+                                precision,
+                                scale,
+                            };
+                            if primary_index == idx - 2 {
+                                Arc::new(new_decimal(array.value(offset))) as Arc<dyn Any + Send + Sync>
+                            } else {
+                                let value = (!array.is_null(offset)
+                                    && projection_mask.leaf_included(idx))
+                                .then(|| new_decimal(array.value(offset)));
+                                Arc::new(value) as Arc<dyn Any + Send + Sync>
+                            }
+                        }
+                        DataType::List(ref item) => {
+                            let array = col.as_list::<i32>();
+                            let value = (!array.is_null(offset)
+                                && projection_mask.leaf_included(idx))
+                            .then(|| list_items(item, array, offset));
+                            Arc::new(value) as Arc<dyn Any + Send + Sync>
+                        }
                         DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                     };
                     columns.push(Value::new(
@@ -208,11 +262,19 @@ macro_rules! implement_record_ref {
                             $(
                                 $alt_variant2 => col.value = Arc::<Option<$alt_ty2>>::new(None),
                             )*
+                            DataType::Decimal128(..) => {
+                                col.value = Arc::<Option<Decimal128>>::new(None)
+                            }
+                            DataType::List(_) => col.value = Arc::<Option<Vec<Value>>>::new(None),
                             DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                         };
                     }
                 }
             }
+
+            fn is_null(&self, index: usize) -> bool {
+                self.columns[index].is_null()
+            }
         }
     };
 }
@@ -235,10 +297,12 @@ implement_record_ref!(
         { LargeString, DataType::LargeString, as_string, i64, String::from },
         { Vec<u8>, DataType::Bytes, as_binary, i32, Vec::from },
         { LargeBinary, DataType::LargeBinary, as_binary, i64, Vec::from },
+        { BigInt, DataType::BigInt, as_binary, i32, BigInt::from_encoded_unchecked },
         { F32, DataType::Float32, as_primitive, Float32Type, F32::from },
         { F64, DataType::Float64, as_primitive, Float64Type, F64::from },
         { Date32, DataType::Date32, as_primitive, Date32Type, Date32::new },
         { Date64, DataType::Date64, as_primitive, Date64Type, Date64::new },
+        { Interval, DataType::Interval, as_primitive, IntervalMonthDayNanoType, Interval::from },
         { Timestamp, DataType::Timestamp(TimeUnit::Second), as_primitive, TimestampSecondType, Timestamp::new_seconds },
         { Timestamp, DataType::Timestamp(TimeUnit::Millisecond), as_primitive, TimestampMillisecondType, Timestamp::new_millis },
         { Timestamp, DataType::Timestamp(TimeUnit::Microsecond), as_primitive, TimestampMicrosecondType, Timestamp::new_micros },
@@ -0,0 +1,383 @@
+use std::{any::Any, sync::Arc};
+
+use arrow::{array::Array, datatypes::DataType as ArrowDataType};
+use datafusion::scalar::ScalarValue;
+use thiserror::Error;
+
+use super::{
+    list::{append_list, item_field, list_builder, list_items},
+    DataType, Value, ValueDesc,
+};
+use crate::record::{
+    BigInt, Date32, Date64, Decimal128, Interval, Time32, Time64, TimeUnit, Timestamp, F32, F64,
+};
+
+#[derive(Debug, Error)]
+pub enum ScalarValueError {
+    #[error("scalar of type {actual} can not be converted to {expected:?}")]
+    TypeMismatch {
+        expected: DataType,
+        actual: ArrowDataType,
+    },
+    #[error("null scalar for non-nullable column {0}")]
+    NullNotAllowed(String),
+    #[error("binary scalar for column {0} is not an encoded big integer")]
+    InvalidBigInt(String),
+}
+
+impl Value {
+    /// convert to a DataFusion [`ScalarValue`]. Null values become typed null scalars
+    pub fn to_scalar(&self) -> ScalarValue {
+        match self.datatype() {
+            DataType::UInt8 => ScalarValue::UInt8(self.get::<u8>().copied()),
+            DataType::UInt16 => ScalarValue::UInt16(self.get::<u16>().copied()),
+            DataType::UInt32 => ScalarValue::UInt32(self.get::<u32>().copied()),
+            DataType::UInt64 => ScalarValue::UInt64(self.get::<u64>().copied()),
+            DataType::Int8 => ScalarValue::Int8(self.get::<i8>().copied()),
+            DataType::Int16 => ScalarValue::Int16(self.get::<i16>().copied()),
+            DataType::Int32 => ScalarValue::Int32(self.get::<i32>().copied()),
+            DataType::Int64 => ScalarValue::Int64(self.get::<i64>().copied()),
+            DataType::Float32 => ScalarValue::Float32(self.get::<F32>().map(F32::value)),
+            DataType::Float64 => ScalarValue::Float64(self.get::<F64>().map(F64::value)),
+            DataType::String => ScalarValue::Utf8(self.get::<String>().cloned()),
+            DataType::LargeString => ScalarValue::LargeUtf8(self.get::<String>().cloned()),
+            DataType::Boolean => ScalarValue::Boolean(self.get::<bool>().copied()),
+            DataType::Bytes => ScalarValue::Binary(self.get::<Vec<u8>>().cloned()),
+            DataType::LargeBinary => ScalarValue::LargeBinary(self.get::<Vec<u8>>().cloned()),
+            DataType::Timestamp(TimeUnit::Second) => ScalarValue::TimestampSecond(
+                self.get::<Timestamp>().map(Timestamp::timestamp),
+                None,
+            ),
+            DataType::Timestamp(TimeUnit::Millisecond) => ScalarValue::TimestampMillisecond(
+                self.get::<Timestamp>().map(Timestamp::timestamp_millis),
+                None,
+            ),
+            DataType::Timestamp(TimeUnit::Microsecond) => ScalarValue::TimestampMicrosecond(
+                self.get::<Timestamp>().map(Timestamp::timestamp_micros),
+                None,
+            ),
+            DataType::Timestamp(TimeUnit::Nanosecond) => ScalarValue::TimestampNanosecond(
+                self.get::<Timestamp>().map(Timestamp::timestamp_nanos),
+                None,
+            ),
+            DataType::Time32(TimeUnit::Second) => {
+                ScalarValue::Time32Second(self.get::<Time32>().map(Time32::value))
+            }
+            DataType::Time32(TimeUnit::Millisecond) => {
+                ScalarValue::Time32Millisecond(self.get::<Time32>().map(Time32::value))
+            }
+            DataType::Time64(TimeUnit::Microsecond) => {
+                ScalarValue::Time64Microsecond(self.get::<Time64>().map(Time64::value))
+            }
+            DataType::Time64(TimeUnit::Nanosecond) => {
+                ScalarValue::Time64Nanosecond(self.get::<Time64>().map(Time64::value))
+            }
+            DataType::Date32 => ScalarValue::Date32(self.get::<Date32>().map(Date32::value)),
+            DataType::Date64 => ScalarValue::Date64(self.get::<Date64>().map(Date64::value)),
+            DataType::Interval => {
+                ScalarValue::IntervalMonthDayNano(self.get::<Interval>().map(Interval::value))
+            }
+            DataType::BigInt => {
+                ScalarValue::Binary(self.get::<BigInt>().map(|value| value.as_bytes().to_vec()))
+            }
+            DataType::Decimal128(precision, scale) => ScalarValue::Decimal128(
+                self.get::<Decimal128>().map(Decimal128::value),
+                precision,
+                scale,
+            ),
+            DataType::List(item) => {
+                let mut builder = list_builder(&item, 1);
+                match self.get::<Vec<Value>>() {
+                    Some(items) => append_list(&mut builder, items),
+                    None => builder.append_null(),
+                }
+                ScalarValue::List(Arc::new(builder.finish()))
+            }
+            DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
+        }
+    }
+
+    /// build a value described by `desc` from a DataFusion [`ScalarValue`]. A null scalar is
+    /// only accepted for nullable columns
+    pub fn from_scalar(scalar: &ScalarValue, desc: ValueDesc) -> Result<Self, ScalarValueError> {
+        fn wrap<T: Send + Sync + 'static>(
+            value: Option<T>,
+            desc: &ValueDesc,
+        ) -> Result<Arc<dyn Any + Send + Sync>, ScalarValueError> {
+            if desc.is_nullable {
+                return Ok(Arc::new(value));
+            }
+            match value {
+                Some(value) => Ok(Arc::new(value)),
+                None => Err(ScalarValueError::NullNotAllowed(desc.name.clone())),
+            }
+        }
+
+        let value = match (&desc.datatype, scalar) {
+            (DataType::UInt8, ScalarValue::UInt8(v)) => wrap(*v, &desc)?,
+            (DataType::UInt16, ScalarValue::UInt16(v)) => wrap(*v, &desc)?,
+            (DataType::UInt32, ScalarValue::UInt32(v)) => wrap(*v, &desc)?,
+            (DataType::UInt64, ScalarValue::UInt64(v)) => wrap(*v, &desc)?,
+            (DataType::Int8, ScalarValue::Int8(v)) => wrap(*v, &desc)?,
+            (DataType::Int16, ScalarValue::Int16(v)) => wrap(*v, &desc)?,
+            (DataType::Int32, ScalarValue::Int32(v)) => wrap(*v, &desc)?,
+            (DataType::Int64, ScalarValue::Int64(v)) => wrap(*v, &desc)?,
+            (DataType::Float32, ScalarValue::Float32(v)) => wrap(v.map(F32::from), &desc)?,
+            (DataType::Float64, ScalarValue::Float64(v)) => wrap(v.map(F64::from), &desc)?,
+            (DataType::String, ScalarValue::Utf8(v))
+            | (DataType::LargeString, ScalarValue::LargeUtf8(v)) => wrap(v.clone(), &desc)?,
+            (DataType::Boolean, ScalarValue::Boolean(v)) => wrap(*v, &desc)?,
+            (DataType::Bytes, ScalarValue::Binary(v))
+            | (DataType::LargeBinary, ScalarValue::LargeBinary(v)) => wrap(v.clone(), &desc)?,
+            (DataType::Timestamp(TimeUnit::Second), ScalarValue::TimestampSecond(v, _)) => {
+                wrap(v.map(Timestamp::new_seconds), &desc)?
+            }
+            (
+                DataType::Timestamp(TimeUnit::Millisecond),
+                ScalarValue::TimestampMillisecond(v, _),
+            ) => wrap(v.map(Timestamp::new_millis), &desc)?,
+            (
+                DataType::Timestamp(TimeUnit::Microsecond),
+                ScalarValue::TimestampMicrosecond(v, _),
+            ) => wrap(v.map(Timestamp::new_micros), &desc)?,
+            (DataType::Timestamp(TimeUnit::Nanosecond), ScalarValue::TimestampNanosecond(v, _)) => {
+                wrap(v.map(Timestamp::new_nanos), &desc)?
+            }
+            (DataType::Time32(TimeUnit::Second), ScalarValue::Time32Second(v)) => {
+                wrap(v.map(Time32::new_seconds), &desc)?
+            }
+            (DataType::Time32(TimeUnit::Millisecond), ScalarValue::Time32Millisecond(v)) => {
+                wrap(v.map(Time32::new_millis), &desc)?
+            }
+            (DataType::Time64(TimeUnit::Microsecond), ScalarValue::Time64Microsecond(v)) => {
+                wrap(v.map(Time64::new_micros), &desc)?
+            }
+            (DataType::Time64(TimeUnit::Nanosecond), ScalarValue::Time64Nanosecond(v)) => {
+                wrap(v.map(Time64::new_nanos), &desc)?
+            }
+            (DataType::Date32, ScalarValue::Date32(v)) => wrap(v.map(Date32::new), &desc)?,
+            (DataType::Date64, ScalarValue::Date64(v)) => wrap(v.map(Date64::new), &desc)?,
+            (DataType::Interval, ScalarValue::IntervalMonthDayNano(v)) => {
+                wrap(v.map(Interval::from), &desc)?
+            }
+            // decimals of a different precision or scale are a type mismatch
+            (DataType::Decimal128(precision, scale), ScalarValue::Decimal128(v, p, s))
+                if (precision, scale) == (p, s) =>
+            {
+                let decimal = |value| Decimal128 {
+                    value,
+                    precision: *precision,
+                    scale: *scale,
+                };
+                wrap(v.map(decimal), &desc)?
+            }
+            (DataType::BigInt, ScalarValue::Binary(v)) => {
+                let value = v
+                    .as_deref()
+                    .map(|bytes| {
+                        BigInt::from_encoded(bytes)
+                            .ok_or_else(|| ScalarValueError::InvalidBigInt(desc.name.clone()))
+                    })
+                    .transpose()?;
+                wrap(value, &desc)?
+            }
+            // the items of the list scalar have to be non-null ones of the item type
+            (DataType::List(item), ScalarValue::List(array))
+                if array.data_type() == &ArrowDataType::List(Arc::new(item_field(item))) =>
+            {
+                let items = (!array.is_null(0)).then(|| list_items(item, array, 0));
+                wrap(items, &desc)?
+            }
+            (expected, scalar) => {
+                return Err(ScalarValueError::TypeMismatch {
+                    expected: expected.clone(),
+                    actual: scalar.data_type(),
+                })
+            }
+        };
+
+        Ok(Value { desc, value })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use datafusion::scalar::ScalarValue;
+
+    use super::ScalarValueError;
+    use crate::record::{
+        BigInt, DataType, Date32, Date64, Decimal128, Time32, Time64, TimeUnit, Timestamp, Value,
+        ValueDesc, F32, F64,
+    };
+
+    #[test]
+    fn test_scalar_round_trip() {
+        let values = vec![
+            Value::new(DataType::UInt8, "a".into(), Arc::new(1_u8), false),
+            Value::new(DataType::UInt16, "a".into(), Arc::new(2_u16), false),
+            Value::new(DataType::UInt32, "a".into(), Arc::new(3_u32), false),
+            Value::new(DataType::UInt64, "a".into(), Arc::new(4_u64), false),
+            Value::new(DataType::Int8, "a".into(), Arc::new(-1_i8), false),
+            Value::new(DataType::Int16, "a".into(), Arc::new(-2_i16), false),
+            Value::new(DataType::Int32, "a".into(), Arc::new(-3_i32), false),
+            Value::new(DataType::Int64, "a".into(), Arc::new(-4_i64), false),
+            Value::new(
+                DataType::Float32,
+                "a".into(),
+                Arc::new(F32::from(1.5)),
+                false,
+            ),
+            Value::new(
+                DataType::Float64,
+                "a".into(),
+                Arc::new(F64::from(2.5)),
+                false,
+            ),
+            Value::new(
+                DataType::String,
+                "a".into(),
+                Arc::new("s".to_string()),
+                false,
+            ),
+            Value::new(
+                DataType::LargeString,
+                "a".into(),
+                Arc::new("l".to_string()),
+                false,
+            ),
+            Value::new(DataType::Boolean, "a".into(), Arc::new(true), false),
+            Value::new(DataType::Bytes, "a".into(), Arc::new(vec![1_u8, 2]), false),
+            Value::new(
+                DataType::LargeBinary,
+                "a".into(),
+                Arc::new(vec![3_u8]),
+                false,
+            ),
+            Value::new(
+                DataType::Timestamp(TimeUnit::Second),
+                "a".into(),
+                Arc::new(Timestamp::new_seconds(1717507203)),
+                false,
+            ),
+            Value::new(
+                DataType::Timestamp(TimeUnit::Millisecond),
+                "a".into(),
+                Arc::new(Timestamp::new_millis(1717507203412)),
+                false,
+            ),
+            Value::new(
+                DataType::Timestamp(TimeUnit::Microsecond),
+                "a".into(),
+                Arc::new(Timestamp::new_micros(1717507203412000)),
+                false,
+            ),
+            Value::new(
+                DataType::Timestamp(TimeUnit::Nanosecond),
+                "a".into(),
+                Arc::new(Timestamp::new_nanos(1717507203412000000)),
+                false,
+            ),
+            Value::new(
+                DataType::Time32(TimeUnit::Second),
+                "a".into(),
+                Arc::new(Time32::new_seconds(3600)),
+                false,
+            ),
+            Value::new(
+                DataType::Time32(TimeUnit::Millisecond),
+                "a".into(),
+                Arc::new(Time32::new_millis(3_600_000)),
+                false,
+            ),
+            Value::new(
+                DataType::Time64(TimeUnit::Microsecond),
+                "a".into(),
+                Arc::new(Time64::new_micros(3_600_000_000)),
+                false,
+            ),
+            Value::new(
+                DataType::Time64(TimeUnit::Nanosecond),
+                "a".into(),
+                Arc::new(Time64::new_nanos(3_600_000_000_000)),
+                false,
+            ),
+            Value::new(
+                DataType::Date32,
+                "a".into(),
+                Arc::new(Date32::new(19000)),
+                false,
+            ),
+            Value::new(
+                DataType::Date64,
+                "a".into(),
+                Arc::new(Date64::new(1641600000000)),
+                false,
+            ),
+            Value::new(
+                DataType::Decimal128(10, 2),
+                "a".into(),
+                Arc::new(Decimal128::new(-12_345, 10, 2)),
+                false,
+            ),
+            Value::new(
+                DataType::BigInt,
+                "a".into(),
+                Arc::new(BigInt::from(i128::MIN)),
+                false,
+            ),
+        ];
+
+        for value in values {
+            let scalar = value.to_scalar();
+            assert!(!scalar.is_null());
+            assert_eq!(scalar.data_type(), *value.desc.arrow_field().data_type());
+
+            let round_trip = Value::from_scalar(&scalar, value.desc.clone()).unwrap();
+            assert_eq!(round_trip.to_scalar(), scalar);
+
+            // nullable columns hold `Option<T>` and map null to a typed null scalar
+            let nullable = ValueDesc::new("a".into(), value.datatype(), true);
+            let some = Value::from_scalar(&scalar, nullable.clone()).unwrap();
+            assert_eq!(some.to_scalar(), scalar);
+
+            let null = ScalarValue::try_from(scalar.data_type()).unwrap();
+            let none = Value::from_scalar(&null, nullable).unwrap();
+            assert!(none.to_scalar().is_null());
+            assert_eq!(none.to_scalar().data_type(), scalar.data_type());
+        }
+    }
+
+    #[test]
+    fn test_scalar_errors() {
+        let desc = ValueDesc::new("id".into(), DataType::Int32, false);
+
+        assert!(matches!(
+            Value::from_scalar(&ScalarValue::Int32(None), desc.clone()),
+            Err(ScalarValueError::NullNotAllowed(name)) if name == "id"
+        ));
+        assert!(matches!(
+            Value::from_scalar(&ScalarValue::Utf8(Some("1".into())), desc),
+            Err(ScalarValueError::TypeMismatch {
+                expected: DataType::Int32,
+                ..
+            })
+        ));
+
+        let desc = ValueDesc::new("price".into(), DataType::Decimal128(10, 2), false);
+        assert!(matches!(
+            Value::from_scalar(&ScalarValue::Decimal128(Some(1), 10, 3), desc),
+            Err(ScalarValueError::TypeMismatch {
+                expected: DataType::Decimal128(10, 2),
+                ..
+            })
+        ));
+
+        let desc = ValueDesc::new("id".into(), DataType::BigInt, false);
+        assert!(matches!(
+            Value::from_scalar(&ScalarValue::Binary(Some(vec![9])), desc),
+            Err(ScalarValueError::InvalidBigInt(name)) if name == "id"
+        ));
+    }
+}
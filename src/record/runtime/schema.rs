@@ -1,20 +1,29 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
 
 use arrow::{
-    datatypes::{DataType, Field, Schema as ArrowSchema},
+    datatypes::{DataType, Field, FieldRef, Schema as ArrowSchema},
     error::ArrowError,
 };
 use parquet::{format::SortingColumn, schema::types::ColumnPath};
 use thiserror::Error;
 
 use super::{array::DynRecordImmutableArrays, DynRecord, Value, ValueDesc};
-use crate::{magic, record::Schema};
+use crate::{
+    magic,
+    record::{KeyTypeMismatch, RecordValidationError, Schema, Timestamp},
+};
 
 #[derive(Debug)]
 pub struct DynSchema {
-    schema: Vec<ValueDesc>,
-    primary_index: usize,
+    pub(crate) schema: Vec<ValueDesc>,
+    pub(crate) primary_index: usize,
     arrow_schema: Arc<ArrowSchema>,
+    case_insensitive: bool,
+    coerce_integer_keys: bool,
 }
 
 #[derive(Debug, Error)]
@@ -22,12 +31,161 @@ pub struct DynSchema {
 pub enum SchemaError {
     #[error("write io error: {0}")]
     Arrow(#[from] ArrowError),
+    #[error("column {0} is ambiguous when names are case-insensitive")]
+    AmbiguousColumn(String),
+    #[error("column {0} of type {1:?} can not be used as primary key")]
+    InvalidPrimaryKey(String, super::DataType),
+    #[error("invalid primary_key_index metadata: {0}")]
+    InvalidMetadata(String),
+    #[error("column {0} is reserved for tonbo internal columns")]
+    ReservedColumn(String),
+    #[error("column {0} of arrow type {1} is not supported")]
+    UnsupportedType(String, DataType),
+    #[error("column {0} already exists")]
+    DuplicateColumn(String),
+    #[error("column {0} is not nullable and needs a default to be added")]
+    MissingDefault(String),
+    #[error("invalid default of column {0}: {1}")]
+    InvalidDefault(String, RecordValidationError),
+    #[error("column {0} of type {1:?} can not have a default")]
+    UnsupportedDefault(String, super::DataType),
+}
+
+const PRIMARY_KEY_INDEX: &str = "primary_key_index";
+
+/// the primary key index stored in the schema metadata, if any. The entry is a comma-joined
+/// list of column indices, which only has a single index as composite primary keys are not
+/// supported
+fn metadata_primary_index(arrow_schema: &ArrowSchema) -> Result<Option<usize>, SchemaError> {
+    let Some(entry) = arrow_schema.metadata().get(PRIMARY_KEY_INDEX) else {
+        return Ok(None);
+    };
+    let indices = entry
+        .split(',')
+        .map(|index| {
+            index
+                .trim()
+                .parse::<usize>()
+                .map_err(|_| SchemaError::InvalidMetadata(format!("{:?} is not an index", index)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    match indices[..] {
+        [index] => Ok(Some(index)),
+        _ => Err(SchemaError::InvalidMetadata(format!(
+            "{:?} is a composite primary key, which is not supported",
+            entry
+        ))),
+    }
+}
+
+/// the user columns of `arrow_schema`, without the internal `_null` and `_ts` columns if it
+/// starts with them
+fn user_fields(arrow_schema: &ArrowSchema) -> Result<&[FieldRef], SchemaError> {
+    let fields = &arrow_schema.fields()[..];
+    let fields = match fields {
+        [null, ts, user @ ..] if null.name() == "_null" && ts.name() == magic::TS => {
+            if null.data_type() != &DataType::Boolean {
+                return Err(SchemaError::ReservedColumn(null.name().clone()));
+            }
+            if ts.data_type() != &DataType::UInt32 {
+                return Err(SchemaError::ReservedColumn(ts.name().clone()));
+            }
+            user
+        }
+        _ => fields,
+    };
+    match fields
+        .iter()
+        .find(|field| field.name() == "_null" || field.name() == magic::TS)
+    {
+        Some(field) => Err(SchemaError::ReservedColumn(field.name().clone())),
+        None => Ok(fields),
+    }
+}
+
+/// check that `value` can be stored in the column `desc`
+fn validate_value(desc: &ValueDesc, value: &Value) -> Result<(), RecordValidationError> {
+    if value.datatype() != desc.datatype {
+        return Err(RecordValidationError::ColumnTypeMismatch {
+            column: desc.name.clone(),
+            expected: desc.datatype.clone(),
+            actual: value.datatype(),
+        });
+    }
+    if value.is_nullable() != desc.is_nullable {
+        return Err(RecordValidationError::NullableMismatch {
+            column: desc.name.clone(),
+            expected: desc.is_nullable,
+            actual: value.is_nullable(),
+        });
+    }
+    if !desc.is_nullable && value.is_null() {
+        return Err(RecordValidationError::NullValue {
+            column: desc.name.clone(),
+        });
+    }
+    // the arrays are built by downcasting the values, a value of another type would panic when
+    // the memtable is flushed
+    if !value.is_well_formed() {
+        return Err(RecordValidationError::InvalidValue {
+            column: desc.name.clone(),
+            datatype: desc.datatype.clone(),
+        });
+    }
+    // so do the items of a list
+    if let super::DataType::List(item) = &desc.datatype {
+        let items = value.get::<Vec<Value>>().map_or(&[][..], Vec::as_slice);
+        if items.iter().any(|value| {
+            value.datatype() != **item || value.is_nullable() || !value.is_well_formed()
+        }) {
+            return Err(RecordValidationError::InvalidValue {
+                column: desc.name.clone(),
+                datatype: desc.datatype.clone(),
+            });
+        }
+    }
+    // the arrow column stores the raw value of a timestamp, so its unit has to be the declared
+    // one or the value would be read back as a different instant
+    if let super::DataType::Timestamp(expected) = desc.datatype {
+        match value.get::<Timestamp>().map(Timestamp::unit) {
+            Some(actual) if actual != expected => {
+                return Err(RecordValidationError::TimestampUnitMismatch {
+                    column: desc.name.clone(),
+                    expected,
+                    actual,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
 }
 
 impl DynSchema {
+    /// # Panics
+    ///
+    /// Panics if the primary key column is an [`Interval`](super::DataType::Interval), intervals
+    /// have no meaningful ordering, or a [`List`](super::DataType::List). Panics as well if a list
+    /// column has items of a type that [can not be list items](super::DataType::can_be_list_item)
     pub fn new(schema: Vec<ValueDesc>, primary_index: usize) -> Self {
+        assert!(
+            schema[primary_index].datatype.can_be_key(),
+            "column {} of type {:?} can not be used as primary key",
+            schema[primary_index].name,
+            schema[primary_index].datatype
+        );
+        for desc in schema.iter() {
+            if let super::DataType::List(item) = &desc.datatype {
+                assert!(
+                    item.can_be_list_item(),
+                    "column {} can not be a list of {:?}",
+                    desc.name,
+                    item
+                );
+            }
+        }
         let mut metadata = HashMap::new();
-        metadata.insert("primary_key_index".to_string(), primary_index.to_string());
+        metadata.insert(PRIMARY_KEY_INDEX.to_string(), primary_index.to_string());
         let arrow_schema = Arc::new(ArrowSchema::new_with_metadata(
             [
                 Field::new("_null", DataType::Boolean, false),
@@ -42,39 +200,178 @@ impl DynSchema {
             schema,
             primary_index,
             arrow_schema,
+            case_insensitive: false,
+            coerce_integer_keys: false,
         }
     }
 
-    /// create [`DynSchema`] from [`arrow::datatypes::Schema`]
+    /// create [`DynSchema`] from [`arrow::datatypes::Schema`], e.g. the schema of a parquet file.
+    /// The internal `_null` and `_ts` columns are skipped if `arrow_schema` starts with them and
+    /// `primary_index` is the index among the other columns. If `arrow_schema` carries
+    /// `primary_key_index` metadata, it must agree with `primary_index`
     pub fn from_arrow_schema(
-        arrow_schema: ArrowSchema,
+        arrow_schema: &ArrowSchema,
         primary_index: usize,
     ) -> Result<Self, SchemaError> {
-        let mut metadata = HashMap::new();
-        metadata.insert("primary_key_index".to_string(), primary_index.to_string());
+        let fields = user_fields(arrow_schema)?;
+        if primary_index >= fields.len() {
+            return Err(SchemaError::InvalidMetadata(format!(
+                "index {} is out of {} columns",
+                primary_index,
+                fields.len()
+            )));
+        }
+        if let Some(index) = metadata_primary_index(arrow_schema)? {
+            if index != primary_index {
+                return Err(SchemaError::InvalidMetadata(format!(
+                    "index {} does not match primary index {}",
+                    index, primary_index
+                )));
+            }
+        }
+        let schema = fields
+            .iter()
+            .map(|field| {
+                super::DataType::try_from_field(field)
+                    .map(|datatype| {
+                        let desc =
+                            ValueDesc::new(field.name().clone(), datatype, field.is_nullable());
+                        ValueDesc {
+                            default: desc.field_default(field),
+                            ..desc
+                        }
+                    })
+                    .ok_or_else(|| {
+                        SchemaError::UnsupportedType(
+                            field.name().clone(),
+                            field.data_type().clone(),
+                        )
+                    })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let primary = &schema[primary_index];
+        if !primary.datatype.can_be_key() {
+            return Err(SchemaError::InvalidPrimaryKey(
+                primary.name.clone(),
+                primary.datatype.clone(),
+            ));
+        }
+        Ok(Self::new(schema, primary_index))
+    }
 
-        let arrow_schema = ArrowSchema::try_merge(vec![
-            ArrowSchema::new_with_metadata(
-                vec![
-                    Field::new("_null", DataType::Boolean, false),
-                    Field::new(magic::TS, DataType::UInt32, false),
-                ],
-                metadata,
-            ),
-            arrow_schema,
-        ])?;
-        let mut schema = Vec::with_capacity(arrow_schema.fields.len());
-        for field in arrow_schema.fields.iter() {
-            let col = ValueDesc::from(field.as_ref());
-            schema.push(col);
+    /// recover the [`DynSchema`] a file was written with from its arrow schema, e.g. the schema
+    /// stored in an SSTable. The primary key is read from the `primary_key_index` metadata
+    pub fn from_stored_arrow_schema(arrow_schema: &ArrowSchema) -> Result<Self, SchemaError> {
+        let fields = arrow_schema.fields();
+        if fields.len() <= magic::USER_COLUMN_OFFSET
+            || fields[0].name() != "_null"
+            || fields[1].name() != magic::TS
+        {
+            return Err(SchemaError::InvalidMetadata(
+                "schema has no tonbo internal columns".to_string(),
+            ));
+        }
+        let primary_index = metadata_primary_index(arrow_schema)?
+            .ok_or_else(|| SchemaError::InvalidMetadata("missing".to_string()))?;
+        let columns = fields.len() - magic::USER_COLUMN_OFFSET;
+        if primary_index >= columns {
+            return Err(SchemaError::InvalidMetadata(format!(
+                "index {} is out of {} columns",
+                primary_index, columns
+            )));
+        }
+
+        let schema = fields
+            .iter()
+            .skip(magic::USER_COLUMN_OFFSET)
+            .map(|field| ValueDesc::from(field.as_ref()))
+            .collect::<Vec<_>>();
+        let primary = &schema[primary_index];
+        if !primary.datatype.can_be_key() {
+            return Err(SchemaError::InvalidPrimaryKey(
+                primary.name.clone(),
+                primary.datatype.clone(),
+            ));
+        }
+        Ok(Self::new(schema, primary_index))
+    }
+
+    /// a new schema with `desc` appended as the last column. Rows written before the column was
+    /// added, e.g. in existing SSTables, read the default of `desc`, or null if it has none. A
+    /// non-nullable column must have a default
+    pub fn add_column(&self, desc: ValueDesc) -> Result<Self, SchemaError> {
+        if desc.name == "_null" || desc.name == magic::TS {
+            return Err(SchemaError::ReservedColumn(desc.name));
+        }
+        if self.value_index(&desc.name).is_some() {
+            return Err(SchemaError::DuplicateColumn(desc.name));
+        }
+        let default = desc.default.clone().filter(|default| !default.is_null());
+        match &default {
+            Some(default) => {
+                validate_value(&desc, default)
+                    .map_err(|err| SchemaError::InvalidDefault(desc.name.clone(), err))?;
+                // the default is persisted as text in the arrow field metadata
+                if default.default_text().is_none() {
+                    return Err(SchemaError::UnsupportedDefault(desc.name, desc.datatype));
+                }
+            }
+            None if !desc.is_nullable => return Err(SchemaError::MissingDefault(desc.name)),
+            None => {}
+        }
+
+        let mut schema = self.schema.clone();
+        schema.push(ValueDesc { default, ..desc });
+        let added = Self {
+            coerce_integer_keys: self.coerce_integer_keys,
+            ..Self::new(schema, self.primary_index)
+        };
+        if self.case_insensitive {
+            added.case_insensitive()
+        } else {
+            Ok(added)
         }
+    }
 
+    /// resolve column names case-insensitively in name based lookups, e.g.
+    /// [`Scan::projection`](crate::Scan::projection). Fails if two columns only differ in case
+    pub fn case_insensitive(self) -> Result<Self, SchemaError> {
+        let mut folded = HashSet::with_capacity(self.schema.len());
+        for desc in self.schema.iter() {
+            if !folded.insert(desc.name.to_lowercase()) {
+                return Err(SchemaError::AmbiguousColumn(desc.name.clone()));
+            }
+        }
         Ok(Self {
-            schema,
-            primary_index,
-            arrow_schema: Arc::new(arrow_schema),
+            case_insensitive: true,
+            ..self
         })
     }
+
+    /// accept lookup keys of any integer type for an integer primary key, e.g. an `Int32` key
+    /// for an `Int64` column. Keys are converted only when the value fits in the primary key
+    /// type, others are still rejected
+    pub fn coerce_integer_keys(self) -> Self {
+        Self {
+            coerce_integer_keys: true,
+            ..self
+        }
+    }
+
+    fn name_matches(&self, column: &str, name: &str) -> bool {
+        if self.case_insensitive {
+            column.to_lowercase() == name.to_lowercase()
+        } else {
+            column == name
+        }
+    }
+
+    /// position of the column `name` in the user columns
+    pub(crate) fn value_index(&self, name: &str) -> Option<usize> {
+        self.schema
+            .iter()
+            .position(|desc| self.name_matches(&desc.name, name))
+    }
 }
 
 impl Schema for DynSchema {
@@ -104,6 +401,61 @@ impl Schema for DynSchema {
             ],
         )
     }
+
+    fn column_index(&self, name: &str) -> Option<usize> {
+        self.arrow_schema
+            .fields()
+            .iter()
+            .position(|field| self.name_matches(field.name(), name))
+    }
+
+    fn validate_record(&self, record: &DynRecord) -> Result<(), RecordValidationError> {
+        if record.values.is_empty() {
+            return Err(RecordValidationError::Empty);
+        }
+        if record.values.len() != self.schema.len() {
+            return Err(RecordValidationError::ColumnCount {
+                expected: self.schema.len(),
+                actual: record.values.len(),
+            });
+        }
+        if record.primary_index != self.primary_index {
+            return Err(RecordValidationError::PrimaryIndex {
+                expected: self.primary_index,
+                actual: record.primary_index,
+            });
+        }
+        for (desc, value) in self.schema.iter().zip(record.values.iter()) {
+            validate_value(desc, value)?;
+        }
+        Ok(())
+    }
+
+    fn validate_key(&self, key: &Value) -> Result<(), KeyTypeMismatch> {
+        let expected = self.schema[self.primary_index].datatype.clone();
+        if key.datatype() != expected {
+            return Err(KeyTypeMismatch {
+                expected,
+                actual: key.datatype(),
+            });
+        }
+        Ok(())
+    }
+
+    fn coerce_key<'k>(&self, key: &'k Value) -> Result<Cow<'k, Value>, KeyTypeMismatch> {
+        let expected = self.schema[self.primary_index].datatype.clone();
+        if key.datatype() == expected {
+            return Ok(Cow::Borrowed(key));
+        }
+        self.coerce_integer_keys
+            .then(|| key.cast_integer(expected.clone()))
+            .flatten()
+            .map(Cow::Owned)
+            .ok_or(KeyTypeMismatch {
+                expected,
+                actual: key.datatype(),
+            })
+    }
 }
 
 /// Creates a [`DynSchema`] from literal slice of values and primary key index, suitable for rapid
@@ -161,9 +513,18 @@ macro_rules! make_dyn_schema {
 
 #[cfg(test)]
 mod tests {
-    use arrow::datatypes::{DataType, Field, Schema, TimeUnit};
+    use std::sync::Arc;
+
+    use arrow::datatypes::{DataType, Field, IntervalUnit, Schema, TimeUnit};
 
-    use super::DynSchema;
+    use super::{DynSchema, SchemaError};
+    use crate::{
+        dyn_schema, magic,
+        record::{
+            runtime::test::test_dyn_item_schema, DataType as ColumnType, DynRecordBuilder,
+            RecordValidationError, Schema as _, Value, ValueDesc,
+        },
+    };
 
     #[test]
     fn test_from_arrow_schema() {
@@ -179,7 +540,7 @@ mod tests {
         ];
         let arrow_schema = Schema::new(fields.clone());
 
-        let dyn_schema = DynSchema::from_arrow_schema(arrow_schema.clone(), 0).unwrap();
+        let dyn_schema = DynSchema::from_arrow_schema(&arrow_schema, 0).unwrap();
         for (expected, actual) in dyn_schema
             .arrow_schema
             .fields()
@@ -189,7 +550,8 @@ mod tests {
         {
             assert_eq!(expected, actual)
         }
-        for (expected, actual) in dyn_schema.schema.iter().skip(2).zip(arrow_schema.fields()) {
+        assert_eq!(dyn_schema.schema.len(), fields.len());
+        for (expected, actual) in dyn_schema.schema.iter().zip(arrow_schema.fields()) {
             assert_eq!(&expected.name, actual.name());
             assert_eq!(expected.is_nullable, actual.is_nullable());
             assert_eq!(expected.datatype, actual.data_type().into());
@@ -199,4 +561,292 @@ mod tests {
         let primary_key_index = metadata.get("primary_key_index");
         assert_eq!(primary_key_index, Some(&"0".into()));
     }
+
+    #[test]
+    fn test_arrow_schema_round_trip() {
+        let schema = test_dyn_item_schema();
+        let descs = |schema: &DynSchema| {
+            schema
+                .schema
+                .iter()
+                .map(|desc| (desc.name.clone(), desc.datatype.clone(), desc.is_nullable))
+                .collect::<Vec<_>>()
+        };
+        let rebuilt = DynSchema::from_arrow_schema(schema.arrow_schema(), 0).unwrap();
+        assert_eq!(descs(&rebuilt), descs(&schema));
+        assert_eq!(rebuilt.primary_index, schema.primary_index);
+        assert_eq!(rebuilt.arrow_schema(), schema.arrow_schema());
+
+        // user columns only
+        let user_columns = Schema::new(schema.arrow_schema().fields()[2..].to_vec());
+        let rebuilt = DynSchema::from_arrow_schema(&user_columns, 0).unwrap();
+        assert_eq!(descs(&rebuilt), descs(&schema));
+
+        let reserved = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new("_ts", DataType::UInt32, false),
+        ]);
+        assert!(matches!(
+            DynSchema::from_arrow_schema(&reserved, 0),
+            Err(SchemaError::ReservedColumn(name)) if name == "_ts"
+        ));
+        let reserved = Schema::new(vec![
+            Field::new("_null", DataType::Boolean, false),
+            Field::new("_ts", DataType::Int64, false),
+            Field::new("id", DataType::Int64, false),
+        ]);
+        assert!(matches!(
+            DynSchema::from_arrow_schema(&reserved, 0),
+            Err(SchemaError::ReservedColumn(name)) if name == "_ts"
+        ));
+
+        let unsupported = Schema::new(vec![
+            Field::new("id", DataType::Int64, false),
+            Field::new_list("tags", Field::new_list_field(DataType::Utf8, true), true),
+        ]);
+        let err = DynSchema::from_arrow_schema(&unsupported, 0).unwrap_err();
+        assert!(matches!(&err, SchemaError::UnsupportedType(name, _) if name == "tags"));
+        assert!(err
+            .to_string()
+            .starts_with("column tags of arrow type List("));
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let schema = dyn_schema!(("id", Int64, false), ("Name", String, true), 0);
+        assert_eq!(schema.column_index("id"), Some(2));
+        assert_eq!(schema.column_index("ID"), None);
+
+        let schema = schema.case_insensitive().unwrap();
+        assert_eq!(schema.column_index("ID"), Some(2));
+        assert_eq!(schema.column_index("name"), Some(3));
+        assert_eq!(schema.column_index("email"), None);
+
+        let record = DynRecordBuilder::new(&schema)
+            .set("ID", 1_i64)
+            .build()
+            .unwrap();
+        assert_eq!(record.values[0].name(), "id");
+
+        assert!(matches!(
+            dyn_schema!(("id", Int64, false), ("ID", String, true), 0).case_insensitive(),
+            Err(SchemaError::AmbiguousColumn(name)) if name == "ID"
+        ));
+    }
+
+    #[test]
+    fn test_add_column() {
+        let schema = dyn_schema!(("id", Int64, false), ("name", String, true), 0);
+        let score = |value: i32| {
+            ValueDesc::new("score".to_string(), ColumnType::Int32, false).with_default(Value::new(
+                ColumnType::Int32,
+                "score".to_string(),
+                Arc::new(value),
+                false,
+            ))
+        };
+
+        let added = schema.add_column(score(0)).unwrap();
+        assert_eq!(added.primary_index, 0);
+        assert_eq!(added.column_index("score"), Some(4));
+        let stored = DynSchema::from_stored_arrow_schema(added.arrow_schema()).unwrap();
+        let default = stored.schema[2].default.as_ref().unwrap();
+        assert_eq!(default.get::<i32>(), Some(&0));
+
+        // a nullable column reads null without a default
+        let added = schema
+            .add_column(ValueDesc::new("note".to_string(), ColumnType::String, true))
+            .unwrap();
+        assert!(added.schema[2].default.is_none());
+
+        assert!(matches!(
+            schema.add_column(ValueDesc::new("score".to_string(), ColumnType::Int32, false)),
+            Err(SchemaError::MissingDefault(name)) if name == "score"
+        ));
+        assert!(matches!(
+            schema.add_column(ValueDesc::new("name".to_string(), ColumnType::Int32, true)),
+            Err(SchemaError::DuplicateColumn(name)) if name == "name"
+        ));
+        assert!(matches!(
+            schema.add_column(ValueDesc::new(
+                magic::TS.to_string(),
+                ColumnType::UInt32,
+                true
+            )),
+            Err(SchemaError::ReservedColumn(_))
+        ));
+        assert!(matches!(
+            schema.add_column(
+                ValueDesc::new("score".to_string(), ColumnType::Int32, false).with_default(
+                    Value::new(
+                        ColumnType::Int64,
+                        "score".to_string(),
+                        Arc::new(0_i64),
+                        false
+                    )
+                )
+            ),
+            Err(SchemaError::InvalidDefault(
+                _,
+                RecordValidationError::ColumnTypeMismatch { .. }
+            ))
+        ));
+        assert!(matches!(
+            schema.add_column(
+                ValueDesc::new("bytes".to_string(), ColumnType::Bytes, false).with_default(
+                    Value::new(
+                        ColumnType::Bytes,
+                        "bytes".to_string(),
+                        Arc::new(vec![0_u8]),
+                        false
+                    )
+                )
+            ),
+            Err(SchemaError::UnsupportedDefault(_, ColumnType::Bytes))
+        ));
+    }
+
+    #[test]
+    fn test_from_stored_arrow_schema() {
+        let schema = dyn_schema!(("name", String, true), ("id", Int64, false), 1);
+
+        let stored = DynSchema::from_stored_arrow_schema(schema.arrow_schema()).unwrap();
+        assert_eq!(stored.primary_index, 1);
+        assert_eq!(stored.arrow_schema(), schema.arrow_schema());
+
+        let with_metadata = |index: &str| {
+            Schema::new_with_metadata(
+                schema.arrow_schema().fields().clone(),
+                [("primary_key_index".to_string(), index.to_string())].into(),
+            )
+        };
+        for index in ["", "one", "-1", "2", "0,x", "1,"] {
+            assert!(matches!(
+                DynSchema::from_stored_arrow_schema(&with_metadata(index)),
+                Err(SchemaError::InvalidMetadata(_))
+            ));
+        }
+        assert_eq!(
+            DynSchema::from_stored_arrow_schema(&with_metadata("0"))
+                .unwrap()
+                .primary_index,
+            0
+        );
+        let err = DynSchema::from_stored_arrow_schema(&with_metadata("0,1")).unwrap_err();
+        assert!(err.to_string().contains("composite primary key"));
+        assert!(matches!(
+            DynSchema::from_stored_arrow_schema(&Schema::new(
+                schema.arrow_schema().fields().clone()
+            )),
+            Err(SchemaError::InvalidMetadata(_))
+        ));
+        // user columns only, without the internal `_null` and `_ts` columns
+        assert!(matches!(
+            DynSchema::from_stored_arrow_schema(&Schema::new_with_metadata(
+                vec![Field::new("id", DataType::Int64, false)],
+                [("primary_key_index".to_string(), "0".to_string())].into(),
+            )),
+            Err(SchemaError::InvalidMetadata(_))
+        ));
+
+        let user_columns = Schema::new_with_metadata(
+            vec![
+                Field::new("id", DataType::Int64, false),
+                Field::new("name", DataType::Utf8, true),
+            ],
+            [("primary_key_index".to_string(), "1".to_string())].into(),
+        );
+        assert!(matches!(
+            DynSchema::from_arrow_schema(&user_columns, 0),
+            Err(SchemaError::InvalidMetadata(_))
+        ));
+    }
+
+    #[test]
+    fn test_interval_primary_key() {
+        let interval = DataType::Interval(IntervalUnit::MonthDayNano);
+        let arrow_schema = Schema::new(vec![
+            Field::new("id", DataType::UInt64, false),
+            Field::new("period", interval.clone(), true),
+        ]);
+        assert!(DynSchema::from_arrow_schema(&arrow_schema, 0).is_ok());
+
+        let arrow_schema = Schema::new(vec![Field::new("period", interval, false)]);
+        assert!(matches!(
+            DynSchema::from_arrow_schema(&arrow_schema, 0),
+            Err(SchemaError::InvalidPrimaryKey(name, _)) if name == "period"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be used as primary key")]
+    fn test_interval_primary_key_panics() {
+        dyn_schema!(("period", Interval, false), 0);
+    }
+
+    #[test]
+    fn test_list_column() {
+        let list = ColumnType::List(Box::new(ColumnType::Int32));
+        let schema = DynSchema::new(
+            vec![
+                ValueDesc::new("id".to_string(), ColumnType::Int64, false),
+                ValueDesc::new("tags".to_string(), list.clone(), true),
+            ],
+            0,
+        );
+        let item = Field::new("item", DataType::Int32, false);
+        assert_eq!(
+            schema.arrow_schema().field(3).data_type(),
+            &DataType::List(Arc::new(item))
+        );
+
+        let rebuilt = DynSchema::from_arrow_schema(schema.arrow_schema(), 0).unwrap();
+        assert_eq!(rebuilt.schema[1].datatype, list);
+        assert!(matches!(
+            DynSchema::from_arrow_schema(schema.arrow_schema(), 1),
+            Err(SchemaError::InvalidPrimaryKey(name, datatype))
+                if name == "tags" && datatype == list
+        ));
+
+        let item =
+            |value: i32| Value::new(ColumnType::Int32, "item".into(), Arc::new(value), false);
+        let record = DynRecordBuilder::new(&schema)
+            .set("id", 1_i64)
+            .set("tags", vec![item(1), item(2)])
+            .build()
+            .unwrap();
+        assert!(schema.validate_record(&record).is_ok());
+
+        // the items have to be of the item type
+        let record = DynRecordBuilder::new(&schema)
+            .set("id", 1_i64)
+            .set(
+                "tags",
+                vec![Value::new(
+                    ColumnType::Int64,
+                    "item".into(),
+                    Arc::new(1_i64),
+                    false,
+                )],
+            )
+            .build()
+            .unwrap();
+        assert!(matches!(
+            schema.validate_record(&record),
+            Err(RecordValidationError::InvalidValue { column, .. }) if column == "tags"
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be used as primary key")]
+    fn test_list_primary_key_panics() {
+        DynSchema::new(
+            vec![ValueDesc::new(
+                "tags".to_string(),
+                ColumnType::List(Box::new(ColumnType::Int32)),
+                false,
+            )],
+            0,
+        );
+    }
 }
@@ -1,4 +1,4 @@
-use std::{any::Any, fmt::Debug, hash::Hash, sync::Arc};
+use std::{any::Any, collections::HashMap, fmt::Debug, hash::Hash, mem, sync::Arc};
 
 use arrow::{
     array::{
@@ -9,22 +9,34 @@ use arrow::{
         TimestampNanosecondArray, TimestampSecondArray, UInt16Array, UInt32Array, UInt64Array,
         UInt8Array,
     },
-    datatypes::{DataType as ArrowDataType, Field},
+    datatypes::{DataType as ArrowDataType, Field, IntervalUnit},
 };
 use fusio::{SeqRead, Write};
 use fusio_log::{Decode, DecodeError, Encode};
 
-use super::DataType;
-use crate::record::{
-    Date32, Date64, Key, KeyRef, LargeBinary, LargeString, Time32, Time64, TimeUnit, Timestamp,
-    F32, F64,
+use super::{
+    list::{decode_items, encode_items, item_field, items_size},
+    DataType, BIGINT_EXTENSION_NAME, EXTENSION_NAME_KEY,
 };
+use crate::{
+    cast_arc_value,
+    record::{
+        BigInt, Date32, Date64, Decimal128, Interval, Key, KeyRef, LargeBinary, LargeString,
+        Time32, Time64, TimeUnit, Timestamp, F32, F64,
+    },
+};
+
+/// the arrow field metadata key storing the default of a column
+pub(crate) const DEFAULT_VALUE_KEY: &str = "tonbo.default";
 
 #[derive(Debug, Clone)]
 pub struct ValueDesc {
     pub datatype: DataType,
     pub is_nullable: bool,
     pub name: String,
+    /// value of the column in rows written before the column was added, see
+    /// [`DynSchema::add_column`](super::DynSchema::add_column)
+    pub default: Option<Arc<Value>>,
 }
 
 impl ValueDesc {
@@ -33,9 +45,31 @@ impl ValueDesc {
             name,
             datatype,
             is_nullable,
+            default: None,
+        }
+    }
+
+    /// set the default of the column. Only integer, float, boolean and string columns can have
+    /// a default
+    pub fn with_default(self, default: Value) -> Self {
+        Self {
+            default: Some(Arc::new(default)),
+            ..self
         }
     }
 
+    /// the default stored in the metadata of `field`, if any
+    pub(crate) fn field_default(&self, field: &Field) -> Option<Arc<Value>> {
+        let text = field.metadata().get(DEFAULT_VALUE_KEY)?;
+        let value = parse_default(&self.datatype, text, self.is_nullable)?;
+        Some(Arc::new(Value::new(
+            self.datatype.clone(),
+            self.name.clone(),
+            value,
+            self.is_nullable,
+        )))
+    }
+
     pub(crate) fn arrow_field(&self) -> Field {
         let arrow_type = match self.datatype {
             DataType::UInt8 => ArrowDataType::UInt8,
@@ -64,22 +98,70 @@ impl ValueDesc {
             DataType::Date64 => ArrowDataType::Date64,
             DataType::LargeBinary => ArrowDataType::LargeBinary,
             DataType::LargeString => ArrowDataType::LargeUtf8,
+            DataType::Interval => ArrowDataType::Interval(IntervalUnit::MonthDayNano),
+            DataType::Decimal128(precision, scale) => ArrowDataType::Decimal128(precision, scale),
+            DataType::BigInt => ArrowDataType::Binary,
+            DataType::List(ref item) => ArrowDataType::List(Arc::new(item_field(item))),
         };
-        Field::new(&self.name, arrow_type, self.is_nullable)
+        let mut metadata = HashMap::new();
+        if self.datatype == DataType::BigInt {
+            metadata.insert(
+                EXTENSION_NAME_KEY.to_string(),
+                BIGINT_EXTENSION_NAME.to_string(),
+            );
+        }
+        if let Some(text) = self.default.as_ref().and_then(|value| value.default_text()) {
+            metadata.insert(DEFAULT_VALUE_KEY.to_string(), text);
+        }
+        Field::new(&self.name, arrow_type, self.is_nullable).with_metadata(metadata)
+    }
+}
+
+/// parse the default stored by [`Value::default_text`]
+pub(crate) fn parse_default(
+    datatype: &DataType,
+    text: &str,
+    is_nullable: bool,
+) -> Option<Arc<dyn Any + Send + Sync>> {
+    fn wrap<T: Any + Send + Sync>(value: T, is_nullable: bool) -> Arc<dyn Any + Send + Sync> {
+        if is_nullable {
+            Arc::new(Some(value))
+        } else {
+            Arc::new(value)
+        }
     }
+
+    Some(match datatype {
+        DataType::UInt8 => wrap(text.parse::<u8>().ok()?, is_nullable),
+        DataType::UInt16 => wrap(text.parse::<u16>().ok()?, is_nullable),
+        DataType::UInt32 => wrap(text.parse::<u32>().ok()?, is_nullable),
+        DataType::UInt64 => wrap(text.parse::<u64>().ok()?, is_nullable),
+        DataType::Int8 => wrap(text.parse::<i8>().ok()?, is_nullable),
+        DataType::Int16 => wrap(text.parse::<i16>().ok()?, is_nullable),
+        DataType::Int32 => wrap(text.parse::<i32>().ok()?, is_nullable),
+        DataType::Int64 => wrap(text.parse::<i64>().ok()?, is_nullable),
+        DataType::Float32 => wrap(F32::from(text.parse::<f32>().ok()?), is_nullable),
+        DataType::Float64 => wrap(F64::from(text.parse::<f64>().ok()?), is_nullable),
+        DataType::Boolean => wrap(text.parse::<bool>().ok()?, is_nullable),
+        DataType::String => wrap(text.to_string(), is_nullable),
+        _ => return None,
+    })
 }
 
 impl From<Field> for ValueDesc {
     fn from(field: Field) -> Self {
-        let datatype = DataType::from(field.data_type());
-        ValueDesc::new(field.name().to_owned(), datatype, field.is_nullable())
+        ValueDesc::from(&field)
     }
 }
 
 impl From<&Field> for ValueDesc {
     fn from(field: &Field) -> Self {
-        let datatype = DataType::from(field.data_type());
-        ValueDesc::new(field.name().to_owned(), datatype, field.is_nullable())
+        let datatype = DataType::from_field(field);
+        let desc = ValueDesc::new(field.name().to_owned(), datatype, field.is_nullable());
+        ValueDesc {
+            default: desc.field_default(field),
+            ..desc
+        }
     }
 }
 
@@ -103,7 +185,7 @@ impl Value {
     }
 
     pub fn datatype(&self) -> DataType {
-        self.desc.datatype
+        self.desc.datatype.clone()
     }
 
     pub fn is_nullable(&self) -> bool {
@@ -113,6 +195,90 @@ impl Value {
     pub fn name(&self) -> String {
         self.desc.name.clone()
     }
+
+    /// the text a default is stored as in the arrow field metadata, `None` for a null value or a
+    /// datatype without a text form
+    pub(crate) fn default_text(&self) -> Option<String> {
+        match self.datatype() {
+            DataType::UInt8 => self.get::<u8>().map(u8::to_string),
+            DataType::UInt16 => self.get::<u16>().map(u16::to_string),
+            DataType::UInt32 => self.get::<u32>().map(u32::to_string),
+            DataType::UInt64 => self.get::<u64>().map(u64::to_string),
+            DataType::Int8 => self.get::<i8>().map(i8::to_string),
+            DataType::Int16 => self.get::<i16>().map(i16::to_string),
+            DataType::Int32 => self.get::<i32>().map(i32::to_string),
+            DataType::Int64 => self.get::<i64>().map(i64::to_string),
+            // the shortest text that parses back to the same float
+            DataType::Float32 => self.get::<F32>().map(|value| value.value().to_string()),
+            DataType::Float64 => self.get::<F64>().map(|value| value.value().to_string()),
+            DataType::Boolean => self.get::<bool>().map(bool::to_string),
+            DataType::String => self.get::<String>().cloned(),
+            _ => None,
+        }
+    }
+
+    /// the inner value, whether it is stored as `T` or as a nullable `Option<T>`
+    pub(crate) fn get<T: 'static>(&self) -> Option<&T> {
+        self.value.downcast_ref::<T>().or_else(|| {
+            self.value
+                .downcast_ref::<Option<T>>()
+                .and_then(Option::as_ref)
+        })
+    }
+
+    /// bytes this value allocates on the heap: the shared allocation holding it, its column name
+    /// and the contents of strings and binaries
+    pub fn heap_size(&self) -> usize {
+        let contents = match self.datatype() {
+            DataType::String | DataType::LargeString => {
+                self.get::<String>().map_or(0, String::capacity)
+            }
+            DataType::Bytes | DataType::LargeBinary => {
+                self.get::<Vec<u8>>().map_or(0, Vec::capacity)
+            }
+            DataType::BigInt => self
+                .get::<BigInt>()
+                .map_or(0, |value| value.bytes.capacity()),
+            DataType::List(_) => self.get::<Vec<Value>>().map_or(0, |items| {
+                items.capacity() * mem::size_of::<Value>()
+                    + items.iter().map(Value::heap_size).sum::<usize>()
+            }),
+            _ => 0,
+        };
+        // the strong and weak counts of the `Arc` are allocated along with the value
+        2 * mem::size_of::<usize>()
+            + mem::size_of_val(self.value.as_ref())
+            + self.desc.name.capacity()
+            + contents
+    }
+
+    /// convert an integer value to the integer `datatype`, returns `None` if either type is not
+    /// an integer or the value is out of the range of `datatype`
+    pub fn cast_integer(&self, datatype: DataType) -> Option<Value> {
+        let value = match self.datatype() {
+            DataType::UInt8 => *self.value.downcast_ref::<u8>()? as i128,
+            DataType::UInt16 => *self.value.downcast_ref::<u16>()? as i128,
+            DataType::UInt32 => *self.value.downcast_ref::<u32>()? as i128,
+            DataType::UInt64 => *self.value.downcast_ref::<u64>()? as i128,
+            DataType::Int8 => *self.value.downcast_ref::<i8>()? as i128,
+            DataType::Int16 => *self.value.downcast_ref::<i16>()? as i128,
+            DataType::Int32 => *self.value.downcast_ref::<i32>()? as i128,
+            DataType::Int64 => *self.value.downcast_ref::<i64>()? as i128,
+            _ => return None,
+        };
+        let value: Arc<dyn Any + Send + Sync> = match datatype {
+            DataType::UInt8 => Arc::new(u8::try_from(value).ok()?),
+            DataType::UInt16 => Arc::new(u16::try_from(value).ok()?),
+            DataType::UInt32 => Arc::new(u32::try_from(value).ok()?),
+            DataType::UInt64 => Arc::new(u64::try_from(value).ok()?),
+            DataType::Int8 => Arc::new(i8::try_from(value).ok()?),
+            DataType::Int16 => Arc::new(i16::try_from(value).ok()?),
+            DataType::Int32 => Arc::new(i32::try_from(value).ok()?),
+            DataType::Int64 => Arc::new(i64::try_from(value).ok()?),
+            _ => return None,
+        };
+        Some(Value::new(datatype, self.name(), value, self.is_nullable()))
+    }
 }
 
 impl Eq for Value {}
@@ -125,14 +291,14 @@ impl PartialOrd for Value {
 
 macro_rules! implement_col {
     ([], $({$Type:ty, $DataType:pat}), *) => {
+        /// values of nullable columns are compared by their inner value and nulls sort first,
+        /// matching the `nulls_first` of the sorting columns in
+        /// [`Schema::primary_key_path`](crate::record::Schema::primary_key_path)
         impl Ord for Value {
             fn cmp(&self, other: &Self) -> std::cmp::Ordering {
                 match self.datatype() {
                     $(
-                        $DataType => self
-                            .value
-                            .downcast_ref::<$Type>()
-                            .cmp(&other.value.downcast_ref::<$Type>()),
+                        $DataType => self.get::<$Type>().cmp(&other.get::<$Type>()),
                     )*
                     DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 }
@@ -168,7 +334,7 @@ macro_rules! implement_col {
             fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
                 match self.datatype() {
                     $(
-                        $DataType => self.value.downcast_ref::<$Type>().hash(state),
+                        $DataType => self.get::<$Type>().hash(state),
                     )*
                     DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 }
@@ -210,6 +376,34 @@ macro_rules! implement_col {
                     DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 }
             }
+
+            /// whether the value is stored as the type of its datatype, in an `Option` if it is
+            /// nullable
+            pub(crate) fn is_well_formed(&self) -> bool {
+                match self.datatype() {
+                    $(
+                        $DataType => if self.is_nullable() {
+                            self.value.is::<Option<$Type>>()
+                        } else {
+                            self.value.is::<$Type>()
+                        },
+                    )*
+                    DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
+                }
+            }
+
+            /// whether this is the null value of a nullable column
+            pub fn is_null(&self) -> bool {
+                match self.datatype() {
+                    $(
+                        $DataType => self
+                            .value
+                            .downcast_ref::<Option<$Type>>()
+                            .is_some_and(Option::is_none),
+                    )*
+                    DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
+                }
+            }
         }
     };
 }
@@ -228,7 +422,7 @@ macro_rules! implement_key_col {
                 self.clone()
             }
 
-            fn to_arrow_datum(&self) -> Arc<dyn arrow::array::Datum> {
+            fn to_arrow_datum(&self) -> Arc<dyn arrow::array::Datum + Send + Sync> {
                 match self.datatype() {
                     $(
                         $primitive_pat => Arc::new(<$primitive_array_ty>::new_scalar(
@@ -258,6 +452,21 @@ macro_rules! implement_key_col {
                                 .$value_fn()
                         )),
                     )*
+                    // the arrow datatype carries the precision and scale of the decimal
+                    DataType::Decimal128(..) => self
+                        .value
+                        .as_ref()
+                        .downcast_ref::<Decimal128>()
+                        .expect("unexpected datatype, expected Decimal128")
+                        .to_arrow_datum(),
+                    DataType::BigInt => self
+                        .value
+                        .as_ref()
+                        .downcast_ref::<BigInt>()
+                        .expect("unexpected datatype, expected BigInt")
+                        .to_arrow_datum(),
+                    DataType::Interval => unreachable!("interval can not be used as primary key"),
+                    DataType::List(_) => unreachable!("list can not be used as primary key"),
                     DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 }
             }
@@ -283,11 +492,22 @@ macro_rules! implement_decode_col {
                 R: SeqRead,
             {
                 let tag = u8::decode(reader).await?;
-                let datatype = Self::tag_to_datatype(tag);
+                let datatype = match tag {
+                    DECIMAL128_TAG => {
+                        let precision = u8::decode(reader).await?;
+                        let scale = i8::decode(reader).await?;
+                        DataType::Decimal128(precision, scale)
+                    }
+                    LIST_TAG => {
+                        let item = u8::decode(reader).await?;
+                        DataType::List(Box::new(Self::tag_to_datatype(item)))
+                    }
+                    tag => Self::tag_to_datatype(tag),
+                };
                 let is_nullable = bool::decode(reader).await?;
                 let is_some = !bool::decode(reader).await?;
                 let value =
-                    match datatype {
+                    match &datatype {
                         $(
                             $DataType => match is_some {
                                 true => Arc::new(Option::<$Type>::decode(reader).await.map_err(
@@ -300,6 +520,18 @@ macro_rules! implement_decode_col {
                                 false => Arc::new(<$Type>::decode(reader).await?) as Arc<dyn Any + Send + Sync>,
                             },
                         )*
+                        DataType::List(item) => match is_some {
+                            true => {
+                                let items = match bool::decode(reader).await? {
+                                    true => Some(decode_items(item, reader).await?),
+                                    false => None,
+                                };
+                                Arc::new(items) as Arc<dyn Any + Send + Sync>
+                            }
+                            false => {
+                                Arc::new(decode_items(item, reader).await?) as Arc<dyn Any + Send + Sync>
+                            }
+                        },
                         DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                     };
                 let name = String::decode(reader).await?;
@@ -323,7 +555,15 @@ macro_rules! implement_encode_col {
             where
                 W: Write,
             {
-                Self::tag(self.datatype()).encode(writer).await?;
+                Self::tag(&self.desc.datatype).encode(writer).await?;
+                match &self.desc.datatype {
+                    DataType::Decimal128(precision, scale) => {
+                        precision.encode(writer).await?;
+                        scale.encode(writer).await?;
+                    }
+                    DataType::List(item) => Self::tag(item).encode(writer).await?,
+                    _ => {}
+                }
                 self.is_nullable().encode(writer).await?;
                 match self.datatype() {
                         $(
@@ -343,6 +583,20 @@ macro_rules! implement_encode_col {
                                 }
                             }
                         )*
+                        // a nullable list is encoded like an `Option`
+                        DataType::List(_) => {
+                            if let Some(items) = self.value.as_ref().downcast_ref::<Vec<Value>>() {
+                                true.encode(writer).await?;
+                                encode_items(items, writer).await?
+                            } else {
+                                false.encode(writer).await?;
+                                let items = cast_arc_value!(self.value, Option<Vec<Value>>);
+                                items.is_some().encode(writer).await?;
+                                if let Some(items) = items {
+                                    encode_items(items, writer).await?;
+                                }
+                            }
+                        }
                         DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 };
                 self.desc.name.encode(writer).await?;
@@ -350,7 +604,13 @@ macro_rules! implement_encode_col {
             }
 
             fn size(&self) -> usize {
-                3 + self.desc.name.size() + match self.desc.datatype {
+                let decimal_size = match self.desc.datatype {
+                    DataType::Decimal128(precision, scale) => precision.size() + scale.size(),
+                    // the tag of the items
+                    DataType::List(_) => mem::size_of::<u8>(),
+                    _ => 0,
+                };
+                3 + decimal_size + self.desc.name.size() + match self.desc.datatype {
                     $(
                         $DataType => {
                             if let Some(value) = self.value.as_ref().downcast_ref::<$Type>() {
@@ -364,6 +624,16 @@ macro_rules! implement_encode_col {
                             }
                         }
                     )*
+                    DataType::List(_) => {
+                        if let Some(items) = self.value.as_ref().downcast_ref::<Vec<Value>>() {
+                            items_size(items)
+                        } else {
+                            cast_arc_value!(self.value, Option<Vec<Value>>)
+                                .as_deref()
+                                .map_or(0, items_size)
+                                + mem::size_of::<bool>()
+                        }
+                    }
                     DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
                 }
             }
@@ -371,8 +641,14 @@ macro_rules! implement_encode_col {
     }
 }
 
+/// the tag of decimal values, which is followed by the precision and scale of the column
+const DECIMAL128_TAG: u8 = 26;
+
+/// the tag of list values, which is followed by the tag of the items
+const LIST_TAG: u8 = 28;
+
 impl Value {
-    fn tag(datatype: DataType) -> u8 {
+    fn tag(datatype: &DataType) -> u8 {
         match datatype {
             DataType::UInt8 => 0,
             DataType::UInt16 => 1,
@@ -399,6 +675,10 @@ impl Value {
             DataType::Date64 => 22,
             DataType::LargeBinary => 23,
             DataType::LargeString => 24,
+            DataType::Interval => 25,
+            DataType::Decimal128(..) => DECIMAL128_TAG,
+            DataType::BigInt => 27,
+            DataType::List(_) => LIST_TAG,
             DataType::Time32(_) | DataType::Time64(_) => unreachable!(),
         }
     }
@@ -430,6 +710,8 @@ impl Value {
             22 => DataType::Date64,
             23 => DataType::LargeBinary,
             24 => DataType::LargeString,
+            25 => DataType::Interval,
+            27 => DataType::BigInt,
             _ => panic!("invalid datatype tag"),
         }
     }
@@ -482,14 +764,25 @@ impl From<&ValueDesc> for Field {
             DataType::LargeString => {
                 Field::new(&col.name, ArrowDataType::LargeUtf8, col.is_nullable)
             }
+            DataType::Interval => Field::new(
+                &col.name,
+                ArrowDataType::Interval(IntervalUnit::MonthDayNano),
+                col.is_nullable,
+            ),
+            DataType::Decimal128(precision, scale) => Field::new(
+                &col.name,
+                ArrowDataType::Decimal128(precision, scale),
+                col.is_nullable,
+            ),
+            DataType::BigInt | DataType::List(_) => col.arrow_field(),
         }
     }
 }
 
 macro_rules! for_datatype {
-    ($macro:tt $(, $x:tt)*) => {
+    ($macro:tt $(, { $ty:ty, $pat:pat })*) => {
         $macro! {
-            [$($x),*],
+            [],
                 { u8, DataType::UInt8 },
                 { u16, DataType::UInt16 },
                 { u32, DataType::UInt32 },
@@ -507,6 +800,9 @@ macro_rules! for_datatype {
                 { LargeBinary, DataType::LargeBinary },
                 { Date32, DataType::Date32 },
                 { Date64, DataType::Date64 },
+                { Interval, DataType::Interval },
+                { Decimal128, DataType::Decimal128(..) },
+                { BigInt, DataType::BigInt },
                 { Timestamp, DataType::Timestamp(TimeUnit::Second) },
                 { Timestamp, DataType::Timestamp(TimeUnit::Millisecond) },
                 { Timestamp, DataType::Timestamp(TimeUnit::Microsecond) },
@@ -515,7 +811,7 @@ macro_rules! for_datatype {
                 { Time32, DataType::Time32(TimeUnit::Millisecond) },
                 { Time64, DataType::Time64(TimeUnit::Microsecond) },
                 { Time64, DataType::Time64(TimeUnit::Nanosecond) }
-
+                $(, { $ty, $pat })*
         }
     };
 }
@@ -550,13 +846,13 @@ implement_key_col!(
 
     }
 );
-for_datatype! { implement_col }
+for_datatype! { implement_col, { Vec<Value>, DataType::List(_) } }
 for_datatype! { implement_decode_col }
 for_datatype! { implement_encode_col }
 
 #[cfg(test)]
 mod tests {
-    use std::sync::Arc;
+    use std::{cmp::Ordering, sync::Arc};
 
     use super::Value;
     use crate::record::{DataType, TimeUnit, Timestamp};
@@ -630,4 +926,20 @@ mod tests {
             assert_ne!(value1, value3);
         }
     }
+
+    #[test]
+    fn test_value_null_ordering() {
+        let int = |value: Option<i32>| {
+            Value::new(DataType::Int32, "int".to_string(), Arc::new(value), true)
+        };
+        let zero = Value::new(DataType::Int32, "int".to_string(), Arc::new(0_i32), false);
+
+        assert!(int(None) < zero);
+        assert!(int(None) < int(Some(0)));
+        assert!(int(Some(-1)) < int(Some(0)));
+        assert_eq!(int(Some(0)).cmp(&zero), Ordering::Equal);
+        assert!((int(None), int(Some(1))) < (int(None), int(Some(2))));
+        assert_eq!(int(None).cmp(&int(None)), Ordering::Equal);
+        assert_eq!(int(None), int(None));
+    }
 }
@@ -86,6 +86,10 @@ impl<'r> RecordRef<'r> for &'r str {
 
     fn projection(&mut self, _: &ProjectionMask) {}
 
+    fn is_null(&self, _: usize) -> bool {
+        false
+    }
+
     fn from_record_batch(
         record_batch: &'r RecordBatch,
         offset: usize,
@@ -1,4 +1,10 @@
-use std::{collections::Bound, sync::Arc};
+use std::{
+    collections::{BTreeMap, Bound},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
 
 use async_lock::RwLockReadGuard;
 use parquet::arrow::ProjectionMask;
@@ -12,10 +18,54 @@ use crate::{
     DbError, DbStorage, Projection, Scan,
 };
 
+/// a live [`Snapshot`] listed by [`crate::DB::list_snapshots`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnapshotInfo {
+    /// unique within the database, ids increase in the order snapshots were taken
+    pub id: u64,
+    /// the timestamp the snapshot reads at, writes after it are not visible to the snapshot
+    pub ts: Timestamp,
+    /// number of timestamps the database has advanced since the snapshot was taken
+    pub age: u32,
+}
+
+/// pinned timestamps of the snapshots that have not been dropped yet
+#[derive(Default)]
+pub(crate) struct SnapshotRegistry {
+    next_id: AtomicU64,
+    snapshots: Mutex<BTreeMap<u64, Timestamp>>,
+}
+
+impl SnapshotRegistry {
+    fn register(&self, ts: Timestamp) -> u64 {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        self.snapshots.lock().unwrap().insert(id, ts);
+        id
+    }
+
+    fn deregister(&self, id: u64) {
+        self.snapshots.lock().unwrap().remove(&id);
+    }
+
+    pub(crate) fn list(&self, now: Timestamp) -> Vec<SnapshotInfo> {
+        self.snapshots
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, ts)| SnapshotInfo {
+                id: *id,
+                ts: *ts,
+                age: u32::from(now).saturating_sub(u32::from(*ts)),
+            })
+            .collect()
+    }
+}
+
 pub struct Snapshot<'s, R>
 where
     R: Record,
 {
+    id: u64,
     ts: Timestamp,
     share: RwLockReadGuard<'s, DbStorage<R>>,
     version: VersionRef<R>,
@@ -66,8 +116,10 @@ where
         version: VersionRef<R>,
         ctx: Arc<Context<R>>,
     ) -> Self {
+        let ts = version.load_ts();
         Self {
-            ts: version.load_ts(),
+            id: ctx.snapshots.register(ts),
+            ts,
             share,
             version,
             ctx,
@@ -107,6 +159,15 @@ where
     }
 }
 
+impl<R> Drop for Snapshot<'_, R>
+where
+    R: Record,
+{
+    fn drop(&mut self) {
+        self.ctx.snapshots.deregister(self.id);
+    }
+}
+
 #[cfg(all(test, feature = "tokio"))]
 mod tests {
     use std::{collections::Bound, sync::Arc};
@@ -121,8 +182,8 @@ mod tests {
         executor::tokio::TokioExecutor,
         fs::manager::StoreManager,
         inmem::immutable::tests::TestSchema,
-        tests::{build_db, build_schema},
-        DbOption,
+        tests::{build_db, build_schema, Test},
+        DbOption, DB,
     };
 
     #[tokio::test(flavor = "multi_thread")]
@@ -214,4 +275,51 @@ mod tests {
         let entry_14 = stream.next().await.unwrap().unwrap();
         assert_eq!(entry_14.key().value, "funk");
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn list_snapshots() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        db.insert(Test {
+            vstring: "alice".to_string(),
+            vu32: 0,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+        let first = db.snapshot().await;
+        db.insert(Test {
+            vstring: "ben".to_string(),
+            vu32: 1,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+        let second = db.snapshot().await;
+        assert_eq!(db.list_snapshots().len(), 2);
+
+        drop(first);
+        db.insert(Test {
+            vstring: "carl".to_string(),
+            vu32: 2,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+
+        let snapshots = db.list_snapshots();
+        assert_eq!(snapshots.len(), 1);
+        assert_eq!(snapshots[0].ts, second.ts());
+        assert_eq!(snapshots[0].age, 1);
+
+        drop(second);
+        assert!(db.list_snapshots().is_empty());
+    }
 }
@@ -6,6 +6,7 @@ use std::{
     task::{Context, Poll},
 };
 
+use arrow::datatypes::Schema as ArrowSchema;
 use fusio::{
     dynamic::{DynFile, MaybeSendFuture},
     path::Path,
@@ -23,6 +24,7 @@ use crate::{
     scope::Scope,
     stream::record_batch::RecordBatchEntry,
     timestamp::Timestamp,
+    verify::{Quarantine, ScanGaps},
     version::Version,
     DbOption,
 };
@@ -41,6 +43,7 @@ where
     LoadStream(
         Pin<Box<dyn Future<Output = Result<SsTableScan<'level, R>, ParquetError>> + Send + 'level>>,
     ),
+    Done,
 }
 
 pub(crate) struct LevelStream<'level, R>
@@ -52,13 +55,19 @@ where
     ts: Timestamp,
     level: usize,
     option: Arc<DbOption>,
-    gens: VecDeque<FileId>,
+    scope: Scope<<R::Schema as Schema>::Key>,
+    scopes: VecDeque<Scope<<R::Schema as Schema>::Key>>,
     limit: Option<usize>,
     projection_mask: ProjectionMask,
+    arrow_schema: Arc<ArrowSchema>,
     status: FutureStatus<'level, R>,
     fs: Arc<dyn DynFs>,
     path: Option<Path>,
     parquet_lru: Arc<dyn DynLruCache<Ulid> + Send + Sync>,
+    quarantine: Option<(
+        Arc<Quarantine<<R::Schema as Schema>::Key>>,
+        ScanGaps<<R::Schema as Schema>::Key>,
+    )>,
 }
 
 impl<'level, R> LevelStream<'level, R>
@@ -79,16 +88,21 @@ where
         ts: Timestamp,
         limit: Option<usize>,
         projection_mask: ProjectionMask,
+        arrow_schema: Arc<ArrowSchema>,
         fs: Arc<dyn DynFs>,
         parquet_lru: Arc<dyn DynLruCache<Ulid> + Send + Sync>,
+        quarantine: Option<(
+            Arc<Quarantine<<R::Schema as Schema>::Key>>,
+            ScanGaps<<R::Schema as Schema>::Key>,
+        )>,
     ) -> Option<Self> {
         let (lower, upper) = range;
-        let mut gens: VecDeque<FileId> = version.level_slice[level][start..end + 1]
+        let mut scopes: VecDeque<_> = version.level_slice[level][start..end + 1]
             .iter()
-            .map(Scope::gen)
+            .cloned()
             .collect();
-        let first_gen = gens.pop_front()?;
-        let status = FutureStatus::Init(first_gen);
+        let first = scopes.pop_front()?;
+        let status = FutureStatus::Init(first.gen());
 
         Some(LevelStream {
             lower,
@@ -96,15 +110,40 @@ where
             ts,
             level,
             option: version.option().clone(),
-            gens,
+            scope: first,
+            scopes,
             limit,
             projection_mask,
+            arrow_schema,
             status,
             fs,
             path: None,
             parquet_lru,
+            quarantine,
         })
     }
+
+    fn next_table(&mut self) {
+        self.status = match self.scopes.pop_front() {
+            Some(scope) => {
+                let gen = scope.gen();
+                self.scope = scope;
+                FutureStatus::Init(gen)
+            }
+            None => FutureStatus::Done,
+        };
+    }
+
+    /// with quarantine, quarantines the table that failed to open with `err`, reports it as a
+    /// gap of the scan and moves on to the next table. Otherwise returns `err`
+    fn skip_table(&mut self, err: ParquetError) -> Result<(), ParquetError> {
+        let Some((quarantine, gaps)) = &self.quarantine else {
+            return Err(err);
+        };
+        gaps.push(quarantine.insert(self.level, &self.scope, err));
+        self.next_table();
+        Ok(())
+    }
 }
 
 impl<'level, R> Stream for LevelStream<'level, R>
@@ -140,32 +179,10 @@ where
                     continue;
                 }
                 FutureStatus::Ready(stream) => match Pin::new(stream).poll_next(cx) {
-                    Poll::Ready(None) => match self.gens.pop_front() {
-                        None => Poll::Ready(None),
-                        Some(gen) => {
-                            self.path = Some(self.option.table_path(gen, self.level));
-
-                            let reader = self.fs.open_options(
-                                self.path.as_ref().unwrap(),
-                                FileType::Parquet.open_options(true),
-                            );
-                            #[allow(clippy::missing_transmute_annotations)]
-                            let reader = unsafe {
-                                std::mem::transmute::<
-                                    _,
-                                    Pin<
-                                        Box<
-                                            dyn MaybeSendFuture<
-                                                    Output = Result<Box<dyn DynFile>, Error>,
-                                                > + 'static,
-                                        >,
-                                    >,
-                                >(reader)
-                            };
-                            self.status = FutureStatus::OpenFile(gen, reader);
-                            continue;
-                        }
-                    },
+                    Poll::Ready(None) => {
+                        self.next_table();
+                        continue;
+                    }
                     Poll::Ready(Some(result)) => {
                         if let Some(limit) = &mut self.limit {
                             *limit -= 1;
@@ -185,12 +202,16 @@ where
                         continue;
                     }
                     Poll::Ready(Err(err)) => {
-                        Poll::Ready(Some(Err(ParquetError::External(Box::new(err)))))
+                        match self.skip_table(ParquetError::External(Box::new(err))) {
+                            Ok(()) => continue,
+                            Err(err) => Poll::Ready(Some(Err(err))),
+                        }
                     }
                     Poll::Pending => Poll::Pending,
                 },
                 FutureStatus::OpenSst(sst_future) => match Pin::new(sst_future).poll(cx) {
                     Poll::Ready(Ok(sst)) => {
+                        let sst = sst.with_schema(self.arrow_schema.clone());
                         self.status = FutureStatus::LoadStream(Box::pin(sst.scan(
                             (self.lower, self.upper),
                             self.ts,
@@ -200,18 +221,25 @@ where
                         continue;
                     }
                     Poll::Ready(Err(err)) => {
-                        Poll::Ready(Some(Err(ParquetError::External(Box::new(err)))))
+                        match self.skip_table(ParquetError::External(Box::new(err))) {
+                            Ok(()) => continue,
+                            Err(err) => Poll::Ready(Some(Err(err))),
+                        }
                     }
                     Poll::Pending => Poll::Pending,
                 },
                 FutureStatus::LoadStream(stream_future) => match Pin::new(stream_future).poll(cx) {
                     Poll::Ready(Ok(scan)) => {
-                        self.status = FutureStatus::Ready(scan);
+                        self.status = FutureStatus::Ready(scan.located(self.level, self.scope.gen));
                         continue;
                     }
-                    Poll::Ready(Err(err)) => Poll::Ready(Some(Err(err))),
+                    Poll::Ready(Err(err)) => match self.skip_table(err) {
+                        Ok(()) => continue,
+                        Err(err) => Poll::Ready(Some(Err(err))),
+                    },
                     Poll::Pending => Poll::Pending,
                 },
+                FutureStatus::Done => Poll::Ready(None),
             };
         }
     }
@@ -270,8 +298,10 @@ mod tests {
                         .unwrap(),
                     [0, 1, 2, 3],
                 ),
+                TestSchema {}.arrow_schema().clone(),
                 manager.base_fs().clone(),
                 Arc::new(NoCache::default()),
+                None,
             )
             .unwrap();
 
@@ -309,8 +339,10 @@ mod tests {
                         .unwrap(),
                     [0, 1, 2, 4],
                 ),
+                TestSchema {}.arrow_schema().clone(),
                 manager.base_fs().clone(),
                 Arc::new(NoCache::default()),
+                None,
             )
             .unwrap();
 
@@ -348,8 +380,10 @@ mod tests {
                         .unwrap(),
                     [0, 1, 2],
                 ),
+                TestSchema {}.arrow_schema().clone(),
                 manager.base_fs().clone(),
                 Arc::new(NoCache::default()),
+                None,
             )
             .unwrap();
 
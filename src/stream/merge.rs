@@ -22,6 +22,8 @@ pin_project! {
         buf: Option<Entry<'merge, R>>,
         ts: Timestamp,
         limit: Option<usize>,
+        versions: usize,
+        buf_versions: usize,
     }
 }
 
@@ -47,6 +49,8 @@ where
             buf: None,
             ts,
             limit: None,
+            versions: 1,
+            buf_versions: 0,
         };
         merge_stream.next().await;
 
@@ -60,6 +64,14 @@ where
             ..self
         }
     }
+
+    /// keep the `versions` newest versions of each key instead of only the latest one
+    pub(crate) fn versions_per_key(self, versions: usize) -> Self {
+        Self {
+            versions: versions.max(1),
+            ..self
+        }
+    }
 }
 
 impl<'merge, R> Stream for MergeStream<'merge, R>
@@ -88,10 +100,14 @@ where
             if peeked.entry.key().ts > *ts {
                 continue;
             }
-            if let Some(buf) = this.buf {
-                if buf.key().value == peeked.entry.key().value {
-                    continue;
+            match this.buf {
+                Some(buf) if buf.key().value == peeked.entry.key().value => {
+                    if *this.buf_versions >= *this.versions {
+                        continue;
+                    }
+                    *this.buf_versions += 1;
                 }
+                _ => *this.buf_versions = 1,
             }
             if let Some(limit) = this.limit.as_ref() {
                 this.limit.replace(*limit - 1);
@@ -440,4 +456,50 @@ mod tests {
             assert!(merge.next().await.is_none());
         }
     }
+
+    #[tokio::test]
+    async fn merge_mutable_versions_per_key() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let fs = Arc::new(TokioFs) as Arc<dyn DynFs>;
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &StringSchema,
+        );
+
+        fs.create_dir_all(&option.wal_dir_path()).await.unwrap();
+
+        let trigger = TriggerFactory::create(option.trigger_type);
+
+        let m1 =
+            MutableMemTable::<String>::new(&option, trigger, fs.clone(), Arc::new(StringSchema))
+                .await
+                .unwrap();
+        for ts in 0_u32..3 {
+            m1.insert(LogType::Full, "1".into(), ts.into())
+                .await
+                .unwrap();
+        }
+        m1.insert(LogType::Full, "2".into(), 0_u32.into())
+            .await
+            .unwrap();
+
+        let lower = "1".to_string();
+        let upper = "2".to_string();
+        let bound = (Bound::Included(&lower), Bound::Included(&upper));
+        let mut merge =
+            MergeStream::<String>::from_vec(vec![m1.scan(bound, 3.into()).into()], 3.into())
+                .await
+                .unwrap()
+                .versions_per_key(2);
+
+        for (key, ts) in [("1", 2_u32), ("1", 1), ("2", 0)] {
+            if let Some(Ok(Entry::Mutable(entry))) = merge.next().await {
+                assert_eq!(entry.key().value, key);
+                assert_eq!(entry.key().ts, ts.into());
+            } else {
+                unreachable!()
+            }
+        }
+        assert!(merge.next().await.is_none());
+    }
 }
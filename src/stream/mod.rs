@@ -19,11 +19,13 @@ use pin_project_lite::pin_project;
 use record_batch::RecordBatchEntry;
 
 use crate::{
+    fs::FileId,
     inmem::{immutable::ImmutableScan, mutable::MutableScan},
+    magic::USER_COLUMN_OFFSET,
     ondisk::scan::SsTableScan,
     record::{Key, Record, RecordRef, Schema},
     stream::{level::LevelStream, mem_projection::MemProjectionStream},
-    timestamp::Ts,
+    timestamp::{Timestamp, Ts},
     transaction::TransactionScan,
 };
 
@@ -77,6 +79,57 @@ where
             }),
         }
     }
+
+    /// whether the field at `index` of the record is null, the first field is `0`. Entries read
+    /// from arrow arrays use the null buffer of the column instead of decoding the value. Fields
+    /// that were not read and all fields of a removed record are null.
+    pub fn is_null(&self, index: usize) -> bool {
+        match self {
+            Entry::RecordBatch(entry) => entry.is_null(index),
+            Entry::Projection((entry, projection_mask)) => {
+                !projection_mask.leaf_included(index + USER_COLUMN_OFFSET) || entry.is_null(index)
+            }
+            Entry::Transaction(_) | Entry::Mutable(_) => {
+                self.value().is_none_or(|record| record.is_null(index))
+            }
+        }
+    }
+
+    /// value of the `_ts` column of the entry
+    pub fn ts(&self) -> Timestamp {
+        self.key().ts()
+    }
+
+    /// value of the `_level` virtual column: the level of the SSTable the entry was read from,
+    /// `None` if it was read from memory
+    pub fn level(&self) -> Option<usize> {
+        match self {
+            Entry::RecordBatch(entry) => entry.level(),
+            Entry::Projection((entry, _)) => entry.level(),
+            Entry::Transaction(_) | Entry::Mutable(_) => None,
+        }
+    }
+
+    /// value of the `_file_id` virtual column: the id of the SSTable the entry was read from,
+    /// `None` if it was read from memory
+    pub fn file_id(&self) -> Option<FileId> {
+        match self {
+            Entry::RecordBatch(entry) => entry.file_id(),
+            Entry::Projection((entry, _)) => entry.file_id(),
+            Entry::Transaction(_) | Entry::Mutable(_) => None,
+        }
+    }
+}
+
+/// reverse the key order of `entries` sorted by key, keeping the order of the versions of each key
+pub(crate) fn reverse_key_order<R>(entries: &mut [Entry<'_, R>])
+where
+    R: Record,
+{
+    entries.reverse();
+    for versions in entries.chunk_by_mut(|a, b| a.key().value == b.key().value) {
+        versions.reverse();
+    }
 }
 
 impl<R> fmt::Debug for Entry<'_, R>
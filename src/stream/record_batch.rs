@@ -5,10 +5,15 @@ use std::{
     sync::Arc,
 };
 
-use arrow::{array::RecordBatch, datatypes::Schema};
+use arrow::{
+    array::{Array, AsArray, RecordBatch},
+    datatypes::Schema,
+};
 use parquet::arrow::ProjectionMask;
 
 use crate::{
+    fs::FileId,
+    magic::USER_COLUMN_OFFSET,
     record::{option::OptionRecordRef, Key, Record, RecordRef, Schema as RecordSchema},
     timestamp::Ts,
 };
@@ -17,8 +22,12 @@ pub struct RecordBatchEntry<R>
 where
     R: Record,
 {
-    _record_batch: RecordBatch,
+    /// keeps the batch `record_ref` points into alive, it is shared by all rows of the batch
+    record_batch: Arc<RecordBatch>,
+    offset: usize,
+    full_schema: Arc<Schema>,
     record_ref: OptionRecordRef<'static, R::Ref<'static>>,
+    location: Option<(usize, FileId)>,
 }
 
 impl<R> RecordBatchEntry<R>
@@ -26,15 +35,24 @@ where
     R: Record,
 {
     pub(crate) fn new(
-        _record_batch: RecordBatch,
+        record_batch: Arc<RecordBatch>,
+        offset: usize,
+        full_schema: Arc<Schema>,
         record_ref: OptionRecordRef<'static, R::Ref<'static>>,
     ) -> Self {
         Self {
-            _record_batch,
+            record_batch,
+            offset,
+            full_schema,
             record_ref,
+            location: None,
         }
     }
 
+    pub(crate) fn located(self, location: Option<(usize, FileId)>) -> Self {
+        Self { location, ..self }
+    }
+
     pub(crate) fn internal_key(&self) -> Ts<<<R::Schema as RecordSchema>::Key as Key>::Ref<'_>> {
         self.record_ref.key()
     }
@@ -47,6 +65,30 @@ where
         // Safety: shorter lifetime of the key must be safe
         unsafe { transmute(self.record_ref.get()) }
     }
+
+    /// whether the field at `index` of the record is null, the first field is `0`. Read from the
+    /// null buffer of the arrow column without decoding the value. Fields that were not read and
+    /// all fields of a removed record are null.
+    pub fn is_null(&self, index: usize) -> bool {
+        // the `_null` column marks removed records
+        if self.record_batch.column(0).as_boolean().value(self.offset) {
+            return true;
+        }
+        let name = self.full_schema.field(index + USER_COLUMN_OFFSET).name();
+        self.record_batch
+            .column_by_name(name)
+            .is_none_or(|column| column.is_null(self.offset))
+    }
+
+    /// level of the SSTable this entry was read from, `None` if it was read from memory
+    pub fn level(&self) -> Option<usize> {
+        self.location.map(|(level, _)| level)
+    }
+
+    /// id of the SSTable this entry was read from, `None` if it was read from memory
+    pub fn file_id(&self) -> Option<FileId> {
+        self.location.map(|(_, gen)| gen)
+    }
 }
 
 impl<R> Debug for RecordBatchEntry<R>
@@ -60,7 +102,7 @@ where
 
 #[derive(Debug)]
 pub struct RecordBatchIterator<R> {
-    record_batch: RecordBatch,
+    record_batch: Arc<RecordBatch>,
     offset: usize,
     projection_mask: ProjectionMask,
     full_schema: Arc<Schema>,
@@ -77,7 +119,7 @@ where
         full_schema: Arc<Schema>,
     ) -> Self {
         Self {
-            record_batch,
+            record_batch: Arc::new(record_batch),
             offset: 0,
             projection_mask,
             full_schema,
@@ -104,12 +146,18 @@ where
             &self.projection_mask,
             &self.full_schema,
         );
-        let entry = RecordBatchEntry::new(record_batch, unsafe {
-            // Safety: self-referring lifetime is safe
-            transmute::<OptionRecordRef<'_, R::Ref<'_>>, OptionRecordRef<'static, R::Ref<'static>>>(
-                record,
-            )
-        });
+        let entry = RecordBatchEntry::new(
+            record_batch,
+            self.offset,
+            self.full_schema.clone(),
+            unsafe {
+                // Safety: self-referring lifetime is safe
+                transmute::<
+                    OptionRecordRef<'_, R::Ref<'_>>,
+                    OptionRecordRef<'static, R::Ref<'static>>,
+                >(record)
+            },
+        );
         self.offset += 1;
         Some(entry)
     }
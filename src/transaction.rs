@@ -16,7 +16,7 @@ use parquet::{
 use thiserror::Error;
 
 use crate::{
-    compaction::CompactTask,
+    compaction::{self, CompactTask},
     record::{Key, KeyRef, RecordRef, Schema as RecordSchema},
     snapshot::Snapshot,
     stream::{self, mem_projection::MemProjectionStream},
@@ -81,6 +81,7 @@ where
         key: &'get <R::Schema as RecordSchema>::Key,
         projection: Projection<'get>,
     ) -> Result<Option<TransactionEntry<'get, R>>, DbError<R>> {
+        self.snapshot.schema().record_schema.validate_key(key)?;
         Ok(match self.local.get(key) {
             Some(v) => v.as_ref().map(|v| {
                 let mut record_ref = v.as_record_ref();
@@ -100,12 +101,22 @@ where
                     let mut fixed_projection = vec![0, 1, primary_key_index];
                     fixed_projection.append(&mut projection);
                     fixed_projection.dedup();
+                    self.snapshot
+                        .schema()
+                        .restrict_projection(&mut fixed_projection);
 
                     let mask = ProjectionMask::roots(
                         &ArrowSchemaConverter::new().convert(schema).unwrap(),
                         fixed_projection.clone(),
                     );
                     record_ref.projection(&mask);
+                } else if let Some(projection) = &self.snapshot.schema().projection {
+                    let schema = self.snapshot.schema().record_schema.arrow_schema();
+                    let mask = ProjectionMask::roots(
+                        &ArrowSchemaConverter::new().convert(schema).unwrap(),
+                        projection.clone(),
+                    );
+                    record_ref.projection(&mask);
                 }
                 TransactionEntry::Local(record_ref)
             }),
@@ -160,12 +171,19 @@ where
         )
     }
 
-    /// insert a sequence of data as a single batch on this transaction
+    /// insert a record on this transaction
+    ///
+    /// Operations on the same key are last-operation-wins: only the latest [`Transaction::insert`]
+    /// or [`Transaction::remove`] of a key is kept and committed, regardless of the order the
+    /// keys are committed in.
     pub fn insert(&mut self, value: R) {
         self.entry(value.key().to_key(), Some(value))
     }
 
     /// delete the record with the primary key as the `key` on this transaction
+    ///
+    /// See [`Transaction::insert`] for the semantics of multiple operations on the same key. A
+    /// `key` without the primary key datatype makes [`Transaction::commit`] fail.
     pub fn remove(&mut self, key: <R::Schema as RecordSchema>::Key) {
         self.entry(key, None)
     }
@@ -186,8 +204,22 @@ where
     /// This function will return an error if the mutation in the transaction conflict with
     /// other committed transaction
     pub async fn commit(mut self) -> Result<(), CommitError<R>> {
+        let record_schema = &self.snapshot.schema().record_schema;
+        for (key, record) in self.local.iter() {
+            match record {
+                Some(record) => record_schema
+                    .validate_record(record)
+                    .map_err(DbError::from)?,
+                None => record_schema.validate_key(key).map_err(DbError::from)?,
+            }
+        }
+
         let mut _key_guards = Vec::new();
 
+        // `local` is sorted by key, so every transaction locks its keys in ascending key order
+        // whatever the order they were written in. Two transactions waiting on each other's
+        // locks would each need to hold a larger key than the one it waits for, so commits can
+        // not deadlock.
         for (key, _) in self.local.iter() {
             // SAFETY: Error is Never
             _key_guards.push(
@@ -232,11 +264,10 @@ where
             }
         };
         if is_excess {
-            let _ = self
-                .snapshot
-                .schema()
-                .compaction_tx
-                .try_send(CompactTask::Freeze);
+            let (compaction_tx, mode) = self.snapshot.schema().compaction_trigger();
+            drop(_key_guards);
+            drop(self.snapshot);
+            compaction::freeze(&compaction_tx, mode).await;
         }
         Ok(())
     }
@@ -277,6 +308,25 @@ where
             }
         }
     }
+
+    /// whether the field at `index` of the record is null, the first field is `0`. See
+    /// [`Entry::is_null`](stream::Entry::is_null).
+    pub fn is_null(&self, index: usize) -> bool {
+        match self {
+            TransactionEntry::Stream(entry) => entry.is_null(index),
+            TransactionEntry::Local(value) => value.is_null(index),
+        }
+    }
+
+    /// the timestamp the record was committed at, e.g. as returned by
+    /// [`DB::insert`](crate::DB::insert). `None` for a write of the transaction itself, which is
+    /// not committed yet.
+    pub fn ts(&self) -> Option<Timestamp> {
+        match self {
+            TransactionEntry::Stream(entry) => Some(entry.ts()),
+            TransactionEntry::Local(_) => None,
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -300,7 +350,7 @@ where
 
 #[cfg(all(test, feature = "tokio"))]
 mod tests {
-    use std::{collections::Bound, sync::Arc};
+    use std::{collections::Bound, sync::Arc, time::Duration};
 
     use fusio::path::Path;
     use fusio_dispatch::FsOptions;
@@ -497,6 +547,63 @@ mod tests {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn transaction_last_operation_wins() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+
+        let db = DB::<Test, TokioExecutor>::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        let reinserted = 0.to_string();
+        let removed = 1.to_string();
+        {
+            let mut txn = db.transaction().await;
+            txn.insert(Test {
+                vstring: reinserted.clone(),
+                vu32: 0,
+                vbool: Some(true),
+            });
+            txn.remove(reinserted.clone());
+            txn.insert(Test {
+                vstring: reinserted.clone(),
+                vu32: 2,
+                vbool: Some(false),
+            });
+
+            txn.insert(Test {
+                vstring: removed.clone(),
+                vu32: 1,
+                vbool: Some(true),
+            });
+            txn.remove(removed.clone());
+
+            let entry = txn
+                .get(&reinserted, Projection::All)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(entry.get().vu32, Some(2));
+            assert!(txn.get(&removed, Projection::All).await.unwrap().is_none());
+
+            txn.commit().await.unwrap();
+        }
+
+        let txn = db.transaction().await;
+        let entry = txn
+            .get(&reinserted, Projection::All)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.get().vu32, Some(2));
+        assert_eq!(entry.get().vbool, Some(false));
+        assert!(txn.get(&removed, Projection::All).await.unwrap().is_none());
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn write_conflicts() {
         let temp_dir = TempDir::new().unwrap();
@@ -533,6 +640,43 @@ mod tests {
         unreachable!();
     }
 
+    #[tokio::test(flavor = "multi_thread")]
+    async fn commit_lock_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &StringSchema,
+        );
+
+        let db = DB::<String, TokioExecutor>::new(option, TokioExecutor::current(), StringSchema)
+            .await
+            .unwrap();
+
+        for _ in 0..32 {
+            let mut txn_0 = db.transaction().await;
+            let mut txn_1 = db.transaction().await;
+
+            // the keys are written in opposite orders
+            txn_0.insert("a".to_string());
+            txn_0.insert("b".to_string());
+            txn_1.insert("b".to_string());
+            txn_1.insert("a".to_string());
+
+            let (result_0, result_1) = tokio::time::timeout(Duration::from_secs(10), async {
+                tokio::join!(txn_0.commit(), txn_1.commit())
+            })
+            .await
+            .expect("commits deadlocked");
+
+            // both transactions read before either committed, so exactly one of them conflicts
+            match (result_0, result_1) {
+                (Ok(()), Err(CommitError::WriteConflict(_)))
+                | (Err(CommitError::WriteConflict(_)), Ok(())) => {}
+                _ => unreachable!(),
+            }
+        }
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn transaction_projection() {
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,723 @@
+use std::{
+    collections::BTreeMap,
+    ops::Bound,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use fusio::{path::Path, DynFs};
+use futures_util::StreamExt;
+use parquet::arrow::ProjectionMask;
+use parquet_lru::NoCache;
+use tracing::error;
+
+use crate::{
+    fs::{manager::StoreManager, FileId, FileType},
+    ondisk::sstable::SsTable,
+    record::{KeyRef, Record, Schema},
+    scope::Scope,
+    timestamp::Timestamp,
+    version::{edit::VersionEdit, set::VersionSet, Version, VersionError, MAX_LEVEL},
+    DbOption, SstableNaming,
+};
+
+/// result of [`DB::verify`](crate::DB::verify)
+#[derive(Debug)]
+pub struct VerifyReport<K> {
+    /// number of SSTables referenced by the manifest
+    pub checked_tables: usize,
+    pub issues: Vec<VerifyIssue<K>>,
+}
+
+impl<K> VerifyReport<K> {
+    /// returns true if no issue was found
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VerifyIssue<K> {
+    /// the key range recorded in the manifest differs from the keys stored in the SSTable.
+    /// `actual` is `None` when the SSTable holds no rows
+    RangeMismatch {
+        level: usize,
+        gen: FileId,
+        expected: (K, K),
+        actual: Option<(K, K)>,
+    },
+    /// two adjacent SSTables of a sorted level (level 1 and above) overlap or are out of order
+    Overlap {
+        level: usize,
+        gens: (FileId, FileId),
+    },
+    /// the SSTable could not be opened or read
+    Unreadable {
+        level: usize,
+        gen: FileId,
+        error: String,
+    },
+    /// the SSTable could not be opened by a scan and is skipped by scans from now on, keys in
+    /// `range` may be missing from their results. See
+    /// [`DbOption::quarantine_unreadable_sstables`](crate::DbOption::quarantine_unreadable_sstables)
+    Quarantined {
+        level: usize,
+        gen: FileId,
+        range: (K, K),
+        error: String,
+    },
+}
+
+/// SSTables skipped by scans because they could not be opened
+pub(crate) struct Quarantine<K> {
+    tables: Mutex<BTreeMap<FileId, VerifyIssue<K>>>,
+}
+
+impl<K> Default for Quarantine<K> {
+    fn default() -> Self {
+        Self {
+            tables: Mutex::new(BTreeMap::new()),
+        }
+    }
+}
+
+impl<K> Quarantine<K>
+where
+    K: Clone,
+{
+    /// quarantines the SSTable of `scope` and returns its [`VerifyIssue::Quarantined`]
+    pub(crate) fn insert(
+        &self,
+        level: usize,
+        scope: &Scope<K>,
+        error: impl ToString,
+    ) -> VerifyIssue<K> {
+        let error = error.to_string();
+        error!(
+            "[Quarantine]: skipping unreadable SSTable {} of level {}: {}",
+            scope.gen, level, error
+        );
+        let issue = VerifyIssue::Quarantined {
+            level,
+            gen: scope.gen,
+            range: (scope.min.clone(), scope.max.clone()),
+            error,
+        };
+        self.tables.lock().unwrap().insert(scope.gen, issue.clone());
+        issue
+    }
+
+    pub(crate) fn get(&self, gen: &FileId) -> Option<VerifyIssue<K>> {
+        self.tables.lock().unwrap().get(gen).cloned()
+    }
+
+    pub(crate) fn issues(&self) -> Vec<VerifyIssue<K>> {
+        self.tables.lock().unwrap().values().cloned().collect()
+    }
+}
+
+/// the SSTables a scan skipped because they are quarantined, see
+/// [`Scan::gaps`](crate::Scan::gaps). Filled while the scan is consumed.
+#[derive(Debug)]
+pub struct ScanGaps<K> {
+    gaps: Arc<Mutex<Vec<VerifyIssue<K>>>>,
+}
+
+impl<K> Clone for ScanGaps<K> {
+    fn clone(&self) -> Self {
+        Self {
+            gaps: self.gaps.clone(),
+        }
+    }
+}
+
+impl<K> Default for ScanGaps<K> {
+    fn default() -> Self {
+        Self {
+            gaps: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+}
+
+impl<K> ScanGaps<K>
+where
+    K: Clone,
+{
+    /// returns true if the scan skipped no SSTable so far, i.e. its results are complete
+    pub fn is_empty(&self) -> bool {
+        self.gaps.lock().unwrap().is_empty()
+    }
+
+    /// the skipped SSTables as [`VerifyIssue::Quarantined`], keys in their range may be missing
+    /// from the results of the scan
+    pub fn gaps(&self) -> Vec<VerifyIssue<K>> {
+        self.gaps.lock().unwrap().clone()
+    }
+
+    pub(crate) fn push(&self, issue: VerifyIssue<K>) {
+        self.gaps.lock().unwrap().push(issue);
+    }
+}
+
+impl<R> Version<R>
+where
+    R: Record,
+{
+    pub(crate) async fn verify(
+        &self,
+        manager: &StoreManager,
+    ) -> VerifyReport<<R::Schema as Schema>::Key> {
+        let mut report = VerifyReport {
+            checked_tables: 0,
+            issues: Vec::new(),
+        };
+
+        for (level, scopes) in self.level_slice.iter().enumerate() {
+            let fs = self
+                .option()
+                .level_fs_path(level)
+                .map(|path| manager.get_fs(path))
+                .unwrap_or(manager.base_fs());
+
+            for scope in scopes {
+                report.checked_tables += 1;
+
+                match self.table_range(fs, level, scope.gen).await {
+                    Ok(Some((min, max))) if min == scope.min && max == scope.max => {}
+                    Ok(actual) => report.issues.push(VerifyIssue::RangeMismatch {
+                        level,
+                        gen: scope.gen,
+                        expected: (scope.min.clone(), scope.max.clone()),
+                        actual,
+                    }),
+                    Err(err) => report.issues.push(VerifyIssue::Unreadable {
+                        level,
+                        gen: scope.gen,
+                        error: err.to_string(),
+                    }),
+                }
+            }
+            if level == 0 {
+                continue;
+            }
+            for pair in scopes.windows(2) {
+                if pair[0].max >= pair[1].min {
+                    report.issues.push(VerifyIssue::Overlap {
+                        level,
+                        gens: (pair[0].gen, pair[1].gen),
+                    });
+                }
+            }
+        }
+
+        report
+    }
+
+    async fn table_range(
+        &self,
+        fs: &Arc<dyn DynFs>,
+        level: usize,
+        gen: FileId,
+    ) -> Result<Option<(<R::Schema as Schema>::Key, <R::Schema as Schema>::Key)>, VersionError<R>>
+    {
+        Ok(table_summary::<R>(self.option(), fs, level, gen)
+            .await?
+            .map(|(min, max, _)| (min, max)))
+    }
+}
+
+/// scan every version of every key in the table and return the smallest and largest key and the
+/// latest timestamp
+async fn table_summary<R>(
+    option: &DbOption,
+    fs: &Arc<dyn DynFs>,
+    level: usize,
+    gen: FileId,
+) -> Result<
+    Option<(
+        <R::Schema as Schema>::Key,
+        <R::Schema as Schema>::Key,
+        Timestamp,
+    )>,
+    VersionError<R>,
+>
+where
+    R: Record,
+{
+    let file = fs
+        .open_options(
+            &option.table_path(gen, level),
+            FileType::Parquet.open_options(true),
+        )
+        .await?;
+    // bypass the parquet lru, a diagnostic pass should not evict hot readers
+    let mut scan = SsTable::<R>::open(Arc::new(NoCache::default()), gen, file)
+        .await?
+        .scan(
+            (Bound::Unbounded, Bound::Unbounded),
+            Timestamp::from(u32::MAX),
+            None,
+            ProjectionMask::all(),
+        )
+        .await?;
+
+    let mut summary = None;
+    while let Some(entry) = scan.next().await.transpose()? {
+        let ts = entry.internal_key().ts();
+        let key = entry.key().to_key();
+        summary = Some(match summary {
+            None => (key.clone(), key, ts),
+            Some((min, max, latest)) => (
+                Ord::min(min, key.clone()),
+                Ord::max(max, key),
+                Ord::max(latest, ts),
+            ),
+        });
+    }
+    Ok(summary)
+}
+
+/// result of [`DB::rebuild_manifest`](crate::DB::rebuild_manifest)
+#[derive(Debug, Default)]
+pub struct RebuildReport {
+    /// SSTables recorded in the new manifest, by level
+    pub placed: Vec<(usize, FileId)>,
+    /// SSTables left out of the new manifest, they are kept on disk
+    pub unplaced: Vec<UnplacedTable>,
+}
+
+impl RebuildReport {
+    /// returns true if every SSTable was placed
+    pub fn is_complete(&self) -> bool {
+        self.unplaced.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnplacedTable {
+    pub path: Path,
+    pub reason: String,
+}
+
+/// the gen of an SSTable file named by `naming`, and its level if the name records it
+fn parse_table_name(path: &Path, naming: SstableNaming) -> Option<(FileId, Option<usize>)> {
+    let stem = path
+        .filename()?
+        .strip_suffix(&format!(".{}", FileType::Parquet))?;
+    match naming {
+        SstableNaming::Ulid => Some((FileId::from_str(stem).ok()?, None)),
+        SstableNaming::Leveled => {
+            let (level, gen) = stem.strip_prefix('L')?.split_once('-')?;
+            Some((FileId::from_str(gen).ok()?, Some(level.parse().ok()?)))
+        }
+    }
+}
+
+/// write a new manifest referencing the SSTables found in the level directories of `option`.
+///
+/// Tables are placed in the lowest level stored in their directory, or the level in their name
+/// with [`SstableNaming::Leveled`], oldest first. A table is left out if it can't be read, has no
+/// rows or overlaps a table already placed in a sorted level.
+pub(crate) async fn rebuild_manifest<R>(
+    option: Arc<DbOption>,
+    manager: Arc<StoreManager>,
+) -> Result<RebuildReport, VersionError<R>>
+where
+    R: Record,
+{
+    let mut report = RebuildReport::default();
+    let level_dir = |level: usize| option.level_fs_path(level).unwrap_or(&option.base_path);
+
+    let mut found = Vec::new();
+    let mut dirs: Vec<&Path> = Vec::new();
+    for level in 0..MAX_LEVEL {
+        let dir = level_dir(level);
+        if dirs.contains(&dir) {
+            continue;
+        }
+        dirs.push(dir);
+        let fs = manager.get_fs(dir);
+        let mut files = fs.list(dir).await?;
+        while let Some(meta) = files.next().await.transpose()? {
+            let Some((gen, named_level)) = parse_table_name(&meta.path, option.sstable_naming)
+            else {
+                continue;
+            };
+            let levels = (0..MAX_LEVEL)
+                .filter(|level| {
+                    level_dir(*level) == dir && named_level.is_none_or(|named| named == *level)
+                })
+                .collect::<Vec<_>>();
+            if levels.is_empty() {
+                report.unplaced.push(UnplacedTable {
+                    path: meta.path,
+                    reason: format!("its level is not stored in {}", dir),
+                });
+                continue;
+            }
+            match table_summary::<R>(&option, fs, levels[0], gen).await {
+                Ok(Some((min, max, ts))) => found.push((gen, levels, meta.path, min, max, ts)),
+                Ok(None) => report.unplaced.push(UnplacedTable {
+                    path: meta.path,
+                    reason: "it has no rows".to_string(),
+                }),
+                Err(err) => report.unplaced.push(UnplacedTable {
+                    path: meta.path,
+                    reason: err.to_string(),
+                }),
+            }
+        }
+    }
+    // gens grow over time, so level 0 keeps the order the tables were flushed in
+    found.sort_by_key(|(gen, ..)| *gen);
+
+    let mut level_slice: [Vec<Scope<<R::Schema as Schema>::Key>>; MAX_LEVEL] =
+        [const { Vec::new() }; MAX_LEVEL];
+    let mut latest = Timestamp::from(0);
+    for (gen, levels, path, min, max, ts) in found {
+        let level = levels.into_iter().find(|level| {
+            *level == 0
+                || level_slice[*level]
+                    .iter()
+                    .all(|scope| scope.max < min || max < scope.min)
+        });
+        let Some(level) = level else {
+            report.unplaced.push(UnplacedTable {
+                path,
+                reason: "it overlaps the tables of its sorted level".to_string(),
+            });
+            continue;
+        };
+        latest = latest.max(ts);
+        report.placed.push((level, gen));
+        level_slice[level].push(Scope {
+            min,
+            max,
+            gen,
+            wal_ids: None,
+        });
+    }
+
+    let version_dir = option.version_log_dir_path();
+    for fs in [manager.base_fs(), manager.local_fs()] {
+        fs.create_dir_all(&version_dir).await?;
+        let mut logs = fs.list(&version_dir).await?;
+        let mut paths = Vec::new();
+        while let Some(meta) = logs.next().await.transpose()? {
+            paths.push(meta.path);
+        }
+        for path in paths {
+            fs.remove(&path).await?;
+        }
+    }
+    let (clean_sender, _clean_receiver) = flume::unbounded();
+    let version_set = VersionSet::<R>::new(clean_sender, option.clone(), manager).await?;
+    let edits = level_slice
+        .into_iter()
+        .enumerate()
+        .flat_map(|(level, scopes)| {
+            scopes.into_iter().map(move |scope| VersionEdit::Add {
+                level: level as u8,
+                scope,
+            })
+        })
+        .chain([
+            VersionEdit::LatestTimeStamp { ts: latest },
+            // the wal can't tell which of its changes made it into the tables
+            VersionEdit::FlushedTimeStamp { ts: latest },
+        ])
+        .collect();
+    version_set.apply_edits(edits, None, false).await?;
+    version_set.rewrite().await?;
+
+    Ok(report)
+}
+
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::ops::Bound;
+
+    use fusio::path::Path;
+    use futures_util::StreamExt;
+    use tempfile::TempDir;
+
+    use super::VerifyIssue;
+    use crate::{
+        executor::tokio::TokioExecutor, fs::generate_file_id, inmem::immutable::tests::TestSchema,
+        scope::Scope, tests::Test, trigger::TriggerType, version::edit::VersionEdit, DbOption, DB,
+    };
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn verify_healthy_and_corrupted() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        );
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for i in 0..10 {
+            db.insert(Test {
+                vstring: i.to_string(),
+                vu32: i,
+                vbool: Some(true),
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+
+        let report = db.verify().await;
+        assert!(report.is_ok(), "{:?}", report);
+        assert!(report.checked_tables > 0);
+
+        let healthy = db.ctx.version_set.current().await.level_slice[0][0].clone();
+        let missing_gen = generate_file_id();
+        db.ctx
+            .version_set
+            .apply_edits(
+                vec![
+                    VersionEdit::Remove {
+                        level: 0,
+                        gen: healthy.gen,
+                    },
+                    VersionEdit::Add {
+                        level: 0,
+                        scope: Scope {
+                            min: "0".to_string(),
+                            max: "99".to_string(),
+                            gen: healthy.gen,
+                            wal_ids: None,
+                        },
+                    },
+                    VersionEdit::Add {
+                        level: 0,
+                        scope: Scope {
+                            min: "0".to_string(),
+                            max: "9".to_string(),
+                            gen: missing_gen,
+                            wal_ids: None,
+                        },
+                    },
+                ],
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let report = db.verify().await;
+        assert!(!report.is_ok());
+        assert!(report.issues.contains(&VerifyIssue::RangeMismatch {
+            level: 0,
+            gen: healthy.gen,
+            expected: ("0".to_string(), "99".to_string()),
+            actual: Some((healthy.min.clone(), healthy.max.clone())),
+        }));
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            VerifyIssue::Unreadable { level: 0, gen, .. } if *gen == missing_gen
+        )));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn quarantine_unreadable_table() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .quarantine_unreadable_sstables(true);
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+
+        for i in 0..10 {
+            db.insert(Test {
+                vstring: i.to_string(),
+                vu32: i,
+                vbool: Some(true),
+            })
+            .await
+            .unwrap();
+        }
+        db.flush().await.unwrap();
+
+        let missing_gen = generate_file_id();
+        // tables of sorted levels are opened lazily, while the scan is consumed
+        let missing_level_gen = generate_file_id();
+        db.ctx
+            .version_set
+            .apply_edits(
+                vec![
+                    VersionEdit::Add {
+                        level: 0,
+                        scope: Scope {
+                            min: "5".to_string(),
+                            max: "9".to_string(),
+                            gen: missing_gen,
+                            wal_ids: None,
+                        },
+                    },
+                    VersionEdit::Add {
+                        level: 1,
+                        scope: Scope {
+                            min: "a".to_string(),
+                            max: "b".to_string(),
+                            gen: missing_level_gen,
+                            wal_ids: None,
+                        },
+                    },
+                ],
+                None,
+                false,
+            )
+            .await
+            .unwrap();
+
+        let txn = db.transaction().await;
+        let scan_keys = |upper: Option<&'static str>| {
+            let txn = &txn;
+            async move {
+                let upper = upper.map(str::to_string);
+                let upper = match &upper {
+                    Some(upper) => Bound::Excluded(upper),
+                    None => Bound::Unbounded,
+                };
+                let scan = txn.scan((Bound::Unbounded, upper));
+                let gaps = scan.gaps();
+                let mut scan = scan.take().await.unwrap();
+                let mut keys = Vec::new();
+                while let Some(entry) = scan.next().await.transpose().unwrap() {
+                    keys.push(entry.key().value().to_string());
+                }
+                (keys, gaps.gaps())
+            }
+        };
+        let quarantined = |gaps: Vec<VerifyIssue<String>>| {
+            gaps.into_iter()
+                .map(|issue| match issue {
+                    VerifyIssue::Quarantined { level, gen, .. } => (level, gen),
+                    issue => panic!("unexpected gap {:?}", issue),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // the scan that quarantines the tables and the later ones report the gaps
+        for _ in 0..2 {
+            let (keys, gaps) = scan_keys(None).await;
+            assert_eq!(keys, (0..10).map(|i| i.to_string()).collect::<Vec<_>>());
+            assert_eq!(
+                quarantined(gaps),
+                vec![(0, missing_gen), (1, missing_level_gen)]
+            );
+        }
+        // ranges no quarantined table covers are complete
+        let (keys, gaps) = scan_keys(Some("5")).await;
+        assert_eq!(keys, (0..5).map(|i| i.to_string()).collect::<Vec<_>>());
+        assert!(gaps.is_empty());
+
+        let report = db.verify().await;
+        assert!(report.issues.iter().any(|issue| matches!(
+            issue,
+            VerifyIssue::Quarantined { level: 0, gen, range, .. }
+                if *gen == missing_gen && *range == ("5".to_string(), "9".to_string())
+        )));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn rebuild_lost_manifest() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &TestSchema,
+        )
+        .disable_wal(true);
+        option.immutable_chunk_num = 1;
+        option.immutable_chunk_max_num = 1;
+        option.trigger_type = TriggerType::Length(/* max_mutable_len */ 50);
+
+        let db: DB<Test, TokioExecutor> =
+            DB::new(option.clone(), TokioExecutor::current(), TestSchema)
+                .await
+                .unwrap();
+        for round in 0..3_u32 {
+            for i in 0..10 {
+                db.insert(Test {
+                    vstring: i.to_string(),
+                    vu32: round * 100 + i,
+                    vbool: Some(true),
+                })
+                .await
+                .unwrap();
+            }
+            db.flush().await.unwrap();
+        }
+        let tables = db.ctx.version_set.current().await.level_slice[0].len();
+        assert_eq!(tables, 3);
+        drop(db);
+
+        std::fs::remove_dir_all(temp_dir.path().join("version")).unwrap();
+        let corrupted = temp_dir
+            .path()
+            .join(format!("{}.parquet", generate_file_id()));
+        std::fs::write(&corrupted, b"not a parquet file").unwrap();
+
+        let report = DB::<Test, TokioExecutor>::rebuild_manifest(option.clone())
+            .await
+            .unwrap();
+        assert_eq!(report.placed.len(), tables);
+        assert!(report.placed.iter().all(|(level, _)| *level == 0));
+        assert_eq!(report.unplaced.len(), 1);
+        assert!(report.unplaced[0]
+            .path
+            .as_ref()
+            .ends_with(corrupted.file_name().unwrap().to_str().unwrap()));
+
+        let db: DB<Test, TokioExecutor> = DB::new(option, TokioExecutor::current(), TestSchema)
+            .await
+            .unwrap();
+        assert!(db.verify().await.is_ok());
+        let txn = db.transaction().await;
+        let mut values = Vec::new();
+        {
+            let mut scan = txn
+                .scan((Bound::Unbounded, Bound::Unbounded))
+                .take()
+                .await
+                .unwrap();
+            while let Some(entry) = scan.next().await.transpose().unwrap() {
+                values.push(entry.value().unwrap().vu32.unwrap());
+            }
+        }
+        assert_eq!(values, (200..210).collect::<Vec<_>>());
+        drop(txn);
+
+        // the timestamp is restored, so new writes are newer than the rebuilt tables
+        db.insert(Test {
+            vstring: "0".to_string(),
+            vu32: 300,
+            vbool: None,
+        })
+        .await
+        .unwrap();
+        let key = "0".to_string();
+        assert_eq!(
+            db.get(&key, |entry| entry.get().vu32).await.unwrap(),
+            Some(300)
+        );
+    }
+}
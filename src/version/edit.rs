@@ -12,6 +12,8 @@ pub(crate) enum VersionEdit<K> {
     Remove { level: u8, gen: FileId },
     LatestTimeStamp { ts: Timestamp },
     NewLogLength { len: u32 },
+    FormatVersion { version: u32 },
+    FlushedTimeStamp { ts: Timestamp },
 }
 
 impl<K> VersionEdit<K>
@@ -67,6 +69,14 @@ where
                 3u8.encode(writer).await?;
                 len.encode(writer).await?;
             }
+            VersionEdit::FormatVersion { version } => {
+                4u8.encode(writer).await?;
+                version.encode(writer).await?;
+            }
+            VersionEdit::FlushedTimeStamp { ts } => {
+                5u8.encode(writer).await?;
+                ts.encode(writer).await?;
+            }
         }
 
         Ok(())
@@ -80,6 +90,8 @@ where
                 VersionEdit::Remove { .. } => 16,
                 VersionEdit::LatestTimeStamp { ts } => ts.size(),
                 VersionEdit::NewLogLength { .. } => size_of::<u32>(),
+                VersionEdit::FormatVersion { .. } => size_of::<u32>(),
+                VersionEdit::FlushedTimeStamp { ts } => ts.size(),
             }
     }
 }
@@ -118,6 +130,14 @@ where
                 let len = u32::decode(reader).await?;
                 VersionEdit::NewLogLength { len }
             }
+            4 => {
+                let version = u32::decode(reader).await?;
+                VersionEdit::FormatVersion { version }
+            }
+            5 => {
+                let ts = Timestamp::decode(reader).await?;
+                VersionEdit::FlushedTimeStamp { ts }
+            }
             _ => unreachable!(),
         })
     }
@@ -150,6 +170,8 @@ mod tests {
             },
             VersionEdit::LatestTimeStamp { ts: 10.into() },
             VersionEdit::NewLogLength { len: 233 },
+            VersionEdit::FormatVersion { version: 1 },
+            VersionEdit::FlushedTimeStamp { ts: 8.into() },
         ];
 
         let mut buf = Vec::new();
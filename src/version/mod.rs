@@ -10,9 +10,11 @@ use std::{
     },
 };
 
+use arrow::datatypes::Schema as ArrowSchema;
 use flume::{SendError, Sender};
 use fusio::DynFs;
 use fusio_log::{error::LogError, Encode};
+use futures_util::StreamExt;
 use parquet::arrow::ProjectionMask;
 use thiserror::Error;
 use tracing::error;
@@ -25,11 +27,14 @@ use crate::{
     scope::Scope,
     stream::{level::LevelStream, record_batch::RecordBatchEntry, ScanStream},
     timestamp::{Timestamp, TsRef},
+    verify::ScanGaps,
     version::{cleaner::CleanTag, edit::VersionEdit},
     DbOption, ParquetLru,
 };
 
 pub(crate) const MAX_LEVEL: usize = 7;
+/// version of the on-disk format, stamped into the manifest
+pub(crate) const FORMAT_VERSION: u32 = 2;
 
 pub(crate) type VersionRef<R> = Arc<Version<R>>;
 
@@ -50,6 +55,8 @@ where
     option: Arc<DbOption>,
     timestamp: Arc<AtomicU32>,
     log_length: u32,
+    /// the newest timestamp whose changes were flushed out of the wal
+    flushed_ts: Timestamp,
 }
 
 impl<R> Version<R>
@@ -70,12 +77,17 @@ where
             option: option.clone(),
             timestamp,
             log_length: 0,
+            flushed_ts: Timestamp::from(0),
         }
     }
 
     pub(crate) fn option(&self) -> &Arc<DbOption> {
         &self.option
     }
+
+    pub(crate) fn flushed_ts(&self) -> Timestamp {
+        self.flushed_ts
+    }
 }
 
 impl<R> TransactionTs for Version<R>
@@ -109,6 +121,7 @@ where
             option: self.option.clone(),
             timestamp: self.timestamp.clone(),
             log_length: self.log_length,
+            flushed_ts: self.flushed_ts,
         }
     }
 }
@@ -122,6 +135,7 @@ where
         manager: &StoreManager,
         key: &TsRef<<R::Schema as Schema>::Key>,
         projection_mask: ProjectionMask,
+        arrow_schema: &Arc<ArrowSchema>,
         parquet_lru: ParquetLru,
     ) -> Result<Option<RecordBatchEntry<R>>, VersionError<R>> {
         let level_0_path = self
@@ -140,6 +154,7 @@ where
                     0,
                     scope.gen,
                     projection_mask.clone(),
+                    arrow_schema,
                     parquet_lru.clone(),
                 )
                 .await?
@@ -168,6 +183,7 @@ where
                     leve,
                     sort_runs[index].gen,
                     projection_mask.clone(),
+                    arrow_schema,
                     parquet_lru.clone(),
                 )
                 .await?
@@ -186,6 +202,7 @@ where
         level: usize,
         gen: FileId,
         projection_mask: ProjectionMask,
+        arrow_schema: &Arc<ArrowSchema>,
         parquet_lru: ParquetLru,
     ) -> Result<Option<RecordBatchEntry<R>>, VersionError<R>> {
         let file = store
@@ -197,6 +214,7 @@ where
             .map_err(VersionError::Fusio)?;
         SsTable::<R>::open(parquet_lru, gen, file)
             .await?
+            .with_schema(arrow_schema.clone())
             .get(key, projection_mask)
             .await
             .map_err(VersionError::Parquet)
@@ -227,30 +245,56 @@ where
         ts: Timestamp,
         limit: Option<usize>,
         projection_mask: ProjectionMask,
+        gaps: &ScanGaps<<R::Schema as Schema>::Key>,
     ) -> Result<(), VersionError<R>> {
         let level_0_path = self
             .option
             .level_fs_path(0)
             .unwrap_or(&self.option.base_path);
         let level_0_fs = ctx.manager.get_fs(level_0_path);
-        for scope in self.level_slice[0].iter() {
+        let quarantine = self.option.quarantine_sstables;
+        // tables quarantined by earlier scans are skipped without being opened
+        let readable = |scope: &Scope<<R::Schema as Schema>::Key>| {
             if !scope.meets_range(range) {
-                continue;
+                return false;
             }
-            let file = level_0_fs
-                .open_options(
-                    &self.option.table_path(scope.gen, 0),
-                    FileType::Parquet.open_options(true),
-                )
-                .await
-                .map_err(VersionError::Fusio)?;
-            let table = SsTable::open(ctx.parquet_lru.clone(), scope.gen, file).await?;
+            match ctx.quarantine.get(&scope.gen).filter(|_| quarantine) {
+                Some(issue) => {
+                    gaps.push(issue);
+                    false
+                }
+                None => true,
+            }
+        };
+        let read_concurrency = self.option.read_concurrency;
+        let projection = &projection_mask;
+        // tables are opened concurrently, `buffered` keeps them in the order of the level
+        let mut scans =
+            futures_util::stream::iter(self.level_slice[0].iter().filter(|scope| readable(scope)))
+                .map(|scope| async move {
+                    let scan = async {
+                        self.open_table(ctx, level_0_fs, 0, scope.gen)
+                            .await?
+                            .scan(range, ts, limit, projection.clone())
+                            .await
+                            .map_err(VersionError::Parquet)
+                    }
+                    .await;
+                    (scope, scan)
+                })
+                .buffered(read_concurrency);
+        while let Some((scope, scan)) = scans.next().await {
+            let scan = match scan {
+                Ok(scan) => scan,
+                Err(err) if quarantine => {
+                    gaps.push(ctx.quarantine.insert(0, scope, err));
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
 
             streams.push(ScanStream::SsTable {
-                inner: table
-                    .scan(range, ts, limit, projection_mask.clone())
-                    .await
-                    .map_err(VersionError::Parquet)?,
+                inner: scan.located(0, scope.gen),
             })
         }
         for (i, scopes) in self.level_slice[1..].iter().enumerate() {
@@ -263,42 +307,72 @@ where
                 .unwrap_or(&self.option.base_path);
             let level_fs = ctx.manager.get_fs(level_path);
 
-            let (mut start, mut end) = (None, None);
-
-            for (idx, scope) in scopes.iter().enumerate() {
-                if scope.meets_range(range) {
-                    if start.is_none() {
-                        start = Some(idx);
+            // contiguous runs of readable scopes meeting the range, quarantined tables split them.
+            // The tables of a run are opened lazily by `LevelStream`, which quarantines the ones
+            // it fails to open
+            let mut runs = Vec::new();
+            let mut start = None;
+            for (idx, readable) in scopes.iter().map(&readable).enumerate() {
+                match start {
+                    None if readable => start = Some(idx),
+                    Some(first) if !readable => {
+                        runs.push((first, idx - 1));
+                        start = None;
                     }
-                    end = Some(idx);
+                    _ => {}
                 }
             }
-            if start.is_none() {
-                continue;
+            if let Some(first) = start {
+                runs.push((first, scopes.len() - 1));
             }
 
-            streams.push(ScanStream::Level {
-                // SAFETY: checked scopes no empty
-                inner: LevelStream::new(
-                    self,
-                    i + 1,
-                    start.unwrap(),
-                    end.unwrap(),
-                    range,
-                    ts,
-                    limit,
-                    projection_mask.clone(),
-                    level_fs.clone(),
-                    ctx.parquet_lru.clone(),
-                )
-                .unwrap(),
-            });
+            for (start, end) in runs {
+                streams.push(ScanStream::Level {
+                    // SAFETY: checked scopes no empty
+                    inner: LevelStream::new(
+                        self,
+                        i + 1,
+                        start,
+                        end,
+                        range,
+                        ts,
+                        limit,
+                        projection_mask.clone(),
+                        ctx.arrow_schema().clone(),
+                        level_fs.clone(),
+                        ctx.parquet_lru.clone(),
+                        quarantine.then(|| (ctx.quarantine.clone(), gaps.clone())),
+                    )
+                    .unwrap(),
+                });
+            }
         }
         Ok(())
     }
 
+    async fn open_table(
+        &self,
+        ctx: &Context<R>,
+        fs: &Arc<dyn DynFs>,
+        level: usize,
+        gen: FileId,
+    ) -> Result<SsTable<R>, VersionError<R>> {
+        let file = fs
+            .open_options(
+                &self.option.table_path(gen, level),
+                FileType::Parquet.open_options(true),
+            )
+            .await
+            .map_err(VersionError::Fusio)?;
+        Ok(SsTable::open(ctx.parquet_lru.clone(), gen, file)
+            .await?
+            .with_schema(ctx.arrow_schema().clone()))
+    }
+
     pub(crate) fn to_edits(&self) -> Vec<VersionEdit<<R::Schema as Schema>::Key>> {
-        let mut edits = Vec::new();
+        let mut edits = vec![VersionEdit::FormatVersion {
+            version: FORMAT_VERSION,
+        }];
 
         for (level, scopes) in self.level_slice.iter().enumerate() {
             for scope in scopes {
@@ -309,6 +383,9 @@ where
             }
         }
         edits.push(VersionEdit::LatestTimeStamp { ts: self.load_ts() });
+        edits.push(VersionEdit::FlushedTimeStamp {
+            ts: self.flushed_ts,
+        });
         edits.push(VersionEdit::NewLogLength { len: 0 });
         edits
     }
@@ -344,4 +421,6 @@ where
     Send(#[from] SendError<CleanTag>),
     #[error("log error: {0}")]
     Logger(#[from] LogError),
+    #[error("unsupported database format version {found}, the current version is {supported}")]
+    UnsupportedFormatVersion { found: u32, supported: u32 },
 }
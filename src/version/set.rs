@@ -13,7 +13,7 @@ use fusio::{fs::FileMeta, DynFs};
 use fusio_log::{Logger, Options};
 use futures_util::StreamExt;
 
-use super::{TransactionTs, MAX_LEVEL};
+use super::{TransactionTs, FORMAT_VERSION, MAX_LEVEL};
 use crate::{
     fs::{generate_file_id, manager::StoreManager, parse_file_id, FileId, FileType},
     record::{Record, Schema},
@@ -97,6 +97,12 @@ impl<R> VersionSet<R>
 where
     R: Record,
 {
+    /// make the timestamp at least `ts`, e.g. for writes replayed with the timestamp they were
+    /// committed at
+    pub(crate) fn advance_ts(&self, ts: Timestamp) {
+        self.timestamp.fetch_max(u32::from(ts), Ordering::Release);
+    }
+
     pub(crate) async fn new(
         clean_sender: Sender<CleanTag>,
         option: Arc<DbOption>,
@@ -157,6 +163,17 @@ where
             }
         };
 
+        let format_version = edits
+            .iter()
+            .rev()
+            .find_map(|edit| match edit {
+                VersionEdit::FormatVersion { version } => Some(*version),
+                _ => None,
+            })
+            // a manifest with edits but no stamp was written before the format was versioned
+            .unwrap_or(if edits.is_empty() { FORMAT_VERSION } else { 0 });
+        let edits = Self::migrate(format_version, edits)?;
+
         let timestamp = Arc::new(AtomicU32::default());
         drop(log_stream);
         let set = VersionSet::<R> {
@@ -168,6 +185,7 @@ where
                     option: option.clone(),
                     timestamp: timestamp.clone(),
                     log_length: 0,
+                    flushed_ts: Timestamp::from(0),
                 }),
                 log_id,
                 deleted_wal: Default::default(),
@@ -178,11 +196,47 @@ where
             option,
             manager,
         };
+        // recovery rewrites the manifest, which stamps it with the current format version
         set.apply_edits(edits, None, true).await?;
 
         Ok(set)
     }
 
+    /// upgrade the recovered manifest `edits` of format version `from` to [`FORMAT_VERSION`],
+    /// one version at a time. Fails with [`VersionError::UnsupportedFormatVersion`] for a version
+    /// newer than [`FORMAT_VERSION`] or one without a migration.
+    fn migrate(
+        from: u32,
+        mut edits: Vec<VersionEdit<<R::Schema as Schema>::Key>>,
+    ) -> Result<Vec<VersionEdit<<R::Schema as Schema>::Key>>, VersionError<R>> {
+        let unsupported = VersionError::UnsupportedFormatVersion {
+            found: from,
+            supported: FORMAT_VERSION,
+        };
+        if from > FORMAT_VERSION {
+            return Err(unsupported);
+        }
+        for version in from..FORMAT_VERSION {
+            match version {
+                // databases created before the format was versioned share the layout of version 1
+                0 => {}
+                // version 1 didn't record which changes were flushed out of the wal, so every
+                // change up to the latest timestamp is assumed to be
+                1 => {
+                    let flushed = edits.iter().rev().find_map(|edit| match edit {
+                        VersionEdit::LatestTimeStamp { ts } => Some(*ts),
+                        _ => None,
+                    });
+                    if let Some(ts) = flushed {
+                        edits.push(VersionEdit::FlushedTimeStamp { ts });
+                    }
+                }
+                _ => return Err(unsupported),
+            }
+        }
+        Ok(edits)
+    }
+
     pub(crate) async fn current(&self) -> VersionRef<R> {
         self.inner.read().await.current.clone()
     }
@@ -249,6 +303,10 @@ where
                 VersionEdit::NewLogLength { len } => {
                     new_version.log_length = len;
                 }
+                VersionEdit::FormatVersion { .. } => {}
+                VersionEdit::FlushedTimeStamp { ts } => {
+                    new_version.flushed_ts = ts;
+                }
             }
         }
         if let Some(delete_gens) = delete_gens {
@@ -405,7 +463,7 @@ pub(crate) mod tests {
             cleaner::CleanTag,
             edit::VersionEdit,
             set::{VersionSet, VersionSetInner},
-            TransactionTs, Version, VersionError,
+            TransactionTs, Version, VersionError, FORMAT_VERSION,
         },
         DbOption,
     };
@@ -562,9 +620,9 @@ pub(crate) mod tests {
             )
             .await;
 
-            assert_eq!(edits.len(), 8);
+            assert_eq!(edits.len(), 9);
             assert_eq!(
-                edits[2..],
+                edits[3..],
                 [
                     VersionEdit::Add {
                         level: 0,
@@ -622,10 +680,13 @@ pub(crate) mod tests {
             let guard = version_set.inner.write().await;
             let edits = guard.current.to_edits();
 
-            assert_eq!(edits.len(), 3);
+            assert_eq!(edits.len(), 4);
             assert_eq!(
                 edits,
                 vec![
+                    VersionEdit::FormatVersion {
+                        version: FORMAT_VERSION
+                    },
                     VersionEdit::Add {
                         level: 0,
                         scope: Scope {
@@ -723,10 +784,13 @@ pub(crate) mod tests {
         )
         .await;
 
-        assert_eq!(edits.len(), 3);
+        assert_eq!(edits.len(), 5);
         assert_eq!(
             edits,
             vec![
+                VersionEdit::FormatVersion {
+                    version: FORMAT_VERSION
+                },
                 VersionEdit::Add {
                     level: 0,
                     scope: Scope {
@@ -737,6 +801,7 @@ pub(crate) mod tests {
                     },
                 },
                 VersionEdit::LatestTimeStamp { ts: 0.into() },
+                VersionEdit::FlushedTimeStamp { ts: 0.into() },
                 VersionEdit::NewLogLength { len: 0 }
             ]
         );
@@ -755,10 +820,13 @@ pub(crate) mod tests {
         let edits =
             VersionEdit::<String>::recover(logs.pop().unwrap().path, option.base_fs.clone()).await;
 
-        assert_eq!(edits.len(), 3);
+        assert_eq!(edits.len(), 5);
         assert_eq!(
             edits,
             vec![
+                VersionEdit::FormatVersion {
+                    version: FORMAT_VERSION
+                },
                 VersionEdit::Add {
                     level: 0,
                     scope: Scope {
@@ -769,6 +837,7 @@ pub(crate) mod tests {
                     },
                 },
                 VersionEdit::LatestTimeStamp { ts: 0.into() },
+                VersionEdit::FlushedTimeStamp { ts: 0.into() },
                 VersionEdit::NewLogLength { len: 0 }
             ]
         );
@@ -917,4 +986,31 @@ pub(crate) mod tests {
             ]
         );
     }
+
+    #[test]
+    fn test_migrate_unsupported_version() {
+        assert!(VersionSet::<String>::migrate(0, vec![]).is_ok());
+        assert!(VersionSet::<String>::migrate(FORMAT_VERSION, vec![]).is_ok());
+        assert!(matches!(
+            VersionSet::<String>::migrate(FORMAT_VERSION + 1, vec![]),
+            Err(VersionError::UnsupportedFormatVersion { found, supported })
+                if found == FORMAT_VERSION + 1 && supported == FORMAT_VERSION
+        ));
+    }
+    #[test]
+    fn test_migrate_flushed_ts() {
+        let edits = VersionSet::<String>::migrate(
+            1,
+            vec![
+                VersionEdit::LatestTimeStamp { ts: 3.into() },
+                VersionEdit::LatestTimeStamp { ts: 7.into() },
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            edits.last(),
+            Some(&VersionEdit::FlushedTimeStamp { ts: 7.into() })
+        );
+        assert!(VersionSet::<String>::migrate(1, vec![]).unwrap().is_empty());
+    }
 }
@@ -1,10 +1,10 @@
 pub(crate) mod log;
 
-use std::{pin::pin, sync::Arc};
+use std::{mem, pin::pin, sync::Arc};
 
 use async_stream::stream;
 use fusio::{disk::LocalFs, DynFs};
-use fusio_log::{error::LogError, Decode, FsOptions, Logger, Options, Path};
+use fusio_log::{error::LogError, Decode, Encode, FsOptions, Logger, Options, Path};
 use futures_core::Stream;
 use futures_util::{StreamExt, TryStreamExt};
 use thiserror::Error;
@@ -18,6 +18,12 @@ where
     file: Option<Logger<Log<R>>>,
     file_id: FileId,
     path: Path,
+    /// bytes written to the active segment
+    written: usize,
+    /// bytes written to all segments
+    total_written: usize,
+    /// segments that were rotated out, oldest first
+    sealed: Vec<(FileId, Path)>,
     wal_buffer_size: usize,
     fs: Arc<dyn DynFs>,
     local_fs: Arc<dyn DynFs>,
@@ -45,14 +51,30 @@ where
             file: Some(file),
             file_id,
             path,
+            written: 0,
+            total_written: 0,
+            sealed: Vec::new(),
             wal_buffer_size,
             fs,
             local_fs,
         }
     }
 
-    pub(crate) fn file_id(&self) -> FileId {
-        self.file_id
+    /// ids of all segments of this wal, oldest first
+    pub(crate) fn file_ids(&self) -> Vec<FileId> {
+        self.sealed
+            .iter()
+            .map(|(file_id, _)| *file_id)
+            .chain(Some(self.file_id))
+            .collect()
+    }
+
+    pub(crate) fn written(&self) -> usize {
+        self.written
+    }
+
+    pub(crate) fn total_written(&self) -> usize {
+        self.total_written
     }
 }
 
@@ -70,7 +92,21 @@ where
             );
         }
 
-        self.file.as_mut().unwrap().write(data).await
+        self.file.as_mut().unwrap().write(data).await?;
+        self.written += data.size();
+        self.total_written += data.size();
+        Ok(())
+    }
+
+    /// seal the active segment and continue writing into a new one at `path`
+    pub(crate) async fn rotate(&mut self, path: Path, file_id: FileId) -> Result<(), LogError> {
+        self.flush().await?;
+
+        let sealed_path = mem::replace(&mut self.path, path);
+        let sealed_id = mem::replace(&mut self.file_id, file_id);
+        self.sealed.push((sealed_id, sealed_path));
+        self.written = 0;
+        Ok(())
     }
 
     pub(crate) async fn flush(&mut self) -> Result<(), LogError> {
@@ -104,6 +140,9 @@ where
         if let Some(mut file) = self.file.take() {
             file.close().await?;
         }
+        for (_, path) in self.sealed.iter() {
+            self.fs.remove(path).await?;
+        }
         self.fs.remove(&self.path).await?;
         Ok(())
     }
@@ -0,0 +1,111 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::{
+        alloc::{GlobalAlloc, Layout, System},
+        cell::Cell,
+        ops::Bound,
+    };
+
+    use fusio::path::Path;
+    use futures_util::StreamExt;
+    use tempfile::TempDir;
+    use tonbo::{executor::tokio::TokioExecutor, DbOption, Record, DB};
+
+    const ROWS: usize = 20_000;
+
+    /// counts the allocations made by the current thread, IO offloaded to other threads is not
+    /// part of the scan path being measured
+    struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+            System.alloc(layout)
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    fn allocations() -> usize {
+        ALLOCATIONS.with(Cell::get)
+    }
+
+    #[derive(Record, Debug)]
+    pub struct Item {
+        #[record(primary_key)]
+        pub id: u64,
+        pub name: String,
+    }
+
+    #[tokio::test]
+    async fn test_scan_ref_allocations() {
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &ItemSchema,
+        );
+        let db: DB<Item, TokioExecutor> = DB::new(option, TokioExecutor::current(), ItemSchema)
+            .await
+            .unwrap();
+        db.insert_batch((0..ROWS).map(|id| Item {
+            id: id as u64,
+            name: format!("item-{}", id),
+        }))
+        .await
+        .unwrap();
+        db.flush().await.unwrap();
+
+        let scan_ref = |upper: Bound<u64>| {
+            let db = &db;
+            async move {
+                let before = allocations();
+                let mut rows = 0;
+                let mut bytes = 0;
+                db.scan_ref((Bound::Unbounded, upper.as_ref()), |item| {
+                    rows += 1;
+                    bytes += item.name.map_or(0, str::len);
+                })
+                .await
+                .unwrap();
+                assert!(bytes > 0);
+                (rows, allocations() - before)
+            }
+        };
+
+        // the scan path without building anything per row
+        let before = allocations();
+        let (rows, bytes) = db
+            .scan((Bound::Unbounded, Bound::Unbounded), |entry| {
+                entry.get().name.map_or(0, str::len)
+            })
+            .await
+            .fold((0, 0), |(rows, bytes), len| async move {
+                (rows + 1, bytes + len.unwrap())
+            })
+            .await;
+        let scan_allocations = allocations() - before;
+        assert_eq!(rows, ROWS);
+        assert!(bytes > 0);
+
+        let (half_rows, half_allocations) = scan_ref(Bound::Excluded(ROWS as u64 / 2)).await;
+        let (rows, borrowed_allocations) = scan_ref(Bound::Unbounded).await;
+        assert_eq!(half_rows, ROWS / 2);
+        assert_eq!(rows, ROWS);
+
+        // only per batch allocations are left, no row allocates
+        assert!(scan_allocations < ROWS / 4);
+        assert!(borrowed_allocations < ROWS / 4);
+        assert!(borrowed_allocations <= scan_allocations);
+        // scanning twice the rows does not allocate more per row
+        assert!(borrowed_allocations.saturating_sub(half_allocations) < ROWS / 8);
+    }
+}
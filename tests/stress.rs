@@ -0,0 +1,190 @@
+#[cfg(all(test, feature = "tokio"))]
+mod tests {
+    use std::{collections::BTreeMap, env, ops::Bound, str::FromStr, sync::Arc};
+
+    use fusio::path::Path;
+    use futures_util::StreamExt;
+    use tempfile::TempDir;
+    use tonbo::{
+        executor::tokio::TokioExecutor, transaction::CommitError, DbOption, Projection, Record, DB,
+    };
+
+    /// keys owned by each task, tasks never touch each other's keys
+    const KEY_SPACE: u64 = 64;
+    /// key shared by all tasks, incremented through conflicting transactions
+    const COUNTER_KEY: u64 = u64::MAX;
+    /// each task flushes the memtable this often, so reads race with compactions
+    const FLUSH_INTERVAL: usize = 97;
+
+    #[derive(Record, Debug)]
+    pub struct Item {
+        #[record(primary_key)]
+        pub id: u64,
+        pub task: u64,
+        pub value: u64,
+    }
+
+    type Model = BTreeMap<u64, u64>;
+
+    /// `TONBO_STRESS_TASKS`, `TONBO_STRESS_OPS` and `TONBO_STRESS_SEED` override the defaults, a
+    /// failing run can be reproduced by running it again with the seed it printed
+    fn config<T: FromStr>(name: &str, default: T) -> T {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    async fn get(db: &DB<Item, TokioExecutor>, id: u64) -> Option<u64> {
+        db.get(&id, |entry| entry.get().value).await.unwrap()
+    }
+
+    async fn scan(db: &DB<Item, TokioExecutor>, lower: u64, upper: u64) -> Model {
+        let txn = db.transaction().await;
+        let mut stream = txn
+            .scan((Bound::Included(&lower), Bound::Excluded(&upper)))
+            .take()
+            .await
+            .unwrap();
+
+        let mut rows = Model::new();
+        while let Some(entry) = stream.next().await.transpose().unwrap() {
+            if let Some(item) = entry.value() {
+                rows.insert(item.id, item.value.unwrap());
+            }
+        }
+        rows
+    }
+
+    async fn increment(db: &DB<Item, TokioExecutor>, task: u64) {
+        loop {
+            let mut txn = db.transaction().await;
+            let value = txn
+                .get(&COUNTER_KEY, Projection::All)
+                .await
+                .unwrap()
+                .and_then(|entry| entry.get().value)
+                .unwrap_or(0);
+            txn.insert(Item {
+                id: COUNTER_KEY,
+                task,
+                value: value + 1,
+            });
+            match txn.commit().await {
+                Ok(()) => return,
+                Err(CommitError::WriteConflict(_)) => continue,
+                Err(err) => panic!("commit failed: {}", err),
+            }
+        }
+    }
+
+    /// runs `ops` random operations on the keys owned by `task`, returns the rows it expects to
+    /// be left and how many times it incremented the shared counter
+    async fn run_task(
+        db: Arc<DB<Item, TokioExecutor>>,
+        task: u64,
+        ops: usize,
+        seed: u64,
+    ) -> (Model, u64) {
+        let mut rng = fastrand::Rng::with_seed(seed.wrapping_add(task));
+        let lower = task * KEY_SPACE;
+        let upper = lower + KEY_SPACE;
+        let mut model = Model::new();
+        let mut increments = 0;
+
+        for op in 0..ops {
+            if op % FLUSH_INTERVAL == FLUSH_INTERVAL - 1 {
+                db.flush().await.unwrap();
+            }
+            let id = rng.u64(lower..upper);
+            match rng.u8(0..10) {
+                0..=3 => {
+                    let value = ((op as u64) << 16) | task;
+                    db.insert(Item { id, task, value }).await.unwrap();
+                    model.insert(id, value);
+                }
+                4 => {
+                    db.remove(id).await.unwrap();
+                    model.remove(&id);
+                }
+                5..=7 => {
+                    assert_eq!(
+                        get(&db, id).await,
+                        model.get(&id).copied(),
+                        "lost update on key {} (task {}, seed {})",
+                        id,
+                        task,
+                        seed
+                    );
+                }
+                8 => {
+                    assert_eq!(
+                        scan(&db, lower, upper).await,
+                        model,
+                        "scan of task {} disagrees with its writes (seed {})",
+                        task,
+                        seed
+                    );
+                }
+                _ => {
+                    increment(&db, task).await;
+                    increments += 1;
+                }
+            }
+        }
+        (model, increments)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_stress() {
+        let tasks: u64 = config("TONBO_STRESS_TASKS", 8);
+        let ops: usize = config("TONBO_STRESS_OPS", 500);
+        let seed: u64 = config("TONBO_STRESS_SEED", 42);
+
+        let temp_dir = TempDir::new().unwrap();
+        let option = DbOption::new(
+            Path::from_filesystem_path(temp_dir.path()).unwrap(),
+            &ItemSchema,
+        )
+        .immutable_chunk_num(1)
+        .major_threshold_with_sst_size(3);
+
+        let db: Arc<DB<Item, TokioExecutor>> = Arc::new(
+            DB::new(option, TokioExecutor::current(), ItemSchema)
+                .await
+                .unwrap(),
+        );
+
+        let handles = (0..tasks)
+            .map(|task| tokio::spawn(run_task(db.clone(), task, ops, seed)))
+            .collect::<Vec<_>>();
+
+        let mut expected = Model::new();
+        let mut increments = 0;
+        for handle in handles {
+            let (mut model, task_increments) = handle.await.unwrap();
+            expected.append(&mut model);
+            increments += task_increments;
+        }
+
+        let rows = scan(&db, 0, tasks * KEY_SPACE).await;
+        assert_eq!(rows, expected, "phantom or missing rows (seed {})", seed);
+        for (id, value) in expected {
+            assert_eq!(
+                get(&db, id).await,
+                Some(value),
+                "key {} (seed {})",
+                id,
+                seed
+            );
+        }
+
+        // a lost update leaves the counter behind the number of committed increments
+        assert_eq!(
+            get(&db, COUNTER_KEY).await.unwrap_or(0),
+            increments,
+            "lost increments (seed {})",
+            seed
+        );
+    }
+}
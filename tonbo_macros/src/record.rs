@@ -426,6 +426,7 @@ fn trait_decode_ref_codegen(
     fields: &[RecordStructFieldOpt],
 ) -> TokenStream {
     let mut ref_projection_fields: Vec<TokenStream> = Vec::new();
+    let mut ref_is_null_fields: Vec<TokenStream> = Vec::new();
 
     let mut from_record_batch_fields: Vec<TokenStream> = Vec::new();
     let mut field_names: Vec<TokenStream> = Vec::new();
@@ -461,6 +462,9 @@ fn trait_decode_ref_codegen(
                     self.#field_name = None;
                 }
             });
+            ref_is_null_fields.push(quote! {
+                #i => self.#field_name.is_none(),
+            });
 
             if is_nullable {
                 from_record_batch_fields.push(quote! {
@@ -521,6 +525,13 @@ fn trait_decode_ref_codegen(
                 #(#ref_projection_fields)*
             }
 
+            fn is_null(&self, index: usize) -> bool {
+                match index {
+                    #(#ref_is_null_fields)*
+                    _ => false,
+                }
+            }
+
             fn from_record_batch(
                 record_batch: &'r ::tonbo::arrow::record_batch::RecordBatch,
                 offset: usize,